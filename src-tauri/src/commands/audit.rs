@@ -0,0 +1,195 @@
+use crate::commands::auth::authenticate;
+use crate::commands::role::require_permission;
+use crate::db::models::audit::{AuditEntry, AuditLogFilter, AuditLogPage};
+use crate::db::models::role::MANAGE_STAFF;
+use crate::db::models::user::User;
+use crate::db::DbState;
+use rusqlite::types::ValueRef;
+use rusqlite::{params, Connection, OptionalExtension, Result};
+use serde_json::Value;
+use std::path::Path;
+
+const DEFAULT_PAGE_SIZE: i64 = 50;
+const MAX_PAGE_SIZE: i64 = 500;
+
+// Filters shared by the log query and its count; the parameters are those of filter_params
+const FILTER_CONDITION: &str =
+    "WHERE (?1 IS NULL OR user_id = ?1)
+       AND (?2 IS NULL OR action = ?2)
+       AND (?3 IS NULL OR entity_type = ?3)
+       AND (?4 IS NULL OR entity_id = ?4)
+       AND (?5 IS NULL OR date(created_at) >= date(?5))
+       AND (?6 IS NULL OR date(created_at) <= date(?6))";
+
+/// A row as JSON, column by column, for the before and after of an audit entry.
+/// Password, PIN and badge hashes are left out.
+pub fn snapshot(conn: &Connection, table: &str, id: i64) -> Result<Option<Value>, String> {
+    let mut stmt = conn.prepare(&format!("SELECT * FROM {} WHERE id = ?1", table))
+        .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+    let columns: Vec<String> = stmt.column_names().iter().map(|c| c.to_string()).collect();
+
+    stmt.query_row(params![id], |row| {
+        let mut object = serde_json::Map::new();
+        for (index, column) in columns.iter().enumerate() {
+            if column.ends_with("_hash") {
+                continue;
+            }
+            let value = match row.get_ref(index)? {
+                ValueRef::Null | ValueRef::Blob(_) => Value::Null,
+                ValueRef::Integer(value) => value.into(),
+                ValueRef::Real(value) => value.into(),
+                ValueRef::Text(value) => String::from_utf8_lossy(value).into(),
+            };
+            object.insert(column.clone(), value);
+        }
+        Ok(Value::Object(object))
+    }).optional().map_err(|e| format!("Failed to read {} {}: {}", table, id, e))
+}
+
+/// Append an entry to the audit log. Called inside the change's transaction where there is
+/// one, so the entry is only kept if the change is.
+pub fn record_audit(
+    conn: &Connection,
+    actor: Option<&User>,
+    action: &str,
+    entity_type: &str,
+    entity_id: Option<i64>,
+    before: Option<Value>,
+    after: Option<Value>,
+) -> Result<(), String> {
+    let created_at = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+
+    conn.execute(
+        "INSERT INTO audit_log (user_id, username, action, entity_type, entity_id, before, after, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            actor.map(|user| user.id),
+            actor.map(|user| user.username.as_str()),
+            action,
+            entity_type,
+            entity_id,
+            before.map(|value| value.to_string()),
+            after.map(|value| value.to_string()),
+            created_at
+        ],
+    ).map_err(|e| format!("Failed to write audit log: {}", e))?;
+
+    Ok(())
+}
+
+fn map_entry(row: &rusqlite::Row) -> Result<AuditEntry> {
+    let json = |text: Option<String>| text.and_then(|text| serde_json::from_str(&text).ok());
+
+    Ok(AuditEntry {
+        id: row.get(0)?,
+        user_id: row.get(1)?,
+        username: row.get(2)?,
+        action: row.get(3)?,
+        entity_type: row.get(4)?,
+        entity_id: row.get(5)?,
+        before: json(row.get(6)?),
+        after: json(row.get(7)?),
+        created_at: row.get(8)?,
+    })
+}
+
+fn query_audit_log(conn: &Connection, filter: &AuditLogFilter, limit: i64, offset: i64) -> Result<Vec<AuditEntry>, String> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT id, user_id, username, action, entity_type, entity_id, before, after, created_at
+         FROM audit_log {}
+         ORDER BY id DESC
+         LIMIT ?7 OFFSET ?8",
+        FILTER_CONDITION
+    )).map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+    let rows = stmt.query_map(
+        params![
+            filter.user_id,
+            filter.action,
+            filter.entity_type,
+            filter.entity_id,
+            filter.start_date,
+            filter.end_date,
+            limit,
+            offset
+        ],
+        map_entry,
+    ).map_err(|e| format!("Failed to query audit log: {}", e))?;
+
+    rows.collect::<Result<Vec<_>>>()
+        .map_err(|e| format!("Failed to collect audit log: {}", e))
+}
+
+/// Audit entries matching the filter, newest first, a page at a time
+#[tauri::command]
+pub fn get_audit_log(state: tauri::State<DbState>, token: String, filter: Option<AuditLogFilter>) -> Result<AuditLogPage, String> {
+    let conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let user = authenticate(&conn, &token)?;
+    require_permission(&conn, &user, MANAGE_STAFF, "view the audit log")?;
+
+    let filter = filter.unwrap_or_default();
+    let page = filter.page.unwrap_or(1).max(1);
+    let page_size = filter.page_size.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+
+    let total: i64 = conn.query_row(
+        &format!("SELECT COUNT(*) FROM audit_log {}", FILTER_CONDITION),
+        params![
+            filter.user_id,
+            filter.action,
+            filter.entity_type,
+            filter.entity_id,
+            filter.start_date,
+            filter.end_date
+        ],
+        |row| row.get(0),
+    ).map_err(|e| format!("Failed to count audit log: {}", e))?;
+
+    let entries = query_audit_log(&conn, &filter, page_size, (page - 1) * page_size)?;
+
+    Ok(AuditLogPage {
+        entries,
+        total,
+        page,
+        page_size,
+    })
+}
+
+/// Write every audit entry matching the filter to a CSV file, ignoring its paging
+#[tauri::command]
+pub fn export_audit_log(state: tauri::State<DbState>, token: String, filter: Option<AuditLogFilter>, path: String) -> Result<String, String> {
+    // Validate path directory exists
+    let export_path = Path::new(&path);
+    if let Some(parent) = export_path.parent() {
+        if !parent.as_os_str().is_empty() && !parent.exists() {
+            return Err("Invalid export path".into());
+        }
+    }
+
+    let conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let user = authenticate(&conn, &token)?;
+    require_permission(&conn, &user, MANAGE_STAFF, "export the audit log")?;
+    println!("Backend: {} exporting the audit log to {}", user.username, path);
+
+    let entries = query_audit_log(&conn, &filter.unwrap_or_default(), -1, 0)?;
+
+    let field = |value: String| format!("\"{}\"", value.replace('"', "\"\""));
+    let json = |value: &Option<Value>| value.as_ref().map(Value::to_string).unwrap_or_default();
+
+    let mut csv = String::from("\"Time\",\"User\",\"Action\",\"Entity\",\"Entity ID\",\"Before\",\"After\"\n");
+    for entry in &entries {
+        let row = [
+            entry.created_at.clone(),
+            entry.username.clone().unwrap_or_default(),
+            entry.action.clone(),
+            entry.entity_type.clone(),
+            entry.entity_id.map(|id| id.to_string()).unwrap_or_default(),
+            json(&entry.before),
+            json(&entry.after),
+        ];
+        csv.push_str(&row.map(field).join(","));
+        csv.push('\n');
+    }
+
+    std::fs::write(export_path, csv).map_err(|e| format!("Failed to write audit log: {}", e))?;
+    Ok(path)
+}
@@ -1,11 +1,18 @@
 use serde::{Deserialize, Serialize};
 
-use crate::db::models::user::{LoginCredentials, NewUser, User};
+use crate::db::models::audit::{CREATE, DELETE, UPDATE};
+use crate::db::models::role::{MANAGE_STAFF, SELL};
+use crate::db::models::user::{BadgeCredentials, LoginCredentials, NewUser, PasswordReset, User};
+use crate::commands::audit::{record_audit, snapshot};
+use crate::commands::role::{check_can_manage_user, check_role, require_admin, require_permission};
+use crate::commands::security::{check_password_policy, load_security_settings};
+use crate::commands::transaction::terminal_code;
 use crate::db::DbState;
 
-use bcrypt::{hash, verify, DEFAULT_COST};
+use bcrypt::{hash, hash_with_salt, verify, DEFAULT_COST};
 use jsonwebtoken::{encode, EncodingKey, Header, decode, DecodingKey, Validation};
-use rusqlite::params;
+use rand::{rngs::OsRng, Rng};
+use rusqlite::{params, OptionalExtension};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Claims {
@@ -18,6 +25,8 @@ pub struct Claims {
 pub struct AuthResponse {
     user: User,
     token: String,
+    // Set on seeded and reset accounts; nothing else works until change_password succeeds
+    must_change_password: bool,
 }
 
 #[derive(Deserialize)]
@@ -28,6 +37,39 @@ pub struct RegisterRequest {
     full_name: String,
 }
 
+const USER_COLUMNS: &str = "id, username, email, password_hash, full_name, role, created_at, updated_at";
+
+fn map_user(row: &rusqlite::Row) -> rusqlite::Result<User> {
+    Ok(User {
+        id: row.get(0)?,
+        username: row.get(1)?,
+        email: row.get(2)?,
+        password_hash: row.get(3)?,
+        full_name: row.get(4)?,
+        role: row.get(5)?,
+        created_at: row.get(6)?,
+        updated_at: row.get(7)?,
+    })
+}
+
+// Characters for temporary passwords, leaving out ones that are easy to misread
+const TEMPORARY_PASSWORD_CHARS: &str = "abcdefghjkmnpqrstuvwxyzABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+const TEMPORARY_PASSWORD_LENGTH: usize = 10;
+
+// Badge codes are hashed with a fixed salt so a scanned badge can be looked up by its hash
+const BADGE_SALT: [u8; 16] = *b"cics-badge-salt!";
+
+fn hash_badge(badge_code: &str) -> Result<String, String> {
+    let badge_code = badge_code.trim();
+    if badge_code.is_empty() {
+        return Err("Badge code is required".to_string());
+    }
+
+    hash_with_salt(badge_code, DEFAULT_COST, BADGE_SALT)
+        .map(|parts| parts.to_string())
+        .map_err(|e| format!("Failed to hash badge: {}", e))
+}
+
 // Function to create a JWT token
 fn create_token(user: &User) -> Result<String, String> {
     let expiration = std::time::SystemTime::now()
@@ -61,44 +103,105 @@ fn verify_token(token: &str) -> Result<Claims, String> {
     Ok(token_data.claims)
 }
 
+// Login failures count against the account; too many in a row lock it for a while
+fn check_lockout(conn: &rusqlite::Connection, user_id: i32) -> Result<(), String> {
+    let locked_until: Option<String> = conn.query_row(
+        "SELECT locked_until FROM users WHERE id = ?1",
+        params![user_id],
+        |row| row.get(0),
+    ).map_err(|e| format!("Database error: {}", e))?;
+
+    let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    match locked_until {
+        Some(until) if until > now => Err(format!("Too many failed logins; the account is locked until {}", until)),
+        _ => Ok(()),
+    }
+}
+
+fn record_failed_login(conn: &rusqlite::Connection, user_id: i32) -> Result<(), String> {
+    let settings = load_security_settings(conn)?;
+
+    let attempts: i32 = conn.query_row(
+        "UPDATE users SET failed_login_attempts = failed_login_attempts + 1 WHERE id = ?1
+         RETURNING failed_login_attempts",
+        params![user_id],
+        |row| row.get(0),
+    ).map_err(|e| format!("Failed to record failed login: {}", e))?;
+
+    if attempts >= settings.max_failed_logins {
+        let locked_until = (chrono::Local::now() + chrono::Duration::minutes(settings.lockout_minutes as i64))
+            .format("%Y-%m-%d %H:%M:%S")
+            .to_string();
+        println!("Backend: Locking user {} until {} after {} failed logins", user_id, locked_until, attempts);
+
+        conn.execute(
+            "UPDATE users SET failed_login_attempts = 0, locked_until = ?1 WHERE id = ?2",
+            params![locked_until, user_id],
+        ).map_err(|e| format!("Failed to lock account: {}", e))?;
+    }
+    Ok(())
+}
+
+// Deactivated accounts keep their history but can't sign in or use an old session
+fn check_active(conn: &rusqlite::Connection, user_id: i32) -> Result<(), String> {
+    let active: bool = conn.query_row(
+        "SELECT active FROM users WHERE id = ?1",
+        params![user_id],
+        |row| row.get(0),
+    ).map_err(|e| format!("Database error: {}", e))?;
+
+    if !active {
+        return Err("This account has been deactivated".to_string());
+    }
+    Ok(())
+}
+
+// Clear the failure count and issue the session
+fn start_session(conn: &rusqlite::Connection, user: User) -> Result<AuthResponse, String> {
+    check_active(conn, user.id)?;
+
+    let must_change_password: bool = conn.query_row(
+        "UPDATE users SET failed_login_attempts = 0, locked_until = NULL WHERE id = ?1
+         RETURNING must_change_password",
+        params![user.id],
+        |row| row.get(0),
+    ).map_err(|e| format!("Failed to record login: {}", e))?;
+
+    let token = create_token(&user)?;
+
+    Ok(AuthResponse {
+        user,
+        token,
+        must_change_password,
+    })
+}
+
 #[tauri::command]
 pub fn login(state: tauri::State<DbState>, creds: LoginCredentials) -> Result<AuthResponse, String> {
     let conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
 
     let mut stmt = conn
-        .prepare("SELECT id, username, email, password_hash, full_name, role, created_at, updated_at FROM users WHERE username = ?1")
+        .prepare(&format!("SELECT {} FROM users WHERE username = ?1", USER_COLUMNS))
         .map_err(|e| format!("Database error: {}", e))?;
         
-    let user = stmt.query_row(params![creds.username], |row| {
-        Ok(User {
-            id: row.get(0)?,
-            username: row.get(1)?,
-            email: row.get(2)?,
-            password_hash: row.get(3)?,
-            full_name: row.get(4)?,
-            role: row.get(5)?,
-            created_at: row.get(6)?,
-            updated_at: row.get(7)?,
-        })
-    }).map_err(|_| "User not found")?;
+    // Don't tell an unknown username apart from a wrong password
+    let user = stmt.query_row(params![creds.username], map_user).map_err(|_| "Invalid username or password")?;
+    check_lockout(&conn, user.id)?;
 
     // Verify that the provided plaintext password matches the stored hash
     if !verify(&creds.password, &user.password_hash).map_err(|_| "Password verification error")? {
-        return Err("Invalid password".to_string());
+        record_failed_login(&conn, user.id)?;
+        return Err("Invalid username or password".to_string());
     }
 
-    // Create a token for the user
-    let token = create_token(&user)?;
-    
-    // Return the AuthResponse with user and token
-    Ok(AuthResponse {
-        user,
-        token,
-    })
+    take_over_terminal(&conn, creds.terminal.as_deref(), &user)?;
+    start_session(&conn, user)
 }
 
+/// Create a staff account. Only admins can register others, so nobody can sign themselves up
+/// with a role of their choosing.
 #[tauri::command]
-pub fn register(state: tauri::State<DbState>, new_user: NewUser) -> Result<User, String> {
+pub fn register(state: tauri::State<DbState>, token: String, new_user: NewUser) -> Result<User, String> {
     if new_user.username.is_empty()
         || new_user.email.is_empty()
         || new_user.password.is_empty()
@@ -107,6 +210,12 @@ pub fn register(state: tauri::State<DbState>, new_user: NewUser) -> Result<User,
     }
 
     let conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let admin = authenticate(&conn, &token)?;
+    require_admin(&admin, "register users")?;
+
+    // Accounts get the plain user role unless another one is picked
+    let role = if new_user.role.trim().is_empty() { "user".to_string() } else { check_role(&conn, &new_user.role)? };
+    check_password_policy(&conn, &new_user.password)?;
 
     let password_hash = hash(&new_user.password, DEFAULT_COST)
         .map_err(|e| format!("Failed to hash password: {}", e))?;
@@ -117,7 +226,7 @@ pub fn register(state: tauri::State<DbState>, new_user: NewUser) -> Result<User,
         email: new_user.email,
         password: password_hash,
         full_name: new_user.full_name,
-        role: "user".to_string(), // Default role
+        role,
     };
 
     conn.execute(
@@ -135,27 +244,92 @@ pub fn register(state: tauri::State<DbState>, new_user: NewUser) -> Result<User,
     let last_id = conn.last_insert_rowid();
 
     let mut stmt = conn
-        .prepare("SELECT id, username, email, password_hash, full_name, role, created_at, updated_at FROM users WHERE id = ?1")
+        .prepare(&format!("SELECT {} FROM users WHERE id = ?1", USER_COLUMNS))
         .map_err(|e| e.to_string())?;
 
     let user = stmt
-        .query_row(params![last_id], |row| {
-            Ok(User {
-                id: row.get(0)?,
-                username: row.get(1)?,
-                email: row.get(2)?,
-                password_hash: row.get(3)?,
-                full_name: row.get(4)?,
-                role: row.get(5)?,
-                created_at: row.get(6)?,
-                updated_at: row.get(7)?,
-            })
-        })
+        .query_row(params![last_id], map_user)
         .map_err(|_| "Could not retrieve created user")?;
 
+    record_audit(&conn, Some(&admin), CREATE, "user", Some(last_id), None, snapshot(&conn, "users", last_id)?)?;
     Ok(user)
 }
 
+// Resolve the user behind a session token, re-reading the row so changes made since login apply
+fn session_user(conn: &rusqlite::Connection, token: &str) -> Result<User, String> {
+    let claims = verify_token(token).map_err(|_| "Invalid or expired session".to_string())?;
+
+    let user = conn.query_row(
+        &format!("SELECT {} FROM users WHERE id = ?1", USER_COLUMNS),
+        params![claims.user.id],
+        map_user,
+    )
+    .map_err(|_| "User not found".to_string())?;
+
+    check_active(conn, user.id)?;
+    Ok(user)
+}
+
+/// The user behind a session token, refused while their password has to be changed
+pub fn authenticate(conn: &rusqlite::Connection, token: &str) -> Result<User, String> {
+    let user = session_user(conn, token)?;
+
+    let must_change_password: bool = conn.query_row(
+        "SELECT must_change_password FROM users WHERE id = ?1",
+        params![user.id],
+        |row| row.get(0),
+    ).map_err(|e| format!("Database error: {}", e))?;
+
+    if must_change_password {
+        return Err("You must change your password before continuing".to_string());
+    }
+    Ok(user)
+}
+
+/// Change the signed-in user's password. Also clears a forced password change; returns a
+/// fresh session.
+#[tauri::command]
+pub fn change_password(
+    state: tauri::State<DbState>,
+    token: String,
+    current_password: String,
+    new_password: String,
+) -> Result<AuthResponse, String> {
+    let conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let user = session_user(&conn, &token)?;
+    println!("Backend: Changing password for {}", user.username);
+
+    check_lockout(&conn, user.id)?;
+    if !verify(&current_password, &user.password_hash).map_err(|_| "Password verification error")? {
+        record_failed_login(&conn, user.id)?;
+        return Err("Current password is incorrect".to_string());
+    }
+    if current_password == new_password {
+        return Err("The new password must be different from the current one".to_string());
+    }
+    check_password_policy(&conn, &new_password)?;
+
+    let password_hash = hash(&new_password, DEFAULT_COST)
+        .map_err(|e| format!("Failed to hash password: {}", e))?;
+    let before = snapshot(&conn, "users", user.id as i64)?;
+
+    conn.execute(
+        "UPDATE users SET password_hash = ?1, must_change_password = 0, password_changed_at = datetime('now'),
+                updated_at = datetime('now')
+         WHERE id = ?2",
+        params![password_hash, user.id],
+    ).map_err(|e| format!("Failed to change password: {}", e))?;
+    record_audit(&conn, Some(&user), UPDATE, "user", Some(user.id as i64), before, snapshot(&conn, "users", user.id as i64)?)?;
+
+    let user = conn.query_row(
+        &format!("SELECT {} FROM users WHERE id = ?1", USER_COLUMNS),
+        params![user.id],
+        map_user,
+    ).map_err(|_| "User not found".to_string())?;
+
+    start_session(&conn, user)
+}
+
 #[tauri::command]
 pub fn verify_auth(token: &str) -> Result<bool, String> {
     // Verify the token and return true if valid, false otherwise
@@ -163,4 +337,320 @@ pub fn verify_auth(token: &str) -> Result<bool, String> {
         Ok(_) => Ok(true),
         Err(_) => Ok(false),
     }
-} 
\ No newline at end of file
+}
+
+// The user a badge belongs to, checking the PIN if one was set with it
+fn badge_user(conn: &rusqlite::Connection, creds: &BadgeCredentials) -> Result<User, String> {
+    let badge_hash = hash_badge(&creds.badge_code)?;
+
+    let found: Option<(User, Option<String>)> = conn.query_row(
+        &format!("SELECT {}, badge_pin_hash FROM users WHERE badge_hash = ?1", USER_COLUMNS),
+        params![badge_hash],
+        |row| Ok((map_user(row)?, row.get(8)?)),
+    ).optional()
+        .map_err(|e| format!("Database error: {}", e))?;
+
+    let (user, pin_hash) = found.ok_or_else(|| "Unknown badge".to_string())?;
+    check_lockout(conn, user.id)?;
+
+    if let Some(pin_hash) = pin_hash {
+        let pin = creds.pin.as_deref().ok_or_else(|| "PIN required".to_string())?;
+        if !verify(pin, &pin_hash).map_err(|_| "PIN verification error")? {
+            record_failed_login(conn, user.id)?;
+            return Err("Invalid PIN".to_string());
+        }
+    }
+
+    Ok(user)
+}
+
+/// Give a user a badge, optionally with a PIN that must be entered after scanning it; no
+/// badge removes it. Admins can set anyone's badge, other users only their own.
+#[tauri::command]
+pub fn set_user_badge(
+    state: tauri::State<DbState>,
+    token: String,
+    user_id: i32,
+    badge_code: Option<String>,
+    pin: Option<String>,
+) -> Result<(), String> {
+    let conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let user = authenticate(&conn, &token)?;
+    println!("Backend: {} setting the badge of user {}", user.username, user_id);
+
+    if user.id != user_id {
+        require_permission(&conn, &user, MANAGE_STAFF, "set another user's badge")?;
+        check_can_manage_user(&conn, &user, user_id, "set an admin's badge")?;
+    }
+
+    let badge_hash = match badge_code.as_deref().map(str::trim).filter(|b| !b.is_empty()) {
+        Some(badge_code) => Some(hash_badge(badge_code)?),
+        None => None,
+    };
+    let pin_hash = match (&badge_hash, pin.as_deref().filter(|p| !p.is_empty())) {
+        (Some(_), Some(pin)) => {
+            if pin.len() < 4 || !pin.chars().all(|c| c.is_ascii_digit()) {
+                return Err("PIN must be at least 4 digits".to_string());
+            }
+            Some(hash(pin, DEFAULT_COST).map_err(|e| format!("Failed to hash PIN: {}", e))?)
+        }
+        _ => None,
+    };
+
+    if let Some(badge_hash) = &badge_hash {
+        let holder: Option<String> = conn.query_row(
+            "SELECT username FROM users WHERE badge_hash = ?1 AND id != ?2",
+            params![badge_hash, user_id],
+            |row| row.get(0),
+        ).optional()
+            .map_err(|e| format!("Database error: {}", e))?;
+
+        if let Some(holder) = holder {
+            return Err(format!("That badge is already assigned to {}", holder));
+        }
+    }
+
+    let before = snapshot(&conn, "users", user_id as i64)?;
+    let updated = conn.execute(
+        "UPDATE users SET badge_hash = ?1, badge_pin_hash = ?2, updated_at = datetime('now') WHERE id = ?3",
+        params![badge_hash, pin_hash, user_id],
+    ).map_err(|e| format!("Failed to set badge: {}", e))?;
+
+    if updated == 0 {
+        return Err("User not found".to_string());
+    }
+    record_audit(&conn, Some(&user), UPDATE, "user", Some(user_id as i64), before, snapshot(&conn, "users", user_id as i64)?)?;
+    Ok(())
+}
+
+/// Log in by scanning a badge instead of typing a username and password
+#[tauri::command]
+pub fn login_with_badge(state: tauri::State<DbState>, creds: BadgeCredentials) -> Result<AuthResponse, String> {
+    let conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+
+    let user = badge_user(&conn, &creds)?;
+    take_over_terminal(&conn, creds.terminal.as_deref(), &user)?;
+    start_session(&conn, user)
+}
+
+/// Hand a terminal over to the cashier whose badge was scanned. Orders rung up on the
+/// terminal from now on are attributed to them, whatever cashier the register sends.
+#[tauri::command]
+pub fn switch_cashier(state: tauri::State<DbState>, terminal: Option<String>, creds: BadgeCredentials) -> Result<AuthResponse, String> {
+    let conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+
+    let user = badge_user(&conn, &creds)?;
+    let terminal = terminal_code(terminal.as_deref());
+    println!("Backend: Switching terminal {} to {}", terminal, user.username);
+
+    let before = terminal_snapshot(&conn, &terminal)?;
+    conn.execute(
+        "INSERT INTO terminal_cashiers (terminal, user_id, switched_at) VALUES (?1, ?2, ?3)
+         ON CONFLICT(terminal) DO UPDATE SET user_id = excluded.user_id, switched_at = excluded.switched_at",
+        params![terminal, user.id, chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string()],
+    ).map_err(|e| format!("Failed to switch cashier: {}", e))?;
+    record_audit(&conn, Some(&user), UPDATE, "terminal_cashier", None, before, terminal_snapshot(&conn, &terminal)?)?;
+
+    start_session(&conn, user)
+}
+
+/// End a session. A cashier switched in by badge on the terminal is switched out again, so
+/// the terminal's orders are no longer rung up under them.
+#[tauri::command]
+pub fn logout(state: tauri::State<DbState>, token: String, terminal: Option<String>) -> Result<(), String> {
+    let conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let user = session_user(&conn, &token)?;
+    println!("Backend: Logging out {}", user.username);
+
+    if let Some(terminal) = terminal.as_deref().map(|t| terminal_code(Some(t))) {
+        if terminal_user_id(&conn, &terminal)? == Some(user.id) {
+            clear_terminal(&conn, &user, &terminal)?;
+        }
+    }
+    Ok(())
+}
+
+/// Switch out whoever was switched in by badge on a terminal, e.g. after they left without logging out
+#[tauri::command]
+pub fn clear_terminal_cashier(state: tauri::State<DbState>, token: String, terminal: Option<String>) -> Result<(), String> {
+    let conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let user = authenticate(&conn, &token)?;
+    require_permission(&conn, &user, SELL, "clear the terminal cashier")?;
+
+    let terminal = terminal_code(terminal.as_deref());
+    println!("Backend: {} clearing the cashier on terminal {}", user.username, terminal);
+    clear_terminal(&conn, &user, &terminal)
+}
+
+/// Name of the cashier switched in on a terminal, which orders naming the terminal are rung
+/// up under. Orders that don't name a terminal keep their own cashier.
+pub fn terminal_cashier(conn: &rusqlite::Connection, terminal: Option<&str>) -> Result<Option<String>, String> {
+    let terminal = match terminal {
+        Some(terminal) => terminal_code(Some(terminal)),
+        None => return Ok(None),
+    };
+
+    conn.query_row(
+        "SELECT u.full_name FROM terminal_cashiers t JOIN users u ON u.id = t.user_id WHERE t.terminal = ?1",
+        params![terminal],
+        |row| row.get(0),
+    ).optional()
+        .map_err(|e| format!("Failed to get terminal cashier: {}", e))
+}
+
+fn terminal_user_id(conn: &rusqlite::Connection, terminal: &str) -> Result<Option<i32>, String> {
+    conn.query_row(
+        "SELECT user_id FROM terminal_cashiers WHERE terminal = ?1",
+        params![terminal],
+        |row| row.get(0),
+    ).optional()
+        .map_err(|e| format!("Failed to get terminal cashier: {}", e))
+}
+
+// Who is switched in on a terminal, as recorded in the audit log
+fn terminal_snapshot(conn: &rusqlite::Connection, terminal: &str) -> Result<Option<serde_json::Value>, String> {
+    conn.query_row(
+        "SELECT user_id, switched_at FROM terminal_cashiers WHERE terminal = ?1",
+        params![terminal],
+        |row| Ok(serde_json::json!({
+            "terminal": terminal,
+            "user_id": row.get::<_, i32>(0)?,
+            "switched_at": row.get::<_, String>(1)?,
+        })),
+    ).optional()
+        .map_err(|e| format!("Failed to get terminal cashier: {}", e))
+}
+
+fn clear_terminal(conn: &rusqlite::Connection, actor: &User, terminal: &str) -> Result<(), String> {
+    let before = terminal_snapshot(conn, terminal)?;
+    if before.is_none() {
+        return Ok(());
+    }
+
+    conn.execute("DELETE FROM terminal_cashiers WHERE terminal = ?1", params![terminal])
+        .map_err(|e| format!("Failed to clear terminal cashier: {}", e))?;
+    record_audit(conn, Some(actor), DELETE, "terminal_cashier", None, before, None)
+}
+
+// Someone else signing in on a terminal takes it back from the cashier switched in there
+fn take_over_terminal(conn: &rusqlite::Connection, terminal: Option<&str>, user: &User) -> Result<(), String> {
+    let terminal = match terminal {
+        Some(terminal) => terminal_code(Some(terminal)),
+        None => return Ok(()),
+    };
+
+    match terminal_user_id(conn, &terminal)? {
+        Some(user_id) if user_id != user.id => clear_terminal(conn, user, &terminal),
+        _ => Ok(()),
+    }
+}
+
+// Random characters for a one-off password, drawn from the operating system's generator
+fn temporary_password() -> String {
+    let charset = TEMPORARY_PASSWORD_CHARS.as_bytes();
+    (0..TEMPORARY_PASSWORD_LENGTH)
+        .map(|_| charset[OsRng.gen_range(0..charset.len())] as char)
+        .collect()
+}
+
+/// Give a user a temporary password they have to change at their next login. Also lifts a
+/// lockout.
+#[tauri::command]
+pub fn reset_password(state: tauri::State<DbState>, token: String, user_id: i32) -> Result<PasswordReset, String> {
+    let conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let admin = authenticate(&conn, &token)?;
+    println!("Backend: {} resetting the password of user {}", admin.username, user_id);
+    require_permission(&conn, &admin, MANAGE_STAFF, "reset passwords")?;
+    check_can_manage_user(&conn, &admin, user_id, "reset an admin's password")?;
+
+    let temporary_password = temporary_password();
+    let password_hash = hash(&temporary_password, DEFAULT_COST)
+        .map_err(|e| format!("Failed to hash password: {}", e))?;
+
+    let before = snapshot(&conn, "users", user_id as i64)?;
+    let username: String = conn.query_row(
+        "UPDATE users SET password_hash = ?1, must_change_password = 1, failed_login_attempts = 0, locked_until = NULL,
+                password_changed_at = datetime('now'), updated_at = datetime('now')
+         WHERE id = ?2
+         RETURNING username",
+        params![password_hash, user_id],
+        |row| row.get(0),
+    ).optional()
+        .map_err(|e| format!("Failed to reset password: {}", e))?
+        .ok_or_else(|| "User not found".to_string())?;
+    record_audit(&conn, Some(&admin), UPDATE, "user", Some(user_id as i64), before, snapshot(&conn, "users", user_id as i64)?)?;
+
+    Ok(PasswordReset {
+        user_id,
+        username,
+        temporary_password,
+    })
+}
+
+/// Stop a user from signing in. Their orders and shifts keep referring to them; their badge
+/// stops working and any terminal they were switched in on goes back to the register's cashier.
+#[tauri::command]
+pub fn deactivate_user(state: tauri::State<DbState>, token: String, user_id: i32) -> Result<(), String> {
+    let mut conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let admin = authenticate(&conn, &token)?;
+    println!("Backend: {} deactivating user {}", admin.username, user_id);
+    require_permission(&conn, &admin, MANAGE_STAFF, "deactivate users")?;
+    check_can_manage_user(&conn, &admin, user_id, "deactivate an admin")?;
+
+    if admin.id == user_id {
+        return Err("You cannot deactivate your own account".to_string());
+    }
+
+    let tx = conn.transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let before = snapshot(&tx, "users", user_id as i64)?;
+
+    let updated = tx.execute(
+        "UPDATE users SET active = 0, deactivated_at = datetime('now'), updated_at = datetime('now')
+         WHERE id = ?1 AND active = 1",
+        params![user_id],
+    ).map_err(|e| format!("Failed to deactivate user: {}", e))?;
+
+    if updated == 0 {
+        return Err("User not found or already deactivated".to_string());
+    }
+
+    let admins_left: i64 = tx.query_row(
+        "SELECT COUNT(*) FROM users WHERE role = 'admin' AND active = 1",
+        [],
+        |row| row.get(0),
+    ).map_err(|e| format!("Database error: {}", e))?;
+
+    if admins_left == 0 {
+        return Err("Cannot deactivate the last active admin".to_string());
+    }
+
+    tx.execute("DELETE FROM terminal_cashiers WHERE user_id = ?1", params![user_id])
+        .map_err(|e| format!("Failed to sign user out of terminals: {}", e))?;
+    record_audit(&tx, Some(&admin), UPDATE, "user", Some(user_id as i64), before, snapshot(&tx, "users", user_id as i64)?)?;
+
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn reactivate_user(state: tauri::State<DbState>, token: String, user_id: i32) -> Result<(), String> {
+    let conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let admin = authenticate(&conn, &token)?;
+    println!("Backend: {} reactivating user {}", admin.username, user_id);
+    require_permission(&conn, &admin, MANAGE_STAFF, "reactivate users")?;
+    check_can_manage_user(&conn, &admin, user_id, "reactivate an admin")?;
+
+    let before = snapshot(&conn, "users", user_id as i64)?;
+    let updated = conn.execute(
+        "UPDATE users SET active = 1, deactivated_at = NULL, failed_login_attempts = 0, locked_until = NULL,
+                updated_at = datetime('now')
+         WHERE id = ?1 AND active = 0",
+        params![user_id],
+    ).map_err(|e| format!("Failed to reactivate user: {}", e))?;
+
+    if updated == 0 {
+        return Err("User not found or already active".to_string());
+    }
+    record_audit(&conn, Some(&admin), UPDATE, "user", Some(user_id as i64), before, snapshot(&conn, "users", user_id as i64)?)?;
+    Ok(())
+}
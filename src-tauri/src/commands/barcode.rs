@@ -0,0 +1,239 @@
+use crate::commands::audit::{record_audit, snapshot};
+use crate::commands::auth::authenticate;
+use crate::commands::price::apply_due_price_changes;
+use crate::commands::product::load_product_with_category;
+use crate::commands::role::require_permission;
+use crate::commands::variant::get_variant;
+use crate::db::models::audit::{CREATE, DELETE};
+use crate::db::models::barcode::{BarcodeConflict, CodeMatch, NewProductBarcode, ProductBarcode};
+use crate::db::models::role::EDIT_INVENTORY;
+use crate::db::DbState;
+use rusqlite::{params, Connection, Result};
+
+// Every place a scannable code can live. Each branch hits a unique index (the barcode
+// branches repeat their partial index's condition so SQLite can use it), and the
+// priority puts a product's own barcode ahead of SKUs when legacy data still overlaps.
+const CODE_LOOKUP: &str =
+    "SELECT source_table, row_id, product_id, variant_id, matched_on FROM (
+        SELECT 'products' AS source_table, id AS row_id, id AS product_id, NULL AS variant_id,
+               'barcode' AS matched_on, 1 AS priority
+        FROM products WHERE barcode = ?1 AND barcode != ''
+        UNION ALL
+        SELECT 'product_barcodes', id, product_id, variant_id, 'alternate', 2
+        FROM product_barcodes WHERE code = ?1
+        UNION ALL
+        SELECT 'product_variants', id, product_id, id, 'variant_barcode', 3
+        FROM product_variants WHERE barcode = ?1 AND barcode != ''
+        UNION ALL
+        SELECT 'products', id, id, NULL, 'sku', 4
+        FROM products WHERE sku = ?1
+        UNION ALL
+        SELECT 'product_variants', id, product_id, id, 'variant_sku', 5
+        FROM product_variants WHERE sku = ?1
+     ) ORDER BY priority";
+
+struct CodeRow {
+    source_table: String,
+    row_id: i32,
+    product_id: i32,
+    variant_id: Option<i32>,
+    matched_on: String,
+}
+
+fn lookup_code(conn: &Connection, code: &str) -> Result<Vec<CodeRow>, String> {
+    let mut stmt = conn.prepare(CODE_LOOKUP)
+        .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+    let rows = stmt.query_map(params![code], |row| {
+        Ok(CodeRow {
+            source_table: row.get(0)?,
+            row_id: row.get(1)?,
+            product_id: row.get(2)?,
+            variant_id: row.get(3)?,
+            matched_on: row.get(4)?,
+        })
+    }).map_err(|e| format!("Failed to look up code: {}", e))?;
+
+    rows.collect::<Result<Vec<_>>>()
+        .map_err(|e| format!("Failed to collect code matches: {}", e))
+}
+
+/// Fail if `code` is already used as a barcode or SKU anywhere in the catalogue.
+///
+/// `owner` is the `(table, id)` row the code is being saved on, so re-saving a row with
+/// its own code is allowed. Empty codes are ignored.
+pub fn ensure_code_available(conn: &Connection, code: &str, owner: Option<(&str, i32)>) -> Result<(), String> {
+    let code = code.trim();
+    if code.is_empty() {
+        return Ok(());
+    }
+
+    let clash = lookup_code(conn, code)?
+        .into_iter()
+        .find(|row| owner != Some((row.source_table.as_str(), row.row_id)));
+
+    if let Some(row) = clash {
+        let name: String = conn.query_row(
+            "SELECT name FROM products WHERE id = ?1",
+            params![row.product_id],
+            |row| row.get(0),
+        ).map_err(|e| format!("Failed to get product {}: {}", row.product_id, e))?;
+
+        return Err(format!("Code {} is already used by {} ({})", code, name, row.matched_on));
+    }
+
+    Ok(())
+}
+
+fn map_barcode(row: &rusqlite::Row) -> Result<ProductBarcode> {
+    Ok(ProductBarcode {
+        id: row.get(0)?,
+        product_id: row.get(1)?,
+        variant_id: row.get(2)?,
+        code: row.get(3)?,
+        label: row.get(4)?,
+        created_at: row.get(5)?,
+    })
+}
+
+#[tauri::command]
+pub fn find_product_by_code(state: tauri::State<DbState>, code: String) -> Result<Option<CodeMatch>, String> {
+    let code = code.trim();
+    println!("Backend: Looking up product by code {:?}", code);
+
+    if code.is_empty() {
+        return Ok(None);
+    }
+
+    let mut conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    apply_due_price_changes(&mut conn)?;
+
+    let row = match lookup_code(&conn, code)?.into_iter().next() {
+        Some(row) => row,
+        None => return Ok(None),
+    };
+
+    let product = load_product_with_category(&conn, row.product_id)?;
+    // Archived products are off the register; their codes stay reserved for a restore
+    if product.archived_at.is_some() {
+        return Ok(None);
+    }
+    let variant = match row.variant_id {
+        Some(variant_id) => Some(get_variant(&conn, variant_id)?),
+        None => None,
+    };
+
+    Ok(Some(CodeMatch {
+        code: code.to_string(),
+        matched_on: row.matched_on,
+        product,
+        variant,
+    }))
+}
+
+#[tauri::command]
+pub fn get_product_barcodes(state: tauri::State<DbState>, product_id: i32) -> Result<Vec<ProductBarcode>, String> {
+    let conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+
+    let mut stmt = conn.prepare(
+        "SELECT id, product_id, variant_id, code, label, created_at
+         FROM product_barcodes WHERE product_id = ?1 ORDER BY id"
+    ).map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+    let rows = stmt.query_map(params![product_id], map_barcode)
+        .map_err(|e| format!("Failed to query product barcodes: {}", e))?;
+
+    rows.collect::<Result<Vec<_>>>()
+        .map_err(|e| format!("Failed to collect product barcodes: {}", e))
+}
+
+#[tauri::command]
+pub fn add_product_barcode(state: tauri::State<DbState>, barcode: NewProductBarcode, token: String) -> Result<ProductBarcode, String> {
+    println!("Backend: Adding barcode {:?} to product {}", barcode.code, barcode.product_id);
+
+    let code = barcode.code.trim();
+    if code.is_empty() {
+        return Err("Barcode is required".to_string());
+    }
+
+    let mut conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let actor = authenticate(&conn, &token)?;
+    require_permission(&conn, &actor, EDIT_INVENTORY, "add barcodes")?;
+    let tx = conn.transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    load_product_with_category(&tx, barcode.product_id)?;
+    if let Some(variant_id) = barcode.variant_id {
+        if get_variant(&tx, variant_id)?.product_id != barcode.product_id {
+            return Err("Variant does not belong to this product".to_string());
+        }
+    }
+
+    ensure_code_available(&tx, code, None)?;
+
+    tx.execute(
+        "INSERT INTO product_barcodes (product_id, variant_id, code, label) VALUES (?1, ?2, ?3, ?4)",
+        params![barcode.product_id, barcode.variant_id, code, barcode.label],
+    ).map_err(|e| format!("Failed to insert product barcode: {}", e))?;
+
+    let id = tx.last_insert_rowid();
+    let after = snapshot(&tx, "product_barcodes", id)?;
+    record_audit(&tx, Some(&actor), CREATE, "product_barcode", Some(id), None, after)?;
+
+    let created = tx.query_row(
+        "SELECT id, product_id, variant_id, code, label, created_at FROM product_barcodes WHERE id = ?1",
+        params![id],
+        map_barcode,
+    ).map_err(|e| format!("Failed to get product barcode: {}", e))?;
+
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    Ok(created)
+}
+
+#[tauri::command]
+pub fn delete_product_barcode(state: tauri::State<DbState>, id: i32, token: String) -> Result<(), String> {
+    println!("Backend: Deleting product barcode {}", id);
+    let mut conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let actor = authenticate(&conn, &token)?;
+    require_permission(&conn, &actor, EDIT_INVENTORY, "delete barcodes")?;
+    let tx = conn.transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let before = snapshot(&tx, "product_barcodes", id as i64)?;
+
+    let deleted = tx.execute("DELETE FROM product_barcodes WHERE id = ?1", params![id])
+        .map_err(|e| format!("Failed to delete product barcode: {}", e))?;
+
+    if deleted == 0 {
+        return Err("Barcode not found".to_string());
+    }
+
+    record_audit(&tx, Some(&actor), DELETE, "product_barcode", Some(id as i64), before, None)?;
+
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    Ok(())
+}
+
+// Duplicates found when the unique barcode indexes were added, for the user to review
+#[tauri::command]
+pub fn get_barcode_conflicts(state: tauri::State<DbState>) -> Result<Vec<BarcodeConflict>, String> {
+    let conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+
+    let mut stmt = conn.prepare(
+        "SELECT id, code, source_table, row_id, kept_row_id, resolution, created_at
+         FROM barcode_conflicts ORDER BY code, id"
+    ).map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+    let rows = stmt.query_map([], |row| {
+        Ok(BarcodeConflict {
+            id: row.get(0)?,
+            code: row.get(1)?,
+            source_table: row.get(2)?,
+            row_id: row.get(3)?,
+            kept_row_id: row.get(4)?,
+            resolution: row.get(5)?,
+            created_at: row.get(6)?,
+        })
+    }).map_err(|e| format!("Failed to query barcode conflicts: {}", e))?;
+
+    rows.collect::<Result<Vec<_>>>()
+        .map_err(|e| format!("Failed to collect barcode conflicts: {}", e))
+}
+
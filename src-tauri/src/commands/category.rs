@@ -1,6 +1,39 @@
-use crate::db::models::category::{Category, NewCategory, UpdateCategory};
+use crate::commands::audit::{record_audit, snapshot};
+use crate::commands::auth::authenticate;
+use crate::commands::notification::{emit_stock_alerts, evaluate_stock_alerts};
+use crate::commands::product::archive_timestamp;
+use crate::commands::role::require_permission;
+use crate::db::models::audit::{CREATE, DELETE, UPDATE};
+use crate::db::models::category::{
+    AffectedProduct, Category, CategoryDeletionPreview, CategoryNode, CategoryRepair, NewCategory, UpdateCategory,
+    ARCHIVE, MOVE, REFUSE, UNCATEGORIZED,
+};
+use crate::db::models::role::EDIT_INVENTORY;
+use crate::db::models::user::User;
 use crate::db::DbState;
-use rusqlite::{params, Result};
+use rusqlite::{params, Connection, OptionalExtension, Result, ToSql};
+use std::collections::HashMap;
+
+const CATEGORY_COLUMNS: &str = "id, name, description, created_at, updated_at, icon, archived_at, parent_id";
+
+/// Subquery for the ids of a category (bound as ?1) and every category below it. UNION rather
+/// than UNION ALL so a cycle in old data can't recurse forever.
+pub const SUBTREE_IDS: &str = "(WITH RECURSIVE subtree(id) AS (
+        SELECT ?1 UNION SELECT c.id FROM categories c JOIN subtree s ON c.parent_id = s.id
+    ) SELECT id FROM subtree)";
+
+fn map_category(row: &rusqlite::Row) -> Result<Category> {
+    Ok(Category {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        description: row.get(2)?,
+        created_at: row.get(3)?,
+        updated_at: row.get(4)?,
+        icon: row.get(5)?,
+        archived_at: row.get(6)?,
+        parent_id: row.get(7)?,
+    })
+}
 
 #[tauri::command]
 pub fn get_all_categories(state: tauri::State<DbState>) -> Result<Vec<Category>, String> {
@@ -8,22 +41,13 @@ pub fn get_all_categories(state: tauri::State<DbState>) -> Result<Vec<Category>,
     let conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
     
     let mut stmt = conn.prepare(
-        "SELECT id, name, description, created_at, updated_at, icon FROM categories ORDER BY name"
+        &format!("SELECT {} FROM categories WHERE archived_at IS NULL ORDER BY name", CATEGORY_COLUMNS)
     ).map_err(|e| {
         println!("Backend error preparing get_all_categories: {}", e);
         e.to_string()
     })?;
 
-    let categories = stmt.query_map([], |row| {
-        Ok(Category {
-            id: row.get(0)?,
-            name: row.get(1)?,
-            description: row.get(2)?,
-            created_at: row.get(3)?,
-            updated_at: row.get(4)?,
-            icon: row.get(5)?,
-        })
-    }).map_err(|e| {
+    let categories = stmt.query_map([], map_category).map_err(|e| {
         println!("Backend error querying categories: {}", e);
         e.to_string()
     })?;
@@ -37,8 +61,112 @@ pub fn get_all_categories(state: tauri::State<DbState>) -> Result<Vec<Category>,
     Ok(result)
 }
 
+/// Every active category, nested under its parent
 #[tauri::command]
-pub fn update_category(state: tauri::State<DbState>, category: UpdateCategory) -> Result<Category, String> {
+pub fn get_category_tree(state: tauri::State<DbState>) -> Result<Vec<CategoryNode>, String> {
+    let conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+
+    let categories: Vec<Category> = {
+        let mut stmt = conn.prepare(
+            &format!("SELECT {} FROM categories WHERE archived_at IS NULL ORDER BY name", CATEGORY_COLUMNS)
+        ).map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let rows = stmt.query_map([], map_category)
+            .map_err(|e| format!("Failed to query categories: {}", e))?;
+
+        rows.collect::<Result<Vec<_>>>()
+            .map_err(|e| format!("Failed to collect categories: {}", e))?
+    };
+
+    let product_counts: HashMap<i32, i64> = {
+        let mut stmt = conn.prepare(
+            "SELECT category_id, COUNT(*) FROM products WHERE archived_at IS NULL GROUP BY category_id"
+        ).map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .map_err(|e| format!("Failed to count category products: {}", e))?;
+
+        rows.collect::<Result<HashMap<_, _>>>()
+            .map_err(|e| format!("Failed to collect category product counts: {}", e))?
+    };
+
+    // Categories whose parent isn't listed are shown at the top level rather than dropped
+    let listed: Vec<i32> = categories.iter().map(|c| c.id).collect();
+    let mut children: HashMap<Option<i32>, Vec<Category>> = HashMap::new();
+    for category in categories {
+        let parent = category.parent_id.filter(|id| listed.contains(id));
+        children.entry(parent).or_default().push(category);
+    }
+
+    Ok(build_tree(None, &mut children, &product_counts))
+}
+
+fn build_tree(
+    parent: Option<i32>,
+    children: &mut HashMap<Option<i32>, Vec<Category>>,
+    product_counts: &HashMap<i32, i64>,
+) -> Vec<CategoryNode> {
+    children.remove(&parent).unwrap_or_default()
+        .into_iter()
+        .map(|category| {
+            let nodes = build_tree(Some(category.id), children, product_counts);
+            let product_count = product_counts.get(&category.id).copied().unwrap_or(0);
+            CategoryNode {
+                total_product_count: product_count + nodes.iter().map(|n| n.total_product_count).sum::<i64>(),
+                product_count,
+                category,
+                children: nodes,
+            }
+        })
+        .collect()
+}
+
+/// The ids of a category and every category below it
+pub fn category_subtree(conn: &Connection, id: i32) -> Result<Vec<i32>, String> {
+    let mut stmt = conn.prepare(&format!("SELECT id FROM {} AS subtree", SUBTREE_IDS))
+        .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+    let rows = stmt.query_map(params![id], |row| row.get(0))
+        .map_err(|e| format!("Failed to query subcategories: {}", e))?;
+
+    rows.collect::<Result<Vec<_>>>()
+        .map_err(|e| format!("Failed to collect subcategories: {}", e))
+}
+
+/// Put a category, with everything below it, under another category, or at the top level
+/// when no parent is given
+#[tauri::command]
+pub fn move_category(state: tauri::State<DbState>, id: i32, parent_id: Option<i32>, token: String) -> Result<Category, String> {
+    println!("Backend: Moving category {} under {:?}", id, parent_id);
+
+    let mut conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let actor = authenticate(&conn, &token)?;
+    require_permission(&conn, &actor, EDIT_INVENTORY, "move categories")?;
+    let tx = conn.transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let category = load_category(&tx, id)?;
+    if let Some(parent_id) = parent_id {
+        if category_subtree(&tx, id)?.contains(&parent_id) {
+            return Err(format!("{} can't be moved under itself or one of its subcategories", category.name));
+        }
+        check_category_active(&tx, parent_id)?;
+    }
+
+    let before = snapshot(&tx, "categories", id as i64)?;
+    tx.execute(
+        "UPDATE categories SET parent_id = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
+        params![parent_id, id],
+    ).map_err(|e| format!("Failed to move category: {}", e))?;
+
+    let after = snapshot(&tx, "categories", id as i64)?;
+    record_audit(&tx, Some(&actor), UPDATE, "category", Some(id as i64), before, after)?;
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+    load_category(&conn, id)
+}
+
+#[tauri::command]
+pub fn update_category(state: tauri::State<DbState>, category: UpdateCategory, token: String) -> Result<Category, String> {
     println!("Backend: Updating category id: {}, name: {:?}", category.id, category.name);
     
     // Get a connection from the pool with proper error handling
@@ -50,6 +178,8 @@ pub fn update_category(state: tauri::State<DbState>, category: UpdateCategory) -
             return Err(error_msg);
         }
     };
+    let actor = authenticate(&conn, &token)?;
+    require_permission(&conn, &actor, EDIT_INVENTORY, "change categories")?;
     
     // Wrap the entire operation in a transaction to ensure atomicity
     let tx = match conn.transaction() {
@@ -61,6 +191,8 @@ pub fn update_category(state: tauri::State<DbState>, category: UpdateCategory) -
         }
     };
     
+    let before = snapshot(&tx, "categories", category.id as i64)?;
+
     // Update the category
     match tx.execute(
         "UPDATE categories SET name = ?1, description = ?2, icon = ?3, updated_at = CURRENT_TIMESTAMP WHERE id = ?4",
@@ -87,7 +219,7 @@ pub fn update_category(state: tauri::State<DbState>, category: UpdateCategory) -
     {
         // Retrieve the updated category
         let mut stmt = match tx.prepare(
-            "SELECT id, name, description, created_at, updated_at, icon FROM categories WHERE id = ?1"
+            &format!("SELECT {} FROM categories WHERE id = ?1", CATEGORY_COLUMNS)
         ) {
             Ok(stmt) => stmt,
             Err(e) => {
@@ -97,16 +229,7 @@ pub fn update_category(state: tauri::State<DbState>, category: UpdateCategory) -
             }
         };
 
-        result = match stmt.query_row(params![category.id], |row| {
-            Ok(Category {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                description: row.get(2)?,
-                created_at: row.get(3)?,
-                updated_at: row.get(4)?,
-                icon: row.get(5)?,
-            })
-        }) {
+        result = match stmt.query_row(params![category.id], map_category) {
             Ok(category) => category,
             Err(e) => {
                 let error_msg = format!("Failed to query updated category: {}", e);
@@ -115,6 +238,9 @@ pub fn update_category(state: tauri::State<DbState>, category: UpdateCategory) -
             }
         };
     } // stmt goes out of scope here
+
+    let after = snapshot(&tx, "categories", category.id as i64)?;
+    record_audit(&tx, Some(&actor), UPDATE, "category", Some(category.id as i64), before, after)?;
     
     // Commit the transaction
     if let Err(e) = tx.commit() {
@@ -128,7 +254,7 @@ pub fn update_category(state: tauri::State<DbState>, category: UpdateCategory) -
 }
 
 #[tauri::command]
-pub fn add_category(state: tauri::State<DbState>, category: NewCategory) -> Result<Category, String> {
+pub fn add_category(state: tauri::State<DbState>, category: NewCategory, token: String) -> Result<Category, String> {
     println!("Backend: Adding new category: {:?}", category.name);
     
     // Get a connection from the pool with proper error handling
@@ -140,6 +266,8 @@ pub fn add_category(state: tauri::State<DbState>, category: NewCategory) -> Resu
             return Err(error_msg);
         }
     };
+    let actor = authenticate(&conn, &token)?;
+    require_permission(&conn, &actor, EDIT_INVENTORY, "add categories")?;
     
     // Wrap the entire operation in a transaction to ensure atomicity
     let tx = match conn.transaction() {
@@ -151,10 +279,14 @@ pub fn add_category(state: tauri::State<DbState>, category: NewCategory) -> Resu
         }
     };
     
+    if let Some(parent_id) = category.parent_id {
+        check_category_active(&tx, parent_id)?;
+    }
+
     // Insert the new category
     match tx.execute(
-        "INSERT INTO categories (name, description, icon) VALUES (?1, ?2, ?3)",
-        params![category.name, category.description, category.icon],
+        "INSERT INTO categories (name, description, icon, parent_id) VALUES (?1, ?2, ?3, ?4)",
+        params![category.name, category.description, category.icon, category.parent_id],
     ) {
         Ok(_) => (),
         Err(e) => {
@@ -173,7 +305,7 @@ pub fn add_category(state: tauri::State<DbState>, category: NewCategory) -> Resu
     {
         // Retrieve the newly inserted category
         let mut stmt = match tx.prepare(
-            "SELECT id, name, description, created_at, updated_at, icon FROM categories WHERE id = ?1"
+            &format!("SELECT {} FROM categories WHERE id = ?1", CATEGORY_COLUMNS)
         ) {
             Ok(stmt) => stmt,
             Err(e) => {
@@ -183,16 +315,7 @@ pub fn add_category(state: tauri::State<DbState>, category: NewCategory) -> Resu
             }
         };
 
-        result = match stmt.query_row(params![last_id], |row| {
-            Ok(Category {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                description: row.get(2)?,
-                created_at: row.get(3)?,
-                updated_at: row.get(4)?,
-                icon: row.get(5)?,
-            })
-        }) {
+        result = match stmt.query_row(params![last_id], map_category) {
             Ok(category) => category,
             Err(e) => {
                 let error_msg = format!("Failed to query inserted category: {}", e);
@@ -201,6 +324,9 @@ pub fn add_category(state: tauri::State<DbState>, category: NewCategory) -> Resu
             }
         };
     } // stmt goes out of scope here
+
+    let after = snapshot(&tx, "categories", last_id)?;
+    record_audit(&tx, Some(&actor), CREATE, "category", Some(last_id), None, after)?;
     
     // Commit the transaction
     if let Err(e) = tx.commit() {
@@ -213,174 +339,380 @@ pub fn add_category(state: tauri::State<DbState>, category: NewCategory) -> Resu
     Ok(result)
 }
 
+const AFFECTED_QUERY: &str =
+    "SELECT p.id, p.name, p.sku, p.archived_at IS NOT NULL,
+            EXISTS (SELECT 1 FROM order_items oi WHERE oi.product_id = p.id)
+     FROM products p";
+
+fn map_affected(row: &rusqlite::Row) -> Result<AffectedProduct> {
+    Ok(AffectedProduct {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        sku: row.get(2)?,
+        archived: row.get(3)?,
+        sold: row.get(4)?,
+    })
+}
+
+fn query_affected(conn: &Connection, condition: &str, params: &[&dyn ToSql]) -> Result<Vec<AffectedProduct>, String> {
+    let mut stmt = conn.prepare(&format!("{} {} ORDER BY p.name", AFFECTED_QUERY, condition))
+        .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+    let rows = stmt.query_map(params, map_affected)
+        .map_err(|e| format!("Failed to query products: {}", e))?;
+
+    rows.collect::<Result<Vec<_>>>()
+        .map_err(|e| format!("Failed to collect products: {}", e))
+}
+
+// File products under another category, keeping an audit entry for each
+fn move_products(conn: &Connection, products: &[AffectedProduct], target_id: i32, actor: Option<&User>) -> Result<(), String> {
+    for product in products {
+        let before = snapshot(conn, "products", product.id as i64)?;
+        conn.execute(
+            "UPDATE products SET category_id = ?1, updated_at = datetime('now') WHERE id = ?2",
+            params![target_id, product.id],
+        ).map_err(|e| format!("Failed to move product {}: {}", product.name, e))?;
+
+        let after = snapshot(conn, "products", product.id as i64)?;
+        record_audit(conn, actor, UPDATE, "product", Some(product.id as i64), before, after)?;
+    }
+    Ok(())
+}
+
+fn subcategories(conn: &Connection, id: i32) -> Result<Vec<Category>, String> {
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM categories WHERE parent_id = ?1 ORDER BY name", CATEGORY_COLUMNS))
+        .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+    let rows = stmt.query_map(params![id], map_category)
+        .map_err(|e| format!("Failed to query subcategories: {}", e))?;
+
+    rows.collect::<Result<Vec<_>>>()
+        .map_err(|e| format!("Failed to collect subcategories: {}", e))
+}
+
+// Move a category's direct subcategories up to its own parent before it is deleted
+fn lift_subcategories(conn: &Connection, category: &Category, actor: Option<&User>) -> Result<(), String> {
+    for child in subcategories(conn, category.id)? {
+        let before = snapshot(conn, "categories", child.id as i64)?;
+        conn.execute(
+            "UPDATE categories SET parent_id = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
+            params![category.parent_id, child.id],
+        ).map_err(|e| format!("Failed to move subcategory {}: {}", child.name, e))?;
+
+        let after = snapshot(conn, "categories", child.id as i64)?;
+        record_audit(conn, actor, UPDATE, "category", Some(child.id as i64), before, after)?;
+    }
+    Ok(())
+}
+
+fn load_category(conn: &Connection, id: i32) -> Result<Category, String> {
+    conn.query_row(&format!("SELECT {} FROM categories WHERE id = ?1", CATEGORY_COLUMNS), params![id], map_category)
+        .optional()
+        .map_err(|e| format!("Failed to get category {}: {}", id, e))?
+        .ok_or_else(|| "Category not found".to_string())
+}
+
+/// Products can only be filed under, or restored into, a category that isn't archived
+pub fn check_category_active(conn: &Connection, category_id: i32) -> Result<(), String> {
+    let category = load_category(conn, category_id)?;
+    if category.archived_at.is_some() {
+        return Err(format!("The {} category is archived; restore it first", category.name));
+    }
+    Ok(())
+}
+
+/// Everything deleting a category would touch: its products, which of them have been sold,
+/// and its promotions
 #[tauri::command]
-pub fn delete_category(state: tauri::State<DbState>, id: i32) -> Result<String, String> {
-    println!("Backend: Deleting category with ID: {}", id);
-    
-    // Get a connection from the pool with proper error handling
-    let conn = match state.pool.get() {
-        Ok(conn) => conn,
-        Err(e) => {
-            let error_msg = format!("Failed to get connection from pool: {}", e);
-            println!("Backend error: {}", error_msg);
-            return Err(error_msg);
-        }
-    };
-    
-    // First check if there are any products associated with this category
-    let product_count = {
-        let mut stmt = match conn.prepare("SELECT COUNT(*) FROM products WHERE category_id = ?1") {
-            Ok(stmt) => stmt,
-            Err(e) => {
-                let error_msg = format!("Failed to prepare statement: {}", e);
-                println!("Backend error: {}", error_msg);
-                return Err(error_msg);
-            }
-        };
-        
-        match stmt.query_row(params![id], |row| row.get::<_, i64>(0)) {
-            Ok(count) => count,
-            Err(e) => {
-                let error_msg = format!("Failed to check product count: {}", e);
-                println!("Backend error: {}", error_msg);
-                return Err(error_msg);
+pub fn preview_delete_category(state: tauri::State<DbState>, id: i32) -> Result<CategoryDeletionPreview, String> {
+    let conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+
+    let category = load_category(&conn, id)?;
+    let products = query_affected(&conn, "WHERE p.category_id = ?1", &[&id])?;
+    let promotions: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM promotions WHERE category_id = ?1",
+        params![id],
+        |row| row.get(0),
+    ).map_err(|e| format!("Failed to count category promotions: {}", e))?;
+
+    Ok(CategoryDeletionPreview {
+        category,
+        sold_products: products.iter().filter(|p| p.sold).count(),
+        products,
+        promotions,
+        subcategories: subcategories(&conn, id)?,
+    })
+}
+
+/// Delete a category, dealing with the products still in it as the strategy says. `refuse`
+/// (the default) only deletes an empty category; `move` files its products under the target
+/// category first; `archive` archives the category and its products instead of deleting them.
+#[tauri::command]
+pub fn delete_category(
+    state: tauri::State<DbState>,
+    id: i32,
+    strategy: Option<String>,
+    target_category_id: Option<i32>,
+    token: String,
+) -> Result<String, String> {
+    let strategy = strategy.as_deref().map(str::trim).filter(|s| !s.is_empty()).unwrap_or(REFUSE).to_lowercase();
+    println!("Backend: Deleting category {} with strategy {}", id, strategy);
+
+    let mut conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let actor = authenticate(&conn, &token)?;
+    require_permission(&conn, &actor, EDIT_INVENTORY, "delete categories")?;
+    let tx = conn.transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let category = load_category(&tx, id)?;
+    let before = snapshot(&tx, "categories", id as i64)?;
+    let products = query_affected(&tx, "WHERE p.category_id = ?1", &[&id])?;
+
+    let message = match strategy.as_str() {
+        REFUSE => {
+            if !products.is_empty() {
+                return Err(format!(
+                    "{} still has {} product(s); move or archive them to delete it",
+                    category.name, products.len()
+                ));
             }
+            format!("Deleted {}", category.name)
         }
-    };
-    
-    println!("Category {} has {} associated products", id, product_count);
-    
-    // Create a new mutable reference for the transaction
-    let mut conn_for_tx = match state.pool.get() {
-        Ok(conn) => conn,
-        Err(e) => {
-            let error_msg = format!("Failed to get connection from pool for transaction: {}", e);
-            println!("Backend error: {}", error_msg);
-            return Err(error_msg);
+        MOVE => {
+            let target_id = target_category_id.ok_or("Pick the category to move the products to")?;
+            if target_id == id {
+                return Err("Products can't be moved to the category being deleted".to_string());
+            }
+            check_category_active(&tx, target_id)?;
+            let target = load_category(&tx, target_id)?;
+
+            move_products(&tx, &products, target_id, Some(&actor))?;
+            format!("Moved {} product(s) to {} and deleted {}", products.len(), target.name, category.name)
         }
-    };
-    
-    // Wrap the entire operation in a transaction to ensure atomicity
-    let tx = match conn_for_tx.transaction() {
-        Ok(tx) => tx,
-        Err(e) => {
-            let error_msg = format!("Failed to start transaction: {}", e);
-            println!("Backend error: {}", error_msg);
-            return Err(error_msg);
+        ARCHIVE => {
+            let archived = archive_in(&tx, id)?;
+            let after = snapshot(&tx, "categories", id as i64)?;
+            record_audit(&tx, Some(&actor), UPDATE, "category", Some(id as i64), before, after)?;
+            tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+            return Ok(format!("Archived {} and {} product(s) instead of deleting them", category.name, archived.len()));
         }
+        _ => return Err(format!("Unknown deletion strategy: {}", strategy)),
     };
-    
-    // Check if any products in this category are referenced in order_items
-    if product_count > 0 {
-        // Get products from this category that are in orders
-        let mut stmt = match tx.prepare(
-            "SELECT DISTINCT p.id 
-             FROM products p 
-             JOIN order_items oi ON oi.product_id = p.id 
-             WHERE p.category_id = ?1"
-        ) {
-            Ok(stmt) => stmt,
-            Err(e) => {
-                let error_msg = format!("Failed to prepare statement: {}", e);
-                println!("Backend error: {}", error_msg);
-                return Err(error_msg);
-            }
-        };
-        
-        let product_ids_result = stmt.query_map(params![id], |row| {
-            row.get(0)
-        }).map_err(|e| {
-            format!("Failed to get products in orders: {}", e)
-        });
-        
-        let product_ids = match product_ids_result {
-            Ok(rows) => {
-                let collected: Result<Vec<i32>, _> = rows.collect();
-                match collected {
-                    Ok(ids) => ids,
-                    Err(e) => return Err(format!("Failed to collect product IDs: {}", e)),
-                }
-            },
-            Err(e) => return Err(e),
-        };
-        
-        if !product_ids.is_empty() {
-            println!("Found {} products in orders that need reassignment", product_ids.len());
-            
-            // Find or create a default "Uncategorized" category
-            let default_category_id = {
-                // Try to find existing "Uncategorized" category
-                let mut stmt = match tx.prepare("SELECT id FROM categories WHERE name = 'Uncategorized'") {
-                    Ok(stmt) => stmt,
-                    Err(e) => return Err(format!("Failed to prepare statement: {}", e)),
-                };
-                
-                let result: Result<Option<i32>, rusqlite::Error> = stmt.query_row([], |row| {
-                    Ok(Some(row.get(0)?))
-                }).or_else(|_| Ok(None));
-                
-                match result {
-                    Ok(Some(existing_id)) => existing_id,
-                    Ok(None) => {
-                        // Create a new "Uncategorized" category
-                        match tx.execute(
-                            "INSERT INTO categories (name, description) VALUES ('Uncategorized', 'Default category for products with deleted categories')",
-                            [],
-                        ) {
-                            Ok(_) => tx.last_insert_rowid() as i32,
-                            Err(e) => return Err(format!("Failed to create Uncategorized category: {}", e)),
-                        }
-                    },
-                    Err(e) => return Err(format!("Database error: {}", e)),
-                }
-            };
-            
-            // Don't allow deleting the Uncategorized category
-            if id == default_category_id {
-                return Err("Cannot delete the Uncategorized category. Please first move all products in this category to another category or create a new category for them.".to_string());
-            }
-            
-            // Move products to the default category
-            for product_id in product_ids {
-                match tx.execute(
-                    "UPDATE products SET category_id = ?1 WHERE id = ?2",
-                    params![default_category_id, product_id],
-                ) {
-                    Ok(_) => (),
-                    Err(e) => return Err(format!("Failed to reassign product {}: {}", product_id, e)),
-                }
-            }
-            
-            println!("Reassigned products to category ID {}", default_category_id);
-        }
+
+    lift_subcategories(&tx, &category, Some(&actor))?;
+
+    // Category-wide promotions go with the category
+    tx.execute("DELETE FROM promotions WHERE category_id = ?1", params![id])
+        .map_err(|e| format!("Failed to delete category promotions: {}", e))?;
+
+    tx.execute("DELETE FROM categories WHERE id = ?1", params![id])
+        .map_err(|e| format!("Failed to delete category: {}", e))?;
+
+    record_audit(&tx, Some(&actor), DELETE, "category", Some(id as i64), before, None)?;
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+    println!("Backend: {}", message);
+    Ok(message)
+}
+
+/// Clean up after older versions of delete_category: move everything out of the Uncategorized
+/// category they created, and products left pointing at deleted categories, into the chosen
+/// category, then remove Uncategorized.
+#[tauri::command]
+pub fn repair_uncategorized(state: tauri::State<DbState>, target_category_id: i32, token: String) -> Result<CategoryRepair, String> {
+    println!("Backend: Repairing uncategorized products into category {}", target_category_id);
+
+    let mut conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let actor = authenticate(&conn, &token)?;
+    require_permission(&conn, &actor, EDIT_INVENTORY, "move uncategorized products")?;
+    let tx = conn.transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let uncategorized: Option<Category> = tx.query_row(
+        &format!("SELECT {} FROM categories WHERE name = ?1", CATEGORY_COLUMNS),
+        params![UNCATEGORIZED],
+        map_category,
+    ).optional()
+        .map_err(|e| format!("Failed to look up the {} category: {}", UNCATEGORIZED, e))?;
+    let uncategorized_id = uncategorized.as_ref().map(|c| c.id);
+
+    if uncategorized_id == Some(target_category_id) {
+        return Err(format!("Pick a category other than {}", UNCATEGORIZED));
     }
-    
-    // Delete the category - SQLite will automatically delete the remaining products due to ON DELETE CASCADE
-    let deleted_count = match tx.execute(
-        "DELETE FROM categories WHERE id = ?1",
-        params![id]
-    ) {
-        Ok(count) => count,
-        Err(e) => {
-            let error_msg = format!("Backend error deleting category: {}", e);
-            println!("{}", error_msg);
-            // Rollback transaction is automatic when tx goes out of scope without commit
-            return Err(error_msg);
-        }
+    check_category_active(&tx, target_category_id)?;
+
+    let moved_products = match uncategorized_id {
+        Some(uncategorized_id) => query_affected(&tx, "WHERE p.category_id = ?1", &[&uncategorized_id])?,
+        None => Vec::new(),
     };
+    move_products(&tx, &moved_products, target_category_id, Some(&actor))?;
+
+    let orphaned_products = query_affected(&tx, "WHERE p.category_id NOT IN (SELECT id FROM categories)", &[])?;
+    move_products(&tx, &orphaned_products, target_category_id, Some(&actor))?;
+
+    if let Some(uncategorized) = &uncategorized {
+        let uncategorized_id = uncategorized.id;
+        let before = snapshot(&tx, "categories", uncategorized_id as i64)?;
+        lift_subcategories(&tx, uncategorized, Some(&actor))?;
 
-    if deleted_count == 0 {
-        println!("Backend: No category found with ID: {}", id);
-        return Err("Category not found".to_string());
+        tx.execute("DELETE FROM promotions WHERE category_id = ?1", params![uncategorized_id])
+            .map_err(|e| format!("Failed to delete category promotions: {}", e))?;
+
+        tx.execute("DELETE FROM categories WHERE id = ?1", params![uncategorized_id])
+            .map_err(|e| format!("Failed to delete the {} category: {}", UNCATEGORIZED, e))?;
+
+        record_audit(&tx, Some(&actor), DELETE, "category", Some(uncategorized_id as i64), before, None)?;
     }
 
-    // Commit the transaction
-    if let Err(e) = tx.commit() {
-        let error_msg = format!("Failed to commit transaction: {}", e);
-        println!("Backend error: {}", error_msg);
-        return Err(error_msg);
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+    println!(
+        "Backend: Moved {} uncategorized and {} orphaned products",
+        moved_products.len(),
+        orphaned_products.len()
+    );
+    Ok(CategoryRepair {
+        target_category_id,
+        moved_products,
+        orphaned_products,
+        removed_uncategorized: uncategorized_id.is_some(),
+    })
+}
+
+// Archive a category, its subcategories and their listed products under one timestamp;
+// returns the products archived
+fn archive_in(conn: &Connection, id: i32) -> Result<Vec<i32>, String> {
+    let archived_at = archive_timestamp();
+
+    let category = load_category(conn, id)?;
+    if category.archived_at.is_some() {
+        return Err("Category not found or already archived".to_string());
+    }
+
+    let product_ids: Vec<i32> = query_affected(
+        conn,
+        &format!("WHERE p.category_id IN {} AND p.archived_at IS NULL", SUBTREE_IDS),
+        &[&id],
+    )?
+        .into_iter()
+        .map(|product| product.id)
+        .collect();
+
+    // Everything shares the category's timestamp so restoring it brings back exactly these
+    conn.execute(
+        &format!(
+            "UPDATE products SET archived_at = ?2, updated_at = datetime('now') WHERE category_id IN {} AND archived_at IS NULL",
+            SUBTREE_IDS
+        ),
+        params![id, archived_at],
+    ).map_err(|e| format!("Failed to archive category products: {}", e))?;
+
+    conn.execute(
+        &format!(
+            "UPDATE categories SET archived_at = ?2, updated_at = CURRENT_TIMESTAMP WHERE id IN {} AND archived_at IS NULL",
+            SUBTREE_IDS
+        ),
+        params![id, archived_at],
+    ).map_err(|e| format!("Failed to archive category: {}", e))?;
+
+    // Archived products no longer raise stock alerts
+    evaluate_stock_alerts(conn, &product_ids)?;
+    Ok(product_ids)
+}
+
+/// Hide a category, its subcategories and every product in them from the catalogue and the register. Sales and
+/// reports still refer to them.
+#[tauri::command]
+pub fn archive_category(state: tauri::State<DbState>, id: i32, token: String) -> Result<Category, String> {
+    println!("Backend: Archiving category {}", id);
+
+    let mut conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let actor = authenticate(&conn, &token)?;
+    require_permission(&conn, &actor, EDIT_INVENTORY, "archive categories")?;
+    let tx = conn.transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let before = snapshot(&tx, "categories", id as i64)?;
+    let product_ids = archive_in(&tx, id)?;
+
+    let after = snapshot(&tx, "categories", id as i64)?;
+    record_audit(&tx, Some(&actor), UPDATE, "category", Some(id as i64), before, after)?;
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+    println!("Backend: Archived category {} with {} products", id, product_ids.len());
+    load_category(&conn, id)
+}
+
+/// Bring back an archived category along with the subcategories and products that were archived
+/// with it. Those archived on their own beforehand stay archived.
+#[tauri::command]
+pub fn restore_category(app: tauri::AppHandle, state: tauri::State<DbState>, id: i32, token: String) -> Result<Category, String> {
+    println!("Backend: Restoring category {}", id);
+
+    let mut conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let actor = authenticate(&conn, &token)?;
+    require_permission(&conn, &actor, EDIT_INVENTORY, "restore categories")?;
+    let tx = conn.transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let category = load_category(&tx, id)?;
+    let archived_at = match &category.archived_at {
+        Some(archived_at) => archived_at.clone(),
+        None => return Err(format!("The {} category is not archived", category.name)),
+    };
+    if let Some(parent_id) = category.parent_id {
+        check_category_active(&tx, parent_id)?;
     }
+    let before = snapshot(&tx, "categories", id as i64)?;
+
+    let product_ids: Vec<i32> = {
+        let mut stmt = tx.prepare(&format!("SELECT id FROM products WHERE category_id IN {} AND archived_at = ?2", SUBTREE_IDS))
+            .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let rows = stmt.query_map(params![id, archived_at], |row| row.get(0))
+            .map_err(|e| format!("Failed to query category products: {}", e))?;
+
+        rows.collect::<Result<Vec<_>>>()
+            .map_err(|e| format!("Failed to collect category products: {}", e))?
+    };
 
-    println!("Backend: Successfully deleted category {} and its products (products in orders were moved to Uncategorized)", id);
-    Ok("Category successfully deleted".to_string())
-} 
\ No newline at end of file
+    tx.execute(
+        &format!(
+            "UPDATE products SET archived_at = NULL, updated_at = datetime('now') WHERE category_id IN {} AND archived_at = ?2",
+            SUBTREE_IDS
+        ),
+        params![id, archived_at],
+    ).map_err(|e| format!("Failed to restore category products: {}", e))?;
+
+    tx.execute(
+        &format!(
+            "UPDATE categories SET archived_at = NULL, updated_at = CURRENT_TIMESTAMP WHERE id IN {} AND archived_at = ?2",
+            SUBTREE_IDS
+        ),
+        params![id, archived_at],
+    ).map_err(|e| format!("Failed to restore category: {}", e))?;
+
+    let alerts = evaluate_stock_alerts(&tx, &product_ids)?;
+
+    let after = snapshot(&tx, "categories", id as i64)?;
+    record_audit(&tx, Some(&actor), UPDATE, "category", Some(id as i64), before, after)?;
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    emit_stock_alerts(&app, &alerts);
+
+    println!("Backend: Restored category {} with {} products", id, product_ids.len());
+    load_category(&conn, id)
+}
+
+#[tauri::command]
+pub fn get_archived_categories(state: tauri::State<DbState>) -> Result<Vec<Category>, String> {
+    let conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM categories WHERE archived_at IS NOT NULL ORDER BY archived_at DESC, name",
+        CATEGORY_COLUMNS
+    )).map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+    let rows = stmt.query_map([], map_category)
+        .map_err(|e| format!("Failed to query archived categories: {}", e))?;
+
+    rows.collect::<Result<Vec<_>>>()
+        .map_err(|e| format!("Failed to collect archived categories: {}", e))
+}
@@ -0,0 +1,318 @@
+use crate::commands::audit::{record_audit, snapshot};
+use crate::commands::auth::authenticate;
+use crate::commands::role::require_permission;
+use crate::db::models::audit::{CREATE, UPDATE};
+use crate::db::models::customer::{
+    AccountEntry, Customer, CustomerStatement, NewAccountPayment, NewCustomer, UpdateCustomer, CHARGE, PAYMENT,
+};
+use crate::db::models::payment::{ACCOUNT, PAYMENT_METHODS, WALLET};
+use crate::db::models::role::SELL;
+use crate::db::DbState;
+use rusqlite::{params, Connection, Result};
+
+// Amounts are compared to the centavo
+const TOLERANCE: f64 = 0.005;
+
+pub const CUSTOMER_COLUMNS: &str =
+    "id, name, id_number, phone, email, credit_limit, balance, active, notes, created_at, updated_at, card_code, wallet_balance";
+
+const ENTRY_QUERY: &str =
+    "SELECT l.id, l.customer_id, l.kind, l.order_id, o.order_id, l.amount, l.method, l.reference, l.notes,
+            l.balance_after, l.created_at
+     FROM customer_ledger l
+     LEFT JOIN orders o ON o.id = l.order_id";
+
+pub fn map_customer(row: &rusqlite::Row) -> Result<Customer> {
+    Ok(Customer {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        id_number: row.get(2)?,
+        phone: row.get(3)?,
+        email: row.get(4)?,
+        credit_limit: row.get(5)?,
+        balance: row.get(6)?,
+        card_code: row.get(11)?,
+        wallet_balance: row.get(12)?,
+        active: row.get(7)?,
+        notes: row.get(8)?,
+        created_at: row.get(9)?,
+        updated_at: row.get(10)?,
+    })
+}
+
+fn map_entry(row: &rusqlite::Row) -> Result<AccountEntry> {
+    Ok(AccountEntry {
+        id: row.get(0)?,
+        customer_id: row.get(1)?,
+        kind: row.get(2)?,
+        order_id: row.get(3)?,
+        order_number: row.get(4)?,
+        amount: row.get(5)?,
+        method: row.get(6)?,
+        reference: row.get(7)?,
+        notes: row.get(8)?,
+        balance_after: row.get(9)?,
+        created_at: row.get(10)?,
+    })
+}
+
+pub fn load_customer(conn: &Connection, id: i32) -> Result<Customer, String> {
+    conn.query_row(
+        &format!("SELECT {} FROM customers WHERE id = ?1", CUSTOMER_COLUMNS),
+        params![id],
+        map_customer,
+    ).map_err(|e| format!("Failed to get customer {}: {}", id, e))
+}
+
+fn validate_credit_limit(credit_limit: f64) -> Result<(), String> {
+    if !credit_limit.is_finite() || credit_limit < 0.0 {
+        return Err("Credit limit cannot be negative".to_string());
+    }
+    Ok(())
+}
+
+// Add an entry to a customer's account and move their balance by its amount;
+// details are the (method, reference, notes) of the entry
+fn post_entry(
+    conn: &Connection,
+    customer: &Customer,
+    kind: &str,
+    order_id: Option<i32>,
+    amount: f64,
+    details: (Option<&str>, Option<&str>, Option<&str>),
+) -> Result<AccountEntry, String> {
+    let (method, reference, notes) = details;
+    let balance_after = ((customer.balance + amount) * 100.0).round() / 100.0;
+    let created_at = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+
+    conn.execute(
+        "INSERT INTO customer_ledger (customer_id, kind, order_id, amount, method, reference, notes, balance_after, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        params![customer.id, kind, order_id, amount, method, reference, notes, balance_after, created_at],
+    ).map_err(|e| format!("Failed to record account entry: {}", e))?;
+    let entry_id = conn.last_insert_rowid();
+
+    conn.execute(
+        "UPDATE customers SET balance = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
+        params![balance_after, customer.id],
+    ).map_err(|e| format!("Failed to update customer balance: {}", e))?;
+
+    conn.query_row(&format!("{} WHERE l.id = ?1", ENTRY_QUERY), params![entry_id], map_entry)
+        .map_err(|e| format!("Failed to get account entry: {}", e))
+}
+
+/// Put an order's account payments on the customer's tab, refusing charges that would take
+/// the balance past the customer's credit limit. Runs in the order's transaction.
+pub fn charge_account(conn: &Connection, customer_id: Option<i32>, order_id: i32, amount: f64) -> Result<(), String> {
+    if amount <= 0.0 {
+        return Ok(());
+    }
+
+    let customer_id = customer_id
+        .ok_or_else(|| "Charging to an account needs a customer on the order".to_string())?;
+    let customer = load_customer(conn, customer_id)?;
+
+    if !customer.active {
+        return Err(format!("{}'s account is closed", customer.name));
+    }
+    if customer.balance + amount > customer.credit_limit + TOLERANCE {
+        return Err(format!(
+            "Charging {:.2} would put {} over their credit limit of {:.2} (balance {:.2})",
+            amount, customer.name, customer.credit_limit, customer.balance
+        ));
+    }
+
+    post_entry(conn, &customer, CHARGE, Some(order_id), amount, (Some(ACCOUNT), None, None))?;
+    Ok(())
+}
+
+/// The customer an order is for, checked to exist and be active
+pub fn check_order_customer(conn: &Connection, customer_id: Option<i32>) -> Result<(), String> {
+    if let Some(customer_id) = customer_id {
+        let customer = load_customer(conn, customer_id)?;
+        if !customer.active {
+            return Err(format!("{}'s account is closed", customer.name));
+        }
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_customers(state: tauri::State<DbState>, search: Option<String>, include_inactive: Option<bool>) -> Result<Vec<Customer>, String> {
+    let conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+
+    let search = search.map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM customers
+         WHERE (?1 OR active = 1)
+           AND (?2 IS NULL OR name LIKE '%' || ?2 || '%' OR id_number LIKE '%' || ?2 || '%' OR phone LIKE '%' || ?2 || '%'
+                OR card_code = ?2)
+         ORDER BY name",
+        CUSTOMER_COLUMNS
+    )).map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+    let rows = stmt.query_map(params![include_inactive.unwrap_or(false), search], map_customer)
+        .map_err(|e| format!("Failed to query customers: {}", e))?;
+
+    rows.collect::<Result<Vec<_>>>()
+        .map_err(|e| format!("Failed to collect customers: {}", e))
+}
+
+#[tauri::command]
+pub fn get_customer(state: tauri::State<DbState>, id: i32) -> Result<Customer, String> {
+    let conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    load_customer(&conn, id)
+}
+
+#[tauri::command]
+pub fn add_customer(state: tauri::State<DbState>, customer: NewCustomer, token: String) -> Result<Customer, String> {
+    println!("Backend: Adding customer {}", customer.name);
+
+    if customer.name.trim().is_empty() {
+        return Err("Customer name is required".to_string());
+    }
+    validate_credit_limit(customer.credit_limit)?;
+
+    let mut conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let actor = authenticate(&conn, &token)?;
+    require_permission(&conn, &actor, SELL, "add customers")?;
+    let tx = conn.transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    tx.execute(
+        "INSERT INTO customers (name, id_number, phone, email, credit_limit, notes) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![customer.name.trim(), customer.id_number, customer.phone, customer.email, customer.credit_limit, customer.notes],
+    ).map_err(|e| format!("Failed to insert customer: {}", e))?;
+
+    let id = tx.last_insert_rowid();
+    let after = snapshot(&tx, "customers", id)?;
+    record_audit(&tx, Some(&actor), CREATE, "customer", Some(id), None, after)?;
+
+    let created = load_customer(&tx, id as i32)?;
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    Ok(created)
+}
+
+// Closing an account (active = false) keeps its history; the balance is left as it is
+#[tauri::command]
+pub fn update_customer(state: tauri::State<DbState>, customer: UpdateCustomer, token: String) -> Result<Customer, String> {
+    println!("Backend: Updating customer {}", customer.id);
+
+    if customer.name.trim().is_empty() {
+        return Err("Customer name is required".to_string());
+    }
+    validate_credit_limit(customer.credit_limit)?;
+
+    let mut conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let actor = authenticate(&conn, &token)?;
+    require_permission(&conn, &actor, SELL, "change customers")?;
+    let tx = conn.transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let before = snapshot(&tx, "customers", customer.id as i64)?;
+    let updated = tx.execute(
+        "UPDATE customers SET name = ?1, id_number = ?2, phone = ?3, email = ?4, credit_limit = ?5, active = ?6,
+                notes = ?7, updated_at = CURRENT_TIMESTAMP
+         WHERE id = ?8",
+        params![
+            customer.name.trim(),
+            customer.id_number,
+            customer.phone,
+            customer.email,
+            customer.credit_limit,
+            customer.active,
+            customer.notes,
+            customer.id
+        ],
+    ).map_err(|e| format!("Failed to update customer: {}", e))?;
+
+    if updated == 0 {
+        return Err("Customer not found".to_string());
+    }
+
+    let after = snapshot(&tx, "customers", customer.id as i64)?;
+    record_audit(&tx, Some(&actor), UPDATE, "customer", Some(customer.id as i64), before, after)?;
+
+    let updated = load_customer(&tx, customer.id)?;
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    Ok(updated)
+}
+
+/// Record money received against a customer's tab. Paying more than is owed leaves
+/// store credit on the account.
+#[tauri::command]
+pub fn record_account_payment(state: tauri::State<DbState>, payment: NewAccountPayment, token: String) -> Result<AccountEntry, String> {
+    println!("Backend: Recording account payment of {:.2} for customer {}", payment.amount, payment.customer_id);
+
+    if !payment.amount.is_finite() || payment.amount <= 0.0 {
+        return Err("Payment amount must be greater than zero".to_string());
+    }
+    let method = payment.method.to_lowercase();
+    if method == ACCOUNT || method == WALLET || !PAYMENT_METHODS.contains(&method.as_str()) {
+        return Err(format!("Unknown payment method: {}", payment.method));
+    }
+
+    let mut conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let actor = authenticate(&conn, &token)?;
+    require_permission(&conn, &actor, SELL, "take account payments")?;
+    let tx = conn.transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let customer = load_customer(&tx, payment.customer_id)?;
+    let before = snapshot(&tx, "customers", customer.id as i64)?;
+    let reference = payment.reference.as_deref().map(str::trim).filter(|r| !r.is_empty());
+    let entry = post_entry(&tx, &customer, PAYMENT, None, -payment.amount, (Some(&method), reference, payment.notes.as_deref()))?;
+
+    let after = snapshot(&tx, "customers", customer.id as i64)?;
+    record_audit(&tx, Some(&actor), UPDATE, "customer", Some(customer.id as i64), before, after)?;
+
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    Ok(entry)
+}
+
+/// Charges and payments on a customer's account between two dates (inclusive), with the
+/// balance carried in and out
+#[tauri::command]
+pub fn get_customer_statement(
+    state: tauri::State<DbState>,
+    customer_id: i32,
+    start_date: String,
+    end_date: String,
+) -> Result<CustomerStatement, String> {
+    let conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let customer = load_customer(&conn, customer_id)?;
+
+    let opening_balance: f64 = conn.query_row(
+        "SELECT COALESCE((SELECT balance_after FROM customer_ledger
+                          WHERE customer_id = ?1 AND date(created_at) < date(?2)
+                          ORDER BY id DESC LIMIT 1), 0)",
+        params![customer_id, start_date],
+        |row| row.get(0),
+    ).map_err(|e| format!("Failed to get opening balance: {}", e))?;
+
+    let entries = {
+        let mut stmt = conn.prepare(&format!(
+            "{} WHERE l.customer_id = ?1 AND date(l.created_at) >= date(?2) AND date(l.created_at) <= date(?3)
+             ORDER BY l.id",
+            ENTRY_QUERY
+        )).map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let rows = stmt.query_map(params![customer_id, start_date, end_date], map_entry)
+            .map_err(|e| format!("Failed to query account entries: {}", e))?;
+
+        rows.collect::<Result<Vec<_>>>()
+            .map_err(|e| format!("Failed to collect account entries: {}", e))?
+    };
+
+    let charges: f64 = entries.iter().filter(|e| e.kind == CHARGE).map(|e| e.amount).sum();
+    let payments: f64 = entries.iter().filter(|e| e.kind == PAYMENT).map(|e| -e.amount).sum();
+    let closing_balance = entries.last().map_or(opening_balance, |e| e.balance_after);
+
+    Ok(CustomerStatement {
+        customer,
+        start_date,
+        end_date,
+        opening_balance,
+        charges,
+        payments,
+        closing_balance,
+        entries,
+    })
+}
@@ -0,0 +1,503 @@
+use crate::commands::audit::{record_audit, snapshot};
+use crate::commands::auth::authenticate;
+use crate::commands::category::category_subtree;
+use crate::commands::role::require_permission;
+use crate::db::models::audit::{CREATE, DELETE, UPDATE};
+use crate::db::models::discount::{
+    AppliedDiscount, DiscountRequest, NewPromotion, OrderDiscount, Promotion, UpdatePromotion, BUY_X_GET_Y, CATEGORY,
+    FIXED, HAPPY_HOUR, PERCENT, SENIOR, SOURCE_LINE, SOURCE_ORDER, SOURCE_PROMOTION, STAFF,
+};
+use crate::db::models::order::NewOrderItem;
+use crate::db::models::role::MANAGE_SETTINGS;
+use crate::db::DbState;
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use rusqlite::{params, Connection, Result};
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+
+const PROMOTION_COLUMNS: &str = "id, name, kind, percent, product_id, category_id, buy_quantity, get_quantity,
+    start_time, end_time, starts_on, ends_on, active, created_at";
+
+fn map_promotion(row: &rusqlite::Row) -> Result<Promotion> {
+    Ok(Promotion {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        kind: row.get(2)?,
+        percent: row.get(3)?,
+        product_id: row.get(4)?,
+        category_id: row.get(5)?,
+        buy_quantity: row.get(6)?,
+        get_quantity: row.get(7)?,
+        start_time: row.get(8)?,
+        end_time: row.get(9)?,
+        starts_on: row.get(10)?,
+        ends_on: row.get(11)?,
+        active: row.get(12)?,
+        created_at: row.get(13)?,
+    })
+}
+
+fn round_cents(amount: f64) -> f64 {
+    (amount * 100.0).round() / 100.0
+}
+
+fn validate_discount(discount: &DiscountRequest) -> Result<(), String> {
+    if !discount.value.is_finite() || discount.value <= 0.0 {
+        return Err("Discount must be greater than zero".to_string());
+    }
+
+    match discount.kind.as_str() {
+        FIXED => Ok(()),
+        PERCENT if discount.value <= 100.0 => Ok(()),
+        STAFF | SENIOR if discount.value <= 100.0 => {
+            let missing = |value: &Option<String>| value.as_deref().is_none_or(|v| v.trim().is_empty());
+            if missing(&discount.reason) {
+                return Err(format!("A reason is required for {} discounts", discount.kind));
+            }
+            if missing(&discount.id_number) {
+                return Err(format!("An ID number is required for {} discounts", discount.kind));
+            }
+            Ok(())
+        }
+        PERCENT | STAFF | SENIOR => Err("A percentage discount cannot exceed 100%".to_string()),
+        other => Err(format!("Unknown discount type: {}", other)),
+    }
+}
+
+// How much a manual discount takes off an amount, never more than the amount itself
+fn discount_amount(discount: &DiscountRequest, base: f64) -> f64 {
+    let amount = if discount.kind == FIXED { discount.value } else { base * discount.value / 100.0 };
+    round_cents(amount.min(base))
+}
+
+fn describe_discount(discount: &DiscountRequest) -> String {
+    match discount.kind.as_str() {
+        FIXED => format!("Discount {:.2}", discount.value),
+        STAFF => format!("Staff discount {}%", discount.value),
+        SENIOR => format!("Senior citizen discount {}%", discount.value),
+        _ => format!("Discount {}%", discount.value),
+    }
+}
+
+fn validate_promotion(promotion: &NewPromotion) -> Result<(), String> {
+    if promotion.name.trim().is_empty() {
+        return Err("Promotion name is required".to_string());
+    }
+    if !promotion.percent.is_finite() || promotion.percent <= 0.0 || promotion.percent > 100.0 {
+        return Err("Promotion percentage must be between 0 and 100".to_string());
+    }
+
+    match promotion.kind.as_str() {
+        BUY_X_GET_Y => {
+            let positive = |q: Option<f64>| q.is_some_and(|q| q.is_finite() && q > 0.0 && q.fract() == 0.0);
+            if !positive(promotion.buy_quantity) || !positive(promotion.get_quantity) {
+                return Err("Buy and get quantities must be whole numbers greater than zero".to_string());
+            }
+        }
+        HAPPY_HOUR => {
+            if promotion.start_time.is_none() || promotion.end_time.is_none() {
+                return Err("A happy hour needs a start and end time".to_string());
+            }
+        }
+        CATEGORY => {
+            if promotion.category_id.is_none() {
+                return Err("A category promotion needs a category".to_string());
+            }
+        }
+        other => return Err(format!("Unknown promotion type: {}", other)),
+    }
+
+    for time in [&promotion.start_time, &promotion.end_time].into_iter().flatten() {
+        NaiveTime::parse_from_str(time, "%H:%M").map_err(|_| format!("Invalid time {}, expected HH:MM", time))?;
+    }
+    if promotion.start_time.is_some() != promotion.end_time.is_some() {
+        return Err("Both a start and end time are needed for a time window".to_string());
+    }
+
+    for date in [&promotion.starts_on, &promotion.ends_on].into_iter().flatten() {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|_| format!("Invalid date {}, expected YYYY-MM-DD", date))?;
+    }
+    if let (Some(start), Some(end)) = (&promotion.starts_on, &promotion.ends_on) {
+        if start > end {
+            return Err("Promotion ends before it starts".to_string());
+        }
+    }
+
+    Ok(())
+}
+
+// Whether a promotion is running at the given moment, by its dates and daily time window
+fn is_running(promotion: &Promotion, now: &NaiveDateTime) -> bool {
+    let today = now.format("%Y-%m-%d").to_string();
+    if promotion.starts_on.as_ref().is_some_and(|start| &today < start)
+        || promotion.ends_on.as_ref().is_some_and(|end| &today > end)
+    {
+        return false;
+    }
+
+    match (&promotion.start_time, &promotion.end_time) {
+        (Some(start), Some(end)) => {
+            let time = now.format("%H:%M").to_string();
+            if start <= end {
+                start <= &time && &time < end
+            } else {
+                // The window runs past midnight
+                &time >= start || &time < end
+            }
+        }
+        _ => true,
+    }
+}
+
+// What a promotion takes off a line, zero when it doesn't cover the product. A category
+// promotion covers products anywhere in the category's subtree.
+fn promotion_amount(
+    promotion: &Promotion,
+    subtrees: &HashMap<i32, Vec<i32>>,
+    product_id: i32,
+    category_id: i32,
+    quantity: f64,
+    unit_price: f64,
+) -> f64 {
+    if promotion.product_id.is_some_and(|id| id != product_id)
+        || promotion.category_id.is_some_and(|id| !subtrees.get(&id).is_some_and(|ids| ids.contains(&category_id)))
+    {
+        return 0.0;
+    }
+
+    let discounted_quantity = if promotion.kind == BUY_X_GET_Y {
+        let buy = promotion.buy_quantity.unwrap_or(0.0);
+        let get = promotion.get_quantity.unwrap_or(0.0);
+        if buy + get <= 0.0 {
+            return 0.0;
+        }
+        (quantity / (buy + get)).floor() * get
+    } else {
+        quantity
+    };
+
+    round_cents(discounted_quantity * unit_price * promotion.percent / 100.0)
+}
+
+fn load_running_promotions(conn: &Connection, now: &NaiveDateTime) -> Result<Vec<Promotion>, String> {
+    let mut stmt = conn.prepare(
+        &format!("SELECT {} FROM promotions WHERE active = 1 ORDER BY id", PROMOTION_COLUMNS)
+    ).map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+    let rows = stmt.query_map([], map_promotion)
+        .map_err(|e| format!("Failed to query promotions: {}", e))?;
+
+    let promotions = rows.collect::<Result<Vec<_>>>()
+        .map_err(|e| format!("Failed to collect promotions: {}", e))?;
+
+    Ok(promotions.into_iter().filter(|p| is_running(p, now)).collect())
+}
+
+/// Work out the discounts of an order's lines, given their unit prices.
+///
+/// Each line gets the best running promotion that covers it, then the cashier's line
+/// discount on what is left. An order discount comes last and is split over the lines
+/// by their remaining value, so every discount is recorded against a line.
+pub fn apply_discounts(
+    conn: &Connection,
+    items: &[NewOrderItem],
+    unit_prices: &[f64],
+    order_discount: Option<&DiscountRequest>,
+) -> Result<Vec<AppliedDiscount>, String> {
+    let now = chrono::Local::now().naive_local();
+    let promotions = load_running_promotions(conn, &now)?;
+
+    let mut subtrees = HashMap::new();
+    for category_id in promotions.iter().filter_map(|p| p.category_id) {
+        if let Entry::Vacant(entry) = subtrees.entry(category_id) {
+            entry.insert(category_subtree(conn, category_id)?);
+        }
+    }
+
+    let mut applied = Vec::new();
+    let mut remaining = Vec::with_capacity(items.len());
+
+    for (line, (item, unit_price)) in items.iter().zip(unit_prices).enumerate() {
+        let mut net = round_cents(item.quantity * unit_price);
+
+        if !promotions.is_empty() {
+            let category_id: i32 = conn.query_row(
+                "SELECT category_id FROM products WHERE id = ?1",
+                params![item.product_id],
+                |row| row.get(0),
+            ).map_err(|e| format!("Failed to get product {}: {}", item.product_id, e))?;
+
+            let best = promotions
+                .iter()
+                .map(|p| (p, promotion_amount(p, &subtrees, item.product_id, category_id, item.quantity, *unit_price)))
+                .filter(|(_, amount)| *amount > 0.0)
+                .max_by(|a, b| a.1.total_cmp(&b.1));
+
+            if let Some((promotion, amount)) = best {
+                let amount = amount.min(net);
+                net = round_cents(net - amount);
+                applied.push(AppliedDiscount {
+                    line,
+                    source: SOURCE_PROMOTION.to_string(),
+                    kind: promotion.kind.clone(),
+                    promotion_id: Some(promotion.id),
+                    description: promotion.name.clone(),
+                    amount,
+                    reason: None,
+                    id_number: None,
+                });
+            }
+        }
+
+        if let Some(discount) = &item.discount {
+            validate_discount(discount)?;
+            let amount = discount_amount(discount, net);
+            if amount > 0.0 {
+                net = round_cents(net - amount);
+                applied.push(AppliedDiscount {
+                    line,
+                    source: SOURCE_LINE.to_string(),
+                    kind: discount.kind.clone(),
+                    promotion_id: None,
+                    description: describe_discount(discount),
+                    amount,
+                    reason: discount.reason.clone(),
+                    id_number: discount.id_number.clone(),
+                });
+            }
+        }
+
+        remaining.push(net);
+    }
+
+    if let Some(discount) = order_discount {
+        validate_discount(discount)?;
+        let base: f64 = remaining.iter().sum();
+        let total = discount_amount(discount, base);
+        let last = remaining.iter().rposition(|net| *net > 0.0);
+
+        let mut given = 0.0;
+        for (line, net) in remaining.iter().enumerate() {
+            if *net <= 0.0 {
+                continue;
+            }
+            // The last line takes whatever rounding left over
+            let share = if Some(line) == last {
+                round_cents(total - given)
+            } else {
+                round_cents(total * net / base)
+            };
+            given += share;
+            if share > 0.0 {
+                applied.push(AppliedDiscount {
+                    line,
+                    source: SOURCE_ORDER.to_string(),
+                    kind: discount.kind.clone(),
+                    promotion_id: None,
+                    description: describe_discount(discount),
+                    amount: share,
+                    reason: discount.reason.clone(),
+                    id_number: discount.id_number.clone(),
+                });
+            }
+        }
+    }
+
+    Ok(applied)
+}
+
+/// Total discount of each line from the discounts applied to the order
+pub fn line_discounts(discounts: &[AppliedDiscount], lines: usize) -> Vec<f64> {
+    let mut totals = vec![0.0; lines];
+    for discount in discounts {
+        totals[discount.line] = round_cents(totals[discount.line] + discount.amount);
+    }
+    totals
+}
+
+/// Save the discounts of a new order against the order items they were given on
+pub fn record_discounts(conn: &Connection, order_id: i32, item_ids: &[i64], discounts: &[AppliedDiscount]) -> Result<(), String> {
+    let created_at = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+
+    for discount in discounts {
+        conn.execute(
+            "INSERT INTO order_discounts (order_id, order_item_id, source, kind, promotion_id, description, amount, reason, id_number, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                order_id,
+                item_ids[discount.line],
+                discount.source,
+                discount.kind,
+                discount.promotion_id,
+                discount.description,
+                discount.amount,
+                discount.reason,
+                discount.id_number,
+                created_at
+            ],
+        ).map_err(|e| format!("Failed to record discount: {}", e))?;
+    }
+
+    Ok(())
+}
+
+pub fn load_order_discounts(conn: &Connection, order_id: i32) -> Result<Vec<OrderDiscount>, String> {
+    let mut stmt = conn.prepare(
+        "SELECT id, order_id, order_item_id, source, kind, promotion_id, description, amount, reason, id_number, created_at
+         FROM order_discounts WHERE order_id = ?1 ORDER BY id"
+    ).map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+    let rows = stmt.query_map(params![order_id], |row| {
+        Ok(OrderDiscount {
+            id: row.get(0)?,
+            order_id: row.get(1)?,
+            order_item_id: row.get(2)?,
+            source: row.get(3)?,
+            kind: row.get(4)?,
+            promotion_id: row.get(5)?,
+            description: row.get(6)?,
+            amount: row.get(7)?,
+            reason: row.get(8)?,
+            id_number: row.get(9)?,
+            created_at: row.get(10)?,
+        })
+    }).map_err(|e| format!("Failed to query order discounts: {}", e))?;
+
+    rows.collect::<Result<Vec<_>>>()
+        .map_err(|e| format!("Failed to collect order discounts: {}", e))
+}
+
+#[tauri::command]
+pub fn get_promotions(state: tauri::State<DbState>, running_only: Option<bool>) -> Result<Vec<Promotion>, String> {
+    let conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+
+    if running_only.unwrap_or(false) {
+        return load_running_promotions(&conn, &chrono::Local::now().naive_local());
+    }
+
+    let mut stmt = conn.prepare(
+        &format!("SELECT {} FROM promotions ORDER BY active DESC, name", PROMOTION_COLUMNS)
+    ).map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+    let rows = stmt.query_map([], map_promotion)
+        .map_err(|e| format!("Failed to query promotions: {}", e))?;
+
+    rows.collect::<Result<Vec<_>>>()
+        .map_err(|e| format!("Failed to collect promotions: {}", e))
+}
+
+fn load_promotion(conn: &Connection, id: i32) -> Result<Promotion, String> {
+    conn.query_row(
+        &format!("SELECT {} FROM promotions WHERE id = ?1", PROMOTION_COLUMNS),
+        params![id],
+        map_promotion,
+    ).map_err(|e| format!("Failed to get promotion {}: {}", id, e))
+}
+
+#[tauri::command]
+pub fn add_promotion(state: tauri::State<DbState>, promotion: NewPromotion, token: String) -> Result<Promotion, String> {
+    println!("Backend: Adding {} promotion {}", promotion.kind, promotion.name);
+    validate_promotion(&promotion)?;
+
+    let mut conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let actor = authenticate(&conn, &token)?;
+    require_permission(&conn, &actor, MANAGE_SETTINGS, "add promotions")?;
+    let tx = conn.transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    tx.execute(
+        "INSERT INTO promotions (name, kind, percent, product_id, category_id, buy_quantity, get_quantity,
+                                 start_time, end_time, starts_on, ends_on, active)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+        params![
+            promotion.name.trim(),
+            promotion.kind,
+            promotion.percent,
+            promotion.product_id,
+            promotion.category_id,
+            promotion.buy_quantity,
+            promotion.get_quantity,
+            promotion.start_time,
+            promotion.end_time,
+            promotion.starts_on,
+            promotion.ends_on,
+            promotion.active
+        ],
+    ).map_err(|e| format!("Failed to insert promotion: {}", e))?;
+
+    let id = tx.last_insert_rowid();
+    let after = snapshot(&tx, "promotions", id)?;
+    record_audit(&tx, Some(&actor), CREATE, "promotion", Some(id), None, after)?;
+
+    let created = load_promotion(&tx, id as i32)?;
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    Ok(created)
+}
+
+#[tauri::command]
+pub fn update_promotion(state: tauri::State<DbState>, update: UpdatePromotion, token: String) -> Result<Promotion, String> {
+    println!("Backend: Updating promotion {}", update.id);
+    let promotion = &update.promotion;
+    validate_promotion(promotion)?;
+
+    let mut conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let actor = authenticate(&conn, &token)?;
+    require_permission(&conn, &actor, MANAGE_SETTINGS, "change promotions")?;
+    let tx = conn.transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let before = snapshot(&tx, "promotions", update.id as i64)?;
+    let updated = tx.execute(
+        "UPDATE promotions SET name = ?1, kind = ?2, percent = ?3, product_id = ?4, category_id = ?5,
+                buy_quantity = ?6, get_quantity = ?7, start_time = ?8, end_time = ?9, starts_on = ?10,
+                ends_on = ?11, active = ?12
+         WHERE id = ?13",
+        params![
+            promotion.name.trim(),
+            promotion.kind,
+            promotion.percent,
+            promotion.product_id,
+            promotion.category_id,
+            promotion.buy_quantity,
+            promotion.get_quantity,
+            promotion.start_time,
+            promotion.end_time,
+            promotion.starts_on,
+            promotion.ends_on,
+            promotion.active,
+            update.id
+        ],
+    ).map_err(|e| format!("Failed to update promotion: {}", e))?;
+
+    if updated == 0 {
+        return Err("Promotion not found".to_string());
+    }
+
+    let after = snapshot(&tx, "promotions", update.id as i64)?;
+    record_audit(&tx, Some(&actor), UPDATE, "promotion", Some(update.id as i64), before, after)?;
+
+    let updated = load_promotion(&tx, update.id)?;
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    Ok(updated)
+}
+
+// Past orders keep the discounts they were given; only the promotion itself goes
+#[tauri::command]
+pub fn delete_promotion(state: tauri::State<DbState>, id: i32, token: String) -> Result<(), String> {
+    println!("Backend: Deleting promotion {}", id);
+    let mut conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let actor = authenticate(&conn, &token)?;
+    require_permission(&conn, &actor, MANAGE_SETTINGS, "delete promotions")?;
+    let tx = conn.transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let before = snapshot(&tx, "promotions", id as i64)?;
+
+    let deleted = tx.execute("DELETE FROM promotions WHERE id = ?1", params![id])
+        .map_err(|e| format!("Failed to delete promotion: {}", e))?;
+
+    if deleted == 0 {
+        return Err("Promotion not found".to_string());
+    }
+
+    record_audit(&tx, Some(&actor), DELETE, "promotion", Some(id as i64), before, None)?;
+
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    Ok(())
+}
@@ -0,0 +1,328 @@
+use crate::commands::audit::{record_audit, snapshot};
+use crate::commands::auth::authenticate;
+use crate::commands::customer::check_order_customer;
+use crate::commands::notification::emit_stock_alerts;
+use crate::commands::price::apply_due_price_changes;
+use crate::commands::role::require_permission;
+use crate::commands::transaction::{get_order_by_id, insert_order, price_order, CreateOrderRequest};
+use crate::db::models::audit::{CREATE, UPDATE};
+use crate::db::models::held_order::{
+    HeldOrder, HoldOrderRequest, ResumedOrder, CANCELLED, DEFAULT_HOLD_MINUTES, EXPIRED, FINALIZED, HELD,
+};
+use crate::db::models::order::{NewOrder, Order};
+use crate::db::models::payment::NewPayment;
+use crate::db::models::role::SELL;
+use crate::db::DbState;
+use rusqlite::types::Type;
+use rusqlite::{params, Connection, Result};
+use serde::de::DeserializeOwned;
+
+const HELD_COLUMNS: &str = "id, cashier, label, items, discount, subtotal, tax, total, status, order_id, created_at,
+    updated_at, expires_at, customer_id";
+
+fn from_json<T: DeserializeOwned>(index: usize, text: &str) -> Result<T> {
+    serde_json::from_str(text).map_err(|e| rusqlite::Error::FromSqlConversionFailure(index, Type::Text, Box::new(e)))
+}
+
+fn map_held_order(row: &rusqlite::Row) -> Result<HeldOrder> {
+    let items: String = row.get(3)?;
+    let discount: Option<String> = row.get(4)?;
+
+    Ok(HeldOrder {
+        id: row.get(0)?,
+        cashier: row.get(1)?,
+        label: row.get(2)?,
+        customer_id: row.get(13)?,
+        items: from_json(3, &items)?,
+        discount: discount.map(|d| from_json(4, &d)).transpose()?,
+        subtotal: row.get(5)?,
+        tax: row.get(6)?,
+        total: row.get(7)?,
+        status: row.get(8)?,
+        order_id: row.get(9)?,
+        created_at: row.get(10)?,
+        updated_at: row.get(11)?,
+        expires_at: row.get(12)?,
+    })
+}
+
+fn now() -> chrono::NaiveDateTime {
+    chrono::Local::now().naive_local()
+}
+
+fn timestamp(time: chrono::NaiveDateTime) -> String {
+    time.format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+/// Mark held carts past their expiry as expired so they drop off the register's list
+pub fn expire_held_orders(conn: &Connection) -> Result<usize, String> {
+    let current_time = timestamp(now());
+    let expired = conn.execute(
+        "UPDATE held_orders SET status = ?1, updated_at = ?2 WHERE status = ?3 AND expires_at < ?2",
+        params![EXPIRED, current_time, HELD],
+    ).map_err(|e| format!("Failed to expire held orders: {}", e))?;
+
+    if expired > 0 {
+        println!("Backend: Expired {} held orders", expired);
+    }
+    Ok(expired)
+}
+
+fn load_held_order(conn: &Connection, id: i32) -> Result<HeldOrder, String> {
+    conn.query_row(
+        &format!("SELECT {} FROM held_orders WHERE id = ?1", HELD_COLUMNS),
+        params![id],
+        map_held_order,
+    ).map_err(|e| format!("Failed to get held order {}: {}", id, e))
+}
+
+// A held cart can only be picked up again while it is still held
+fn load_open_held_order(conn: &Connection, id: i32) -> Result<HeldOrder, String> {
+    expire_held_orders(conn)?;
+    let held = load_held_order(conn, id)?;
+    if held.status != HELD {
+        return Err(format!("Held order {} is {}", held.id, held.status));
+    }
+    Ok(held)
+}
+
+fn cart_request(held: HeldOrder, payments: Vec<NewPayment>, shift_id: Option<i32>, terminal: Option<String>) -> CreateOrderRequest {
+    CreateOrderRequest {
+        order: NewOrder {
+            cashier: held.cashier,
+            subtotal: held.subtotal,
+            tax: held.tax,
+            total: held.total,
+            status: "completed".to_string(),
+        },
+        items: held.items,
+        payments,
+        shift_id,
+        discount: held.discount,
+        terminal,
+        idempotency_key: None,
+        customer_id: held.customer_id,
+    }
+}
+
+// Check a cart prices cleanly (stock units, variants, modifiers, discounts) before keeping it;
+// returns when the held cart will expire
+fn validate_cart(conn: &Connection, request: &HoldOrderRequest) -> Result<String, String> {
+    if request.items.is_empty() {
+        return Err("A held order needs at least one item".to_string());
+    }
+    let minutes = request.expires_in_minutes.unwrap_or(DEFAULT_HOLD_MINUTES);
+    if minutes <= 0 {
+        return Err("Expiry must be in the future".to_string());
+    }
+
+    let cart = CreateOrderRequest {
+        order: NewOrder {
+            cashier: request.cashier.clone(),
+            subtotal: request.subtotal,
+            tax: request.tax,
+            total: request.total,
+            status: HELD.to_string(),
+        },
+        items: request.items.clone(),
+        payments: Vec::new(),
+        shift_id: None,
+        discount: request.discount.clone(),
+        terminal: None,
+        idempotency_key: None,
+        customer_id: request.customer_id,
+    };
+    check_order_customer(conn, request.customer_id)?;
+    price_order(conn, &cart)?;
+
+    Ok(timestamp(now() + chrono::Duration::minutes(minutes)))
+}
+
+fn cart_json(request: &HoldOrderRequest) -> Result<(String, Option<String>), String> {
+    let items = serde_json::to_string(&request.items).map_err(|e| format!("Failed to store held items: {}", e))?;
+    let discount = request.discount.as_ref()
+        .map(serde_json::to_string)
+        .transpose()
+        .map_err(|e| format!("Failed to store held discount: {}", e))?;
+    Ok((items, discount))
+}
+
+#[tauri::command]
+pub fn hold_order(state: tauri::State<DbState>, request: HoldOrderRequest, token: String) -> Result<HeldOrder, String> {
+    println!("Backend: Holding order {:?} for {}", request.label, request.cashier);
+    let mut conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let actor = authenticate(&conn, &token)?;
+    require_permission(&conn, &actor, SELL, "hold orders")?;
+    let tx = conn.transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let expires_at = validate_cart(&tx, &request)?;
+    let (items, discount) = cart_json(&request)?;
+    let current_time = timestamp(now());
+
+    tx.execute(
+        "INSERT INTO held_orders (cashier, label, items, discount, subtotal, tax, total, status, created_at, updated_at, expires_at,
+                                  customer_id)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?9, ?10, ?11)",
+        params![
+            request.cashier,
+            request.label,
+            items,
+            discount,
+            request.subtotal,
+            request.tax,
+            request.total,
+            HELD,
+            current_time,
+            expires_at,
+            request.customer_id
+        ],
+    ).map_err(|e| format!("Failed to hold order: {}", e))?;
+
+    let id = tx.last_insert_rowid();
+    let after = snapshot(&tx, "held_orders", id)?;
+    record_audit(&tx, Some(&actor), CREATE, "held_order", Some(id), None, after)?;
+
+    let held = load_held_order(&tx, id as i32)?;
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    Ok(held)
+}
+
+#[tauri::command]
+pub fn get_held_orders(state: tauri::State<DbState>, cashier: Option<String>) -> Result<Vec<HeldOrder>, String> {
+    let conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    expire_held_orders(&conn)?;
+
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM held_orders WHERE status = ?1 AND (?2 IS NULL OR cashier = ?2) ORDER BY created_at",
+        HELD_COLUMNS
+    )).map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+    let rows = stmt.query_map(params![HELD, cashier], map_held_order)
+        .map_err(|e| format!("Failed to query held orders: {}", e))?;
+
+    rows.collect::<Result<Vec<_>>>()
+        .map_err(|e| format!("Failed to collect held orders: {}", e))
+}
+
+// Bring a held cart back to the register; it stays held until it is finalized
+#[tauri::command]
+pub fn resume_held_order(state: tauri::State<DbState>, id: i32) -> Result<ResumedOrder, String> {
+    let conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+
+    let held = load_open_held_order(&conn, id)?;
+    let (_, priced) = price_order(&conn, &cart_request(held.clone(), Vec::new(), None, None))?;
+
+    Ok(ResumedOrder { held, priced })
+}
+
+#[tauri::command]
+pub fn update_held_order(state: tauri::State<DbState>, id: i32, request: HoldOrderRequest, token: String) -> Result<HeldOrder, String> {
+    println!("Backend: Updating held order {}", id);
+    let mut conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let actor = authenticate(&conn, &token)?;
+    require_permission(&conn, &actor, SELL, "change held orders")?;
+    let tx = conn.transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    load_open_held_order(&tx, id)?;
+    let expires_at = validate_cart(&tx, &request)?;
+    let (items, discount) = cart_json(&request)?;
+
+    let before = snapshot(&tx, "held_orders", id as i64)?;
+    tx.execute(
+        "UPDATE held_orders SET cashier = ?1, label = ?2, items = ?3, discount = ?4, subtotal = ?5, tax = ?6,
+                total = ?7, updated_at = ?8, expires_at = ?9, customer_id = ?10
+         WHERE id = ?11",
+        params![
+            request.cashier,
+            request.label,
+            items,
+            discount,
+            request.subtotal,
+            request.tax,
+            request.total,
+            timestamp(now()),
+            expires_at,
+            request.customer_id,
+            id
+        ],
+    ).map_err(|e| format!("Failed to update held order: {}", e))?;
+
+    let after = snapshot(&tx, "held_orders", id as i64)?;
+    record_audit(&tx, Some(&actor), UPDATE, "held_order", Some(id as i64), before, after)?;
+
+    let held = load_held_order(&tx, id)?;
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    Ok(held)
+}
+
+/// Turn a held cart into an order, taking its items out of stock in the same transaction
+#[tauri::command]
+pub fn finalize_held_order(
+    app: tauri::AppHandle,
+    state: tauri::State<DbState>,
+    id: i32,
+    payments: Vec<NewPayment>,
+    shift_id: Option<i32>,
+    terminal: Option<String>,
+    token: String,
+) -> Result<Order, String> {
+    println!("Backend: Finalizing held order {}", id);
+
+    let (order_id, alerts) = {
+        let mut conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+        let actor = authenticate(&conn, &token)?;
+        require_permission(&conn, &actor, SELL, "sell")?;
+        apply_due_price_changes(&mut conn)?;
+        let tx = conn.transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+        // A retried finalize gets the order the first attempt created
+        if let Some(order_id) = load_held_order(&tx, id)?.order_id {
+            drop(tx);
+            drop(conn);
+            return get_order_by_id(state, order_id);
+        }
+
+        let held = load_open_held_order(&tx, id)?;
+        let (order_id, alerts) = insert_order(&tx, &cart_request(held, payments, shift_id, terminal), &actor)?;
+
+        let before = snapshot(&tx, "held_orders", id as i64)?;
+        tx.execute(
+            "UPDATE held_orders SET status = ?1, order_id = ?2, updated_at = ?3 WHERE id = ?4",
+            params![FINALIZED, order_id, timestamp(now()), id],
+        ).map_err(|e| format!("Failed to finalize held order: {}", e))?;
+
+        let after = snapshot(&tx, "held_orders", id as i64)?;
+        record_audit(&tx, Some(&actor), UPDATE, "held_order", Some(id as i64), before, after)?;
+
+        let after = snapshot(&tx, "orders", order_id as i64)?;
+        record_audit(&tx, Some(&actor), CREATE, "order", Some(order_id as i64), None, after)?;
+
+        tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+        (order_id, alerts)
+    };
+
+    emit_stock_alerts(&app, &alerts);
+    get_order_by_id(state, order_id)
+}
+
+#[tauri::command]
+pub fn cancel_held_order(state: tauri::State<DbState>, id: i32, token: String) -> Result<(), String> {
+    println!("Backend: Cancelling held order {}", id);
+    let mut conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let actor = authenticate(&conn, &token)?;
+    require_permission(&conn, &actor, SELL, "cancel held orders")?;
+    let tx = conn.transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    load_open_held_order(&tx, id)?;
+    let before = snapshot(&tx, "held_orders", id as i64)?;
+    tx.execute(
+        "UPDATE held_orders SET status = ?1, updated_at = ?2 WHERE id = ?3",
+        params![CANCELLED, timestamp(now()), id],
+    ).map_err(|e| format!("Failed to cancel held order: {}", e))?;
+
+    let after = snapshot(&tx, "held_orders", id as i64)?;
+    record_audit(&tx, Some(&actor), UPDATE, "held_order", Some(id as i64), before, after)?;
+
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    Ok(())
+}
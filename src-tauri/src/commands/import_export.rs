@@ -20,7 +20,7 @@ pub struct SalesSummary {
     pub total_sales: f64,
     pub total_revenue: f64,
     pub total_profit: f64,
-    pub items_sold: i64,
+    pub items_sold: f64,
     pub transactions: i64,
 }
 
@@ -0,0 +1,147 @@
+use crate::commands::audit::{record_audit, snapshot};
+use crate::commands::auth::authenticate;
+use crate::commands::barcode::ensure_code_available;
+use crate::commands::product::load_product_with_category;
+use crate::commands::role::require_permission;
+use crate::db::models::audit::UPDATE;
+use crate::db::models::label::{AssignedBarcode, LabelRequest, LabelSheet};
+use crate::db::models::role::EDIT_INVENTORY;
+use crate::db::DbState;
+use crate::printing::barcode::{ean13_check_digit, is_valid_ean13, Symbology};
+use crate::printing::labels::{self, Label};
+use rusqlite::{params, Connection};
+use std::path::Path;
+
+/// Give a product an internal EAN-13 barcode and store it on the product.
+///
+/// Internal codes use the 20-29 restricted-circulation prefixes reserved for in-store
+/// numbering, followed by the zero-padded product id and the check digit.
+pub fn assign_internal_barcode(conn: &Connection, product_id: i32) -> Result<String, String> {
+    for prefix in 20..=29 {
+        let digits = format!("{}{:010}", prefix, product_id);
+        let code = format!("{}{}", digits, ean13_check_digit(&digits)?);
+
+        if ensure_code_available(conn, &code, None).is_err() {
+            continue;
+        }
+
+        conn.execute(
+            "UPDATE products SET barcode = ?1, updated_at = datetime('now') WHERE id = ?2",
+            params![code, product_id],
+        ).map_err(|e| format!("Failed to assign barcode: {}", e))?;
+
+        println!("Backend: Assigned internal barcode {} to product {}", code, product_id);
+        return Ok(code);
+    }
+
+    Err(format!("No free internal barcode for product {}", product_id))
+}
+
+// With SVG each sheet is its own file, numbered when there is more than one
+fn sheet_path(path: &Path, page: usize, pages: usize) -> String {
+    if pages == 1 {
+        return path.to_string_lossy().to_string();
+    }
+
+    let stem = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let extension = path.extension().map(|e| e.to_string_lossy().to_string()).unwrap_or_else(|| "svg".to_string());
+    path.with_file_name(format!("{}-{}.{}", stem, page + 1, extension))
+        .to_string_lossy()
+        .to_string()
+}
+
+#[tauri::command]
+pub fn generate_labels(state: tauri::State<DbState>, request: LabelRequest, token: String) -> Result<LabelSheet, String> {
+    println!("Backend: Generating {} labels for {} products", request.format, request.product_ids.len());
+
+    if request.product_ids.is_empty() {
+        return Err("Select at least one product".to_string());
+    }
+
+    let format = request.format.to_lowercase();
+    if format != "pdf" && format != "svg" {
+        return Err(format!("Unsupported label format: {}", request.format));
+    }
+
+    // Validate path directory exists
+    let path = Path::new(&request.path);
+    if let Some(parent) = path.parent() {
+        if !parent.as_os_str().is_empty() && !parent.exists() {
+            return Err("Invalid export path".into());
+        }
+    }
+
+    let symbology = request.symbology.as_deref().map(Symbology::parse).transpose()?;
+    let template = request.template.clone().unwrap_or_default();
+    let copies = request.copies.unwrap_or(1).max(1);
+    let assign_missing = request.assign_missing.unwrap_or(true);
+
+    let mut conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let actor = authenticate(&conn, &token)?;
+    require_permission(&conn, &actor, EDIT_INVENTORY, "print labels")?;
+    let tx = conn.transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let mut sheet_labels = Vec::new();
+    let mut assigned = Vec::new();
+
+    for &product_id in &request.product_ids {
+        let product = load_product_with_category(&tx, product_id)?;
+
+        let code = match product.barcode.as_deref().map(str::trim).filter(|b| !b.is_empty()) {
+            Some(code) => code.to_string(),
+            None if assign_missing => {
+                let before = snapshot(&tx, "products", product_id as i64)?;
+                let code = assign_internal_barcode(&tx, product_id)?;
+                let after = snapshot(&tx, "products", product_id as i64)?;
+                record_audit(&tx, Some(&actor), UPDATE, "product", Some(product_id as i64), before, after)?;
+                assigned.push(AssignedBarcode { product_id, barcode: code.clone() });
+                code
+            }
+            None => return Err(format!("{} has no barcode", product.name)),
+        };
+
+        let label_symbology = match symbology {
+            Some(Symbology::Ean13) if !is_valid_ean13(&code) => {
+                return Err(format!("{} has barcode {}, which is not a valid EAN-13", product.name, code));
+            }
+            Some(symbology) => symbology,
+            None if is_valid_ean13(&code) => Symbology::Ean13,
+            None => Symbology::Code128,
+        };
+
+        for _ in 0..copies {
+            sheet_labels.push(Label {
+                name: product.name.clone(),
+                price: format!("PHP {:.2}", product.unit_price),
+                code: code.clone(),
+                symbology: label_symbology,
+            });
+        }
+    }
+
+    let (files, pages) = if format == "pdf" {
+        let (bytes, pages) = labels::render_pdf(&template, &sheet_labels)?;
+        std::fs::write(path, bytes).map_err(|e| format!("Failed to write label file: {}", e))?;
+        (vec![request.path.clone()], pages)
+    } else {
+        let sheets = labels::render_svg(&template, &sheet_labels)?;
+        let mut files = Vec::new();
+        for (i, svg) in sheets.iter().enumerate() {
+            let file = sheet_path(path, i, sheets.len());
+            std::fs::write(&file, svg).map_err(|e| format!("Failed to write label file: {}", e))?;
+            files.push(file);
+        }
+        (files, sheets.len())
+    };
+
+    // Only keep newly assigned barcodes once the labels carrying them were written
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+    println!("Backend: Wrote {} labels on {} sheets", sheet_labels.len(), pages);
+    Ok(LabelSheet {
+        files,
+        labels: sheet_labels.len(),
+        pages,
+        assigned,
+    })
+}
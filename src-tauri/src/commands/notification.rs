@@ -0,0 +1,286 @@
+use crate::commands::auth::authenticate;
+use crate::db::models::notification::{Notification, NotificationQuery, LOW_STOCK, OUT_OF_STOCK};
+use crate::db::DbState;
+use rusqlite::{params, Connection, OptionalExtension, Result};
+use tauri::Emitter;
+
+// Name of the event the UI listens to for new stock alerts
+pub const STOCK_ALERT_EVENT: &str = "stock-alert";
+
+// Variant alerts are named after the product and the variant
+const NOTIFICATION_COLUMNS: &str =
+    "n.id, n.product_id,
+     COALESCE(p.name || CASE WHEN n.variant_id IS NULL THEN '' ELSE ' (' || v.name || ')' END, n.product_name),
+     n.kind, n.message, n.stock_level, n.minimum_stock, n.created_at, n.resolved_at,
+     s.read_at IS NOT NULL, s.dismissed_at IS NOT NULL, n.variant_id";
+
+fn map_notification(row: &rusqlite::Row) -> Result<Notification> {
+    Ok(Notification {
+        id: row.get(0)?,
+        product_id: row.get(1)?,
+        product_name: row.get(2)?,
+        kind: row.get(3)?,
+        message: row.get(4)?,
+        stock_level: row.get(5)?,
+        minimum_stock: row.get(6)?,
+        created_at: row.get(7)?,
+        resolved_at: row.get(8)?,
+        is_read: row.get(9)?,
+        is_dismissed: row.get(10)?,
+        variant_id: row.get(11)?,
+    })
+}
+
+/// Re-evaluate the stock thresholds of the given products.
+///
+/// Opens a notification when a product crosses into low or out of stock, and resolves
+/// open notifications that no longer apply. At most one open notification exists per
+/// product and kind, so repeated sales do not repeat the alert. Returns the newly opened
+/// notifications so the caller can emit them once its transaction has committed.
+pub fn evaluate_stock_alerts(conn: &Connection, product_ids: &[i32]) -> Result<Vec<Notification>, String> {
+    let mut opened = Vec::new();
+
+    for &product_id in product_ids {
+        let product = conn.query_row(
+            "SELECT name, current_stock, minimum_stock, archived_at IS NOT NULL FROM products WHERE id = ?1",
+            params![product_id],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, f64>(1)?, row.get::<_, f64>(2)?, row.get::<_, bool>(3)?)),
+        ).optional().map_err(|e| format!("Failed to read product stock: {}", e))?;
+
+        if let Some((name, current_stock, minimum_stock, archived)) = product {
+            opened.extend(update_stock_alert(conn, product_id, None, name, current_stock, minimum_stock, archived)?);
+        }
+    }
+
+    Ok(opened)
+}
+
+/// Re-evaluate the stock thresholds of the given variants, which keep their own stock.
+/// Works like `evaluate_stock_alerts`, with one open notification per variant and kind.
+pub fn evaluate_variant_stock_alerts(conn: &Connection, variant_ids: &[i32]) -> Result<Vec<Notification>, String> {
+    let mut opened = Vec::new();
+
+    for &variant_id in variant_ids {
+        let variant = conn.query_row(
+            "SELECT v.product_id, p.name || ' (' || v.name || ')', v.current_stock, v.minimum_stock, p.archived_at IS NOT NULL
+             FROM product_variants v JOIN products p ON p.id = v.product_id
+             WHERE v.id = ?1",
+            params![variant_id],
+            |row| Ok((row.get::<_, i32>(0)?, row.get::<_, String>(1)?, row.get::<_, f64>(2)?, row.get::<_, f64>(3)?, row.get::<_, bool>(4)?)),
+        ).optional().map_err(|e| format!("Failed to read variant stock: {}", e))?;
+
+        if let Some((product_id, name, current_stock, minimum_stock, archived)) = variant {
+            opened.extend(update_stock_alert(conn, product_id, Some(variant_id), name, current_stock, minimum_stock, archived)?);
+        }
+    }
+
+    Ok(opened)
+}
+
+// Open or resolve the alert of one product, or of one of its variants, for its current stock level
+fn update_stock_alert(
+    conn: &Connection,
+    product_id: i32,
+    variant_id: Option<i32>,
+    name: String,
+    current_stock: f64,
+    minimum_stock: f64,
+    archived: bool,
+) -> Result<Option<Notification>, String> {
+    // Archived products are off the shelf, so their stock level no longer matters
+    let kind = if archived {
+        None
+    } else if current_stock <= 0.0 {
+        Some(OUT_OF_STOCK)
+    } else if current_stock <= minimum_stock {
+        Some(LOW_STOCK)
+    } else {
+        None
+    };
+
+    // Close any open alert that no longer matches the stock level
+    conn.execute(
+        "UPDATE notifications SET resolved_at = datetime('now')
+         WHERE product_id = ?1 AND variant_id IS ?2 AND resolved_at IS NULL AND (?3 IS NULL OR kind != ?3)",
+        params![product_id, variant_id, kind],
+    ).map_err(|e| format!("Failed to resolve stock notifications: {}", e))?;
+
+    let kind = match kind {
+        Some(kind) => kind,
+        None => return Ok(None),
+    };
+
+    let already_open: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM notifications WHERE product_id = ?1 AND variant_id IS ?2 AND kind = ?3 AND resolved_at IS NULL",
+        params![product_id, variant_id, kind],
+        |row| row.get(0),
+    ).map_err(|e| format!("Failed to check stock notifications: {}", e))?;
+
+    if already_open > 0 {
+        return Ok(None);
+    }
+
+    let message = if kind == OUT_OF_STOCK {
+        format!("{} is out of stock", name)
+    } else {
+        format!("{} is running low ({} left, minimum {})", name, current_stock, minimum_stock)
+    };
+
+    conn.execute(
+        "INSERT INTO notifications (product_id, variant_id, product_name, kind, message, stock_level, minimum_stock)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![product_id, variant_id, name, kind, message, current_stock, minimum_stock],
+    ).map_err(|e| format!("Failed to insert stock notification: {}", e))?;
+
+    let id = conn.last_insert_rowid() as i32;
+    let created_at: String = conn.query_row(
+        "SELECT created_at FROM notifications WHERE id = ?1",
+        params![id],
+        |row| row.get(0),
+    ).map_err(|e| format!("Failed to read stock notification: {}", e))?;
+
+    println!("Backend: Opened {} notification for product {} (variant {:?})", kind, product_id, variant_id);
+    Ok(Some(Notification {
+        id,
+        product_id,
+        variant_id,
+        product_name: name,
+        kind: kind.to_string(),
+        message,
+        stock_level: current_stock,
+        minimum_stock,
+        created_at,
+        resolved_at: None,
+        is_read: false,
+        is_dismissed: false,
+    }))
+}
+
+// Push newly opened notifications to the UI; a failed emit must not fail the command itself
+pub fn emit_stock_alerts(app: &tauri::AppHandle, notifications: &[Notification]) {
+    if notifications.is_empty() {
+        return;
+    }
+
+    if let Err(e) = app.emit(STOCK_ALERT_EVENT, notifications) {
+        println!("Backend error emitting stock alerts: {}", e);
+    }
+}
+
+#[tauri::command]
+pub fn get_notifications(state: tauri::State<DbState>, token: String, query: Option<NotificationQuery>) -> Result<Vec<Notification>, String> {
+    let conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let user = authenticate(&conn, &token)?;
+
+    let include_dismissed = query.as_ref().and_then(|q| q.include_dismissed).unwrap_or(false);
+    let include_resolved = query.as_ref().and_then(|q| q.include_resolved).unwrap_or(false);
+    let unread_only = query.as_ref().and_then(|q| q.unread_only).unwrap_or(false);
+    let limit = query.as_ref().and_then(|q| q.limit).unwrap_or(100);
+
+    let mut sql = format!(
+        "SELECT {} FROM notifications n
+         LEFT JOIN products p ON n.product_id = p.id
+         LEFT JOIN product_variants v ON n.variant_id = v.id
+         LEFT JOIN notification_states s ON s.notification_id = n.id AND s.user_id = ?1
+         WHERE 1=1",
+        NOTIFICATION_COLUMNS
+    );
+
+    if !include_dismissed {
+        sql.push_str(" AND s.dismissed_at IS NULL");
+    }
+    if !include_resolved {
+        sql.push_str(" AND n.resolved_at IS NULL");
+    }
+    if unread_only {
+        sql.push_str(" AND s.read_at IS NULL");
+    }
+    sql.push_str(" ORDER BY n.created_at DESC, n.id DESC LIMIT ?2");
+
+    let mut stmt = conn.prepare(&sql)
+        .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+    let rows = stmt.query_map(params![user.id, limit], map_notification)
+        .map_err(|e| format!("Failed to query notifications: {}", e))?;
+
+    rows.collect::<Result<Vec<_>>>()
+        .map_err(|e| format!("Failed to collect notifications: {}", e))
+}
+
+#[tauri::command]
+pub fn mark_notification_read(state: tauri::State<DbState>, token: String, id: i32) -> Result<(), String> {
+    let conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let user = authenticate(&conn, &token)?;
+
+    conn.execute(
+        "INSERT INTO notification_states (notification_id, user_id, read_at) VALUES (?1, ?2, datetime('now'))
+         ON CONFLICT (notification_id, user_id) DO UPDATE SET read_at = COALESCE(read_at, excluded.read_at)",
+        params![id, user.id],
+    ).map_err(|e| format!("Failed to mark notification as read: {}", e))?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn mark_all_notifications_read(state: tauri::State<DbState>, token: String) -> Result<usize, String> {
+    let conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let user = authenticate(&conn, &token)?;
+
+    conn.execute(
+        "INSERT INTO notification_states (notification_id, user_id, read_at)
+         SELECT id, ?1, datetime('now') FROM notifications WHERE resolved_at IS NULL
+         ON CONFLICT (notification_id, user_id) DO UPDATE SET read_at = COALESCE(read_at, excluded.read_at)",
+        params![user.id],
+    ).map_err(|e| format!("Failed to mark notifications as read: {}", e))
+}
+
+#[tauri::command]
+pub fn dismiss_notification(state: tauri::State<DbState>, token: String, id: i32) -> Result<(), String> {
+    let conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let user = authenticate(&conn, &token)?;
+
+    conn.execute(
+        "INSERT INTO notification_states (notification_id, user_id, read_at, dismissed_at)
+         VALUES (?1, ?2, datetime('now'), datetime('now'))
+         ON CONFLICT (notification_id, user_id) DO UPDATE SET
+            read_at = COALESCE(read_at, excluded.read_at),
+            dismissed_at = excluded.dismissed_at",
+        params![id, user.id],
+    ).map_err(|e| format!("Failed to dismiss notification: {}", e))?;
+
+    Ok(())
+}
+
+// Re-check every product and variant, e.g. after an import or when the UI first loads
+#[tauri::command]
+pub fn refresh_stock_alerts(app: tauri::AppHandle, state: tauri::State<DbState>) -> Result<Vec<Notification>, String> {
+    let mut conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let tx = conn.transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let product_ids = {
+        let mut stmt = tx.prepare("SELECT id FROM products")
+            .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+        let ids = stmt.query_map([], |row| row.get::<_, i32>(0))
+            .map_err(|e| format!("Failed to query products: {}", e))?
+            .collect::<Result<Vec<_>>>()
+            .map_err(|e| format!("Failed to collect products: {}", e))?;
+        ids
+    };
+
+    let variant_ids = {
+        let mut stmt = tx.prepare("SELECT id FROM product_variants")
+            .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+        let ids = stmt.query_map([], |row| row.get::<_, i32>(0))
+            .map_err(|e| format!("Failed to query variants: {}", e))?
+            .collect::<Result<Vec<_>>>()
+            .map_err(|e| format!("Failed to collect variants: {}", e))?;
+        ids
+    };
+
+    let mut opened = evaluate_stock_alerts(&tx, &product_ids)?;
+    opened.extend(evaluate_variant_stock_alerts(&tx, &variant_ids)?);
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+    emit_stock_alerts(&app, &opened);
+    Ok(opened)
+}
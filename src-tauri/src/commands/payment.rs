@@ -0,0 +1,105 @@
+use crate::db::models::payment::{NewPayment, OrderPayment, ACCOUNT, CASH, PAYMENT_METHODS, WALLET};
+use crate::db::DbState;
+use rusqlite::{params, Connection, Result};
+
+// Amounts are compared to the centavo
+const TOLERANCE: f64 = 0.005;
+
+/// Validate the payments of an order and store them.
+///
+/// The payments' amounts must add up to the order total. Cash may be tendered above its
+/// amount and the difference is recorded as change; card and e-wallet payments need a
+/// reference number and wallet payments the scanned card's code. Account charges and wallet
+/// purchases are settled against the customer by the caller.
+/// Orders from registers that don't send payments are recorded as paid in cash, except
+/// free orders, which get no payment rows at all.
+pub fn record_payments(conn: &Connection, order_id: i32, total: f64, payments: &[NewPayment]) -> Result<Vec<OrderPayment>, String> {
+    if payments.is_empty() && total.abs() < TOLERANCE {
+        return Ok(Vec::new());
+    }
+
+    let legacy_cash = [NewPayment {
+        method: CASH.to_string(),
+        amount: total,
+        reference: None,
+        tendered: None,
+    }];
+    let payments = if payments.is_empty() { &legacy_cash[..] } else { payments };
+
+    let mut paid = 0.0;
+    for payment in payments {
+        let method = payment.method.to_lowercase();
+        if !PAYMENT_METHODS.contains(&method.as_str()) {
+            return Err(format!("Unknown payment method: {}", payment.method));
+        }
+        if !payment.amount.is_finite() || payment.amount <= 0.0 {
+            return Err(format!("Payment amounts must be greater than zero, got {}", payment.amount));
+        }
+
+        let reference = payment.reference.as_deref().map(str::trim).filter(|r| !r.is_empty());
+        let (tendered, change) = if method == CASH {
+            let tendered = payment.tendered.unwrap_or(payment.amount);
+            if tendered + TOLERANCE < payment.amount {
+                return Err(format!("Cash tendered ({:.2}) is less than the amount paid in cash ({:.2})", tendered, payment.amount));
+            }
+            (Some(tendered), tendered - payment.amount)
+        } else {
+            if payment.tendered.is_some() {
+                return Err(format!("Only cash payments can have a tendered amount, not {}", method));
+            }
+            if reference.is_none() && method == WALLET {
+                return Err("Scan the customer's card to pay from their wallet".to_string());
+            }
+            if reference.is_none() && method != ACCOUNT {
+                return Err(format!("A reference number is required for {} payments", method));
+            }
+            (None, 0.0)
+        };
+
+        conn.execute(
+            "INSERT INTO order_payments (order_id, method, amount, reference, tendered, change)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![order_id, method, payment.amount, reference, tendered, change],
+        ).map_err(|e| format!("Failed to insert payment: {}", e))?;
+
+        paid += payment.amount;
+    }
+
+    if paid + TOLERANCE < total {
+        return Err(format!("Payments of {:.2} don't cover the order total of {:.2}", paid, total));
+    }
+    if paid - TOLERANCE > total {
+        return Err(format!("Payments of {:.2} exceed the order total of {:.2}; record the excess as tendered cash", paid, total));
+    }
+
+    load_order_payments(conn, order_id)
+}
+
+pub fn load_order_payments(conn: &Connection, order_id: i32) -> Result<Vec<OrderPayment>, String> {
+    let mut stmt = conn.prepare(
+        "SELECT id, order_id, method, amount, reference, tendered, change, created_at
+         FROM order_payments WHERE order_id = ?1 ORDER BY id"
+    ).map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+    let rows = stmt.query_map(params![order_id], |row| {
+        Ok(OrderPayment {
+            id: row.get(0)?,
+            order_id: row.get(1)?,
+            method: row.get(2)?,
+            amount: row.get(3)?,
+            reference: row.get(4)?,
+            tendered: row.get(5)?,
+            change: row.get(6)?,
+            created_at: row.get(7)?,
+        })
+    }).map_err(|e| format!("Failed to query payments: {}", e))?;
+
+    rows.collect::<Result<Vec<_>>>()
+        .map_err(|e| format!("Failed to collect payments: {}", e))
+}
+
+#[tauri::command]
+pub fn get_order_payments(state: tauri::State<DbState>, order_id: i32) -> Result<Vec<OrderPayment>, String> {
+    let conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    load_order_payments(&conn, order_id)
+}
@@ -0,0 +1,301 @@
+use crate::commands::audit::{record_audit, snapshot};
+use crate::commands::auth::authenticate;
+use crate::commands::role::require_permission;
+use crate::db::models::audit::{CREATE, UPDATE};
+use crate::db::models::price::{
+    NewScheduledPriceChange, PriceChange, PriceTimeline, ScheduledPriceChange, APPLIED, CANCELLED, PENDING,
+};
+use crate::db::models::role::{EDIT_INVENTORY, VIEW_REPORTS};
+use crate::db::DbState;
+use chrono::{NaiveDate, NaiveDateTime};
+use rusqlite::{params, Connection, OptionalExtension, Result};
+
+const HISTORY_COLUMNS: &str = "id, product_id, old_unit_price, new_unit_price, old_price_bought, new_price_bought,
+    user_id, username, reason, scheduled_change_id, changed_at";
+
+const SCHEDULED_QUERY: &str =
+    "SELECT s.id, s.product_id, p.name, s.unit_price, s.price_bought, s.effective_at, s.reason, s.status,
+            s.created_by, s.created_at, s.applied_at
+     FROM scheduled_price_changes s
+     JOIN products p ON p.id = s.product_id";
+
+fn map_price_change(row: &rusqlite::Row) -> Result<PriceChange> {
+    Ok(PriceChange {
+        id: row.get(0)?,
+        product_id: row.get(1)?,
+        old_unit_price: row.get(2)?,
+        new_unit_price: row.get(3)?,
+        old_price_bought: row.get(4)?,
+        new_price_bought: row.get(5)?,
+        user_id: row.get(6)?,
+        username: row.get(7)?,
+        reason: row.get(8)?,
+        scheduled_change_id: row.get(9)?,
+        changed_at: row.get(10)?,
+    })
+}
+
+fn map_scheduled(row: &rusqlite::Row) -> Result<ScheduledPriceChange> {
+    Ok(ScheduledPriceChange {
+        id: row.get(0)?,
+        product_id: row.get(1)?,
+        product_name: row.get(2)?,
+        unit_price: row.get(3)?,
+        price_bought: row.get(4)?,
+        effective_at: row.get(5)?,
+        reason: row.get(6)?,
+        status: row.get(7)?,
+        created_by: row.get(8)?,
+        created_at: row.get(9)?,
+        applied_at: row.get(10)?,
+    })
+}
+
+fn now() -> String {
+    chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+/// A product's current (retail, cost) prices
+pub fn load_prices(conn: &Connection, product_id: i32) -> Result<(f64, f64), String> {
+    conn.query_row(
+        "SELECT unit_price, price_bought FROM products WHERE id = ?1",
+        params![product_id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    ).optional()
+        .map_err(|e| format!("Failed to get product prices: {}", e))?
+        .ok_or_else(|| "Product not found".to_string())
+}
+
+/// Add a product's change from old to new (retail, cost) prices to its price history; nothing
+/// is recorded if neither moved. `changed_by` is the (user id, username) behind the change.
+pub fn record_price_change(
+    conn: &Connection,
+    product_id: i32,
+    old: (f64, f64),
+    new: (f64, f64),
+    changed_by: Option<(i32, &str)>,
+    reason: Option<&str>,
+    scheduled_change_id: Option<i64>,
+) -> Result<(), String> {
+    if old == new {
+        return Ok(());
+    }
+
+    conn.execute(
+        "INSERT INTO price_history (product_id, old_unit_price, new_unit_price, old_price_bought, new_price_bought,
+                                    user_id, username, reason, scheduled_change_id, changed_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        params![
+            product_id,
+            old.0,
+            new.0,
+            old.1,
+            new.1,
+            changed_by.map(|(id, _)| id),
+            changed_by.map(|(_, username)| username),
+            reason.map(str::trim).filter(|r| !r.is_empty()),
+            scheduled_change_id,
+            now()
+        ],
+    ).map_err(|e| format!("Failed to record price change: {}", e))?;
+
+    Ok(())
+}
+
+/// Put scheduled price changes that have come due into effect, oldest first. Called before
+/// prices are read or sold at, the same way held carts are expired when they're listed.
+pub fn apply_due_price_changes(conn: &mut Connection) -> Result<usize, String> {
+    let tx = conn.transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let due = {
+        let mut stmt = tx.prepare(
+            "SELECT id, product_id, unit_price, price_bought, reason, created_by_id, created_by
+             FROM scheduled_price_changes
+             WHERE status = ?1 AND effective_at <= ?2
+             ORDER BY effective_at, id"
+        ).map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let rows = stmt.query_map(params![PENDING, now()], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, i32>(1)?,
+                row.get::<_, Option<f64>>(2)?,
+                row.get::<_, Option<f64>>(3)?,
+                row.get::<_, Option<String>>(4)?,
+                row.get::<_, Option<i32>>(5)?,
+                row.get::<_, Option<String>>(6)?,
+            ))
+        }).map_err(|e| format!("Failed to query scheduled price changes: {}", e))?;
+
+        rows.collect::<Result<Vec<_>>>()
+            .map_err(|e| format!("Failed to collect scheduled price changes: {}", e))?
+    };
+
+    if due.is_empty() {
+        return Ok(0);
+    }
+
+    for (id, product_id, unit_price, price_bought, reason, created_by_id, created_by) in &due {
+        // The product may have been deleted since the change was scheduled
+        let Ok(old) = load_prices(&tx, *product_id) else {
+            tx.execute(
+                "UPDATE scheduled_price_changes SET status = ?1 WHERE id = ?2",
+                params![CANCELLED, id],
+            ).map_err(|e| format!("Failed to cancel scheduled price change: {}", e))?;
+            continue;
+        };
+        let new = (unit_price.unwrap_or(old.0), price_bought.unwrap_or(old.1));
+        let before = snapshot(&tx, "products", *product_id as i64)?;
+
+        tx.execute(
+            "UPDATE products SET unit_price = ?1, price_bought = ?2, updated_at = datetime('now') WHERE id = ?3",
+            params![new.0, new.1, product_id],
+        ).map_err(|e| format!("Failed to apply scheduled price change: {}", e))?;
+
+        let changed_by = created_by_id.zip(created_by.as_deref());
+        let reason = reason.as_deref().unwrap_or("Scheduled price change");
+        record_price_change(&tx, *product_id, old, new, changed_by, Some(reason), Some(*id))?;
+
+        let after = snapshot(&tx, "products", *product_id as i64)?;
+        record_audit(&tx, None, UPDATE, "product", Some(*product_id as i64), before, after)?;
+
+        tx.execute(
+            "UPDATE scheduled_price_changes SET status = ?1, applied_at = ?2 WHERE id = ?3",
+            params![APPLIED, now(), id],
+        ).map_err(|e| format!("Failed to mark scheduled price change applied: {}", e))?;
+    }
+
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    println!("Backend: Applied {} scheduled price changes", due.len());
+    Ok(due.len())
+}
+
+fn load_scheduled(conn: &Connection, id: i64) -> Result<ScheduledPriceChange, String> {
+    conn.query_row(&format!("{} WHERE s.id = ?1", SCHEDULED_QUERY), params![id], map_scheduled)
+        .map_err(|e| format!("Failed to get scheduled price change {}: {}", id, e))
+}
+
+// Accepts a full timestamp, or a date for the start of that day
+fn parse_effective_at(effective_at: &str) -> Result<NaiveDateTime, String> {
+    let effective_at = effective_at.trim();
+    NaiveDateTime::parse_from_str(effective_at, "%Y-%m-%d %H:%M:%S")
+        .or_else(|_| NaiveDateTime::parse_from_str(effective_at, "%Y-%m-%d %H:%M"))
+        .or_else(|_| NaiveDate::parse_from_str(effective_at, "%Y-%m-%d").map(|date| date.and_hms_opt(0, 0, 0).unwrap()))
+        .map_err(|_| format!("Invalid date {}, expected YYYY-MM-DD or YYYY-MM-DD HH:MM:SS", effective_at))
+}
+
+/// A product's current prices, every change made to them and the changes scheduled for later
+#[tauri::command]
+pub fn get_price_history(state: tauri::State<DbState>, product_id: i32, token: String) -> Result<PriceTimeline, String> {
+    let mut conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let user = authenticate(&conn, &token)?;
+    require_permission(&conn, &user, VIEW_REPORTS, "view price history")?;
+    apply_due_price_changes(&mut conn)?;
+
+    let (unit_price, price_bought) = load_prices(&conn, product_id)?;
+
+    let history = {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM price_history WHERE product_id = ?1 ORDER BY changed_at DESC, id DESC",
+            HISTORY_COLUMNS
+        )).map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let rows = stmt.query_map(params![product_id], map_price_change)
+            .map_err(|e| format!("Failed to query price history: {}", e))?;
+
+        rows.collect::<Result<Vec<_>>>()
+            .map_err(|e| format!("Failed to collect price history: {}", e))?
+    };
+
+    let scheduled = {
+        let mut stmt = conn.prepare(&format!(
+            "{} WHERE s.product_id = ?1 AND s.status = ?2 ORDER BY s.effective_at, s.id",
+            SCHEDULED_QUERY
+        )).map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let rows = stmt.query_map(params![product_id, PENDING], map_scheduled)
+            .map_err(|e| format!("Failed to query scheduled price changes: {}", e))?;
+
+        rows.collect::<Result<Vec<_>>>()
+            .map_err(|e| format!("Failed to collect scheduled price changes: {}", e))?
+    };
+
+    Ok(PriceTimeline {
+        product_id,
+        unit_price,
+        price_bought,
+        history,
+        scheduled,
+    })
+}
+
+/// Set a product's retail price and/or cost to change at a future date
+#[tauri::command]
+pub fn schedule_price_change(
+    state: tauri::State<DbState>,
+    token: String,
+    change: NewScheduledPriceChange,
+) -> Result<ScheduledPriceChange, String> {
+    let conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let user = authenticate(&conn, &token)?;
+    println!("Backend: {} scheduling a price change for product {}: {:?}", user.username, change.product_id, change);
+    require_permission(&conn, &user, EDIT_INVENTORY, "schedule price changes")?;
+
+    if change.unit_price.is_none() && change.price_bought.is_none() {
+        return Err("Give a new retail price, a new cost or both".to_string());
+    }
+    if [change.unit_price, change.price_bought].iter().flatten().any(|price| !price.is_finite() || *price < 0.0) {
+        return Err("Prices cannot be negative".to_string());
+    }
+
+    let effective_at = parse_effective_at(&change.effective_at)?;
+    if effective_at <= chrono::Local::now().naive_local() {
+        return Err("Scheduled price changes must take effect in the future".to_string());
+    }
+    load_prices(&conn, change.product_id)?;
+
+    conn.execute(
+        "INSERT INTO scheduled_price_changes (product_id, unit_price, price_bought, effective_at, reason, status,
+                                              created_by_id, created_by, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        params![
+            change.product_id,
+            change.unit_price,
+            change.price_bought,
+            effective_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+            change.reason.as_deref().map(str::trim).filter(|r| !r.is_empty()),
+            PENDING,
+            user.id,
+            user.username,
+            now()
+        ],
+    ).map_err(|e| format!("Failed to schedule price change: {}", e))?;
+    let id = conn.last_insert_rowid();
+
+    let after = snapshot(&conn, "scheduled_price_changes", id)?;
+    record_audit(&conn, Some(&user), CREATE, "scheduled_price_change", Some(id), None, after)?;
+
+    load_scheduled(&conn, id)
+}
+
+#[tauri::command]
+pub fn cancel_scheduled_price_change(state: tauri::State<DbState>, token: String, id: i64) -> Result<(), String> {
+    let conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let user = authenticate(&conn, &token)?;
+    println!("Backend: {} cancelling scheduled price change {}", user.username, id);
+    require_permission(&conn, &user, EDIT_INVENTORY, "cancel price changes")?;
+
+    let before = snapshot(&conn, "scheduled_price_changes", id)?;
+    let updated = conn.execute(
+        "UPDATE scheduled_price_changes SET status = ?1 WHERE id = ?2 AND status = ?3",
+        params![CANCELLED, id, PENDING],
+    ).map_err(|e| format!("Failed to cancel scheduled price change: {}", e))?;
+
+    if updated == 0 {
+        return Err("Scheduled price change not found or no longer pending".to_string());
+    }
+
+    let after = snapshot(&conn, "scheduled_price_changes", id)?;
+    record_audit(&conn, Some(&user), UPDATE, "scheduled_price_change", Some(id), before, after)?;
+    Ok(())
+}
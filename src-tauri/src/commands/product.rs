@@ -1,6 +1,21 @@
-use crate::db::models::product::{Product, NewProduct, ProductWithCategory};
+use crate::commands::audit::{record_audit, snapshot};
+use crate::commands::auth::authenticate;
+use crate::commands::barcode::ensure_code_available;
+use crate::commands::category::{check_category_active, SUBTREE_IDS};
+use crate::commands::notification::{emit_stock_alerts, evaluate_stock_alerts};
+use crate::commands::price::{apply_due_price_changes, load_prices, record_price_change};
+use crate::commands::role::require_permission;
+use crate::db::models::audit::{CREATE, DELETE, UPDATE};
+use crate::db::models::product::{
+    Product, NewProduct, ProductWithCategory, ProductSearch, ProductSearchPage, SORT_CREATED, SORT_NAME, SORT_PRICE,
+    SORT_RELEVANCE, SORT_SKU, SORT_STOCK, SORT_UPDATED, STOCK_LOW, STOCK_OK, STOCK_OUT,
+};
+use crate::db::models::role::EDIT_INVENTORY;
 use crate::db::DbState;
-use rusqlite::{params, Result};
+use base64::{Engine as _, engine::general_purpose};
+use rusqlite::{params, OptionalExtension, Result, ToSql};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 #[derive(Debug, serde::Deserialize)]
 pub struct UpdateProduct {
@@ -10,48 +25,66 @@ pub struct UpdateProduct {
     pub category_id: i32,
     pub unit_price: f64,
     pub price_bought: f64,
-    pub current_stock: i32,
-    pub minimum_stock: Option<i32>,
+    pub current_stock: f64,
+    pub minimum_stock: Option<f64>,
     pub supplier: Option<String>,
     pub thumbnail_url: Option<String>,
     pub barcode: Option<String>,
+    // Kept in the price history when the retail price or cost changes
+    pub price_change_reason: Option<String>,
+}
+
+const PRODUCT_QUERY: &str =
+    "SELECT p.id, p.name, p.description, p.sku, p.category_id, c.name, p.unit_price, p.price_bought,
+            p.current_stock, p.minimum_stock, p.supplier, p.created_at, p.updated_at, p.thumbnailUrl, p.barcode,
+            p.archived_at
+     FROM products p
+     JOIN categories c ON p.category_id = c.id";
+
+pub fn map_product_with_category(row: &rusqlite::Row) -> Result<ProductWithCategory> {
+    Ok(ProductWithCategory {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        description: row.get(2)?,
+        sku: row.get(3)?,
+        category_id: row.get(4)?,
+        category_name: row.get(5)?,
+        unit_price: row.get(6)?,
+        price_bought: row.get(7)?,
+        current_stock: row.get(8)?,
+        minimum_stock: row.get(9)?,
+        supplier: row.get(10)?,
+        created_at: row.get(11)?,
+        updated_at: row.get(12)?,
+        thumbnail_url: row.get(13)?,
+        barcode: row.get(14)?,
+        archived_at: row.get(15)?,
+    })
+}
+
+/// Load a single product together with its category name
+pub fn load_product_with_category(conn: &rusqlite::Connection, id: i32) -> Result<ProductWithCategory, String> {
+    conn.query_row(
+        &format!("{} WHERE p.id = ?1", PRODUCT_QUERY),
+        params![id],
+        map_product_with_category
+    ).map_err(|e| format!("Failed to get product {}: {}", id, e))
 }
 
 #[tauri::command]
 pub fn get_all_products(state: tauri::State<DbState>) -> Result<Vec<ProductWithCategory>, String> {
     println!("Backend: Getting all products");
-    let conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let mut conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    apply_due_price_changes(&mut conn)?;
     
     let mut stmt = conn.prepare(
-        "SELECT p.id, p.name, p.description, p.sku, p.category_id, c.name as category_name, 
-         p.unit_price, p.price_bought, p.current_stock, p.minimum_stock, p.supplier, p.created_at, p.updated_at, p.thumbnailUrl, p.barcode
-         FROM products p
-         JOIN categories c ON p.category_id = c.id
-         ORDER BY p.name"
+        &format!("{} WHERE p.archived_at IS NULL ORDER BY p.name", PRODUCT_QUERY)
     ).map_err(|e| {
         println!("Backend error preparing get_all_products: {}", e);
         e.to_string()
     })?;
 
-    let products = stmt.query_map([], |row| {
-        Ok(ProductWithCategory {
-            id: row.get(0)?,
-            name: row.get(1)?,
-            description: row.get(2)?,
-            sku: row.get(3)?,
-            category_id: row.get(4)?,
-            category_name: row.get(5)?,
-            unit_price: row.get(6)?,
-            price_bought: row.get(7)?,
-            current_stock: row.get(8)?,
-            minimum_stock: row.get(9)?,
-            supplier: row.get(10)?,
-            created_at: row.get(11)?,
-            updated_at: row.get(12)?,
-            thumbnail_url: row.get(13)?,
-            barcode: row.get(14)?,
-        })
-    }).map_err(|e| {
+    let products = stmt.query_map([], map_product_with_category).map_err(|e| {
         println!("Backend error querying all products: {}", e);
         e.to_string()
     })?;
@@ -65,8 +98,13 @@ pub fn get_all_products(state: tauri::State<DbState>) -> Result<Vec<ProductWithC
     Ok(result)
 }
 
+/// Products in a category and, unless `include_subcategories` is false, every category below it
 #[tauri::command]
-pub fn get_products_by_category(state: tauri::State<DbState>, category_id: Option<i32>) -> Result<Vec<ProductWithCategory>, String> {
+pub fn get_products_by_category(
+    state: tauri::State<DbState>,
+    category_id: Option<i32>,
+    include_subcategories: Option<bool>,
+) -> Result<Vec<ProductWithCategory>, String> {
     println!("Backend: Getting products for category_id: {:?}", category_id);
     
     // If category_id is None or not provided, return all products
@@ -76,7 +114,8 @@ pub fn get_products_by_category(state: tauri::State<DbState>, category_id: Optio
     }
     
     let category_id = category_id.unwrap();
-    let conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let mut conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    apply_due_price_changes(&mut conn)?;
     
     // Print all products for debugging
     let mut all_stmt = conn.prepare(
@@ -152,37 +191,19 @@ pub fn get_products_by_category(state: tauri::State<DbState>, category_id: Optio
     println!("SQL COUNT query found {} products with category_id = {}", count, category_id);
     
     // Now do the actual filtered query
+    let category_filter = if include_subcategories.unwrap_or(true) {
+        format!("p.category_id IN {}", SUBTREE_IDS)
+    } else {
+        "p.category_id = ?1".to_string()
+    };
     let mut stmt = conn.prepare(
-        "SELECT p.id, p.name, p.description, p.sku, p.category_id, c.name as category_name, 
-         p.unit_price, p.price_bought, p.current_stock, p.minimum_stock, p.supplier, p.created_at, p.updated_at, p.thumbnailUrl, p.barcode
-         FROM products p
-         JOIN categories c ON p.category_id = c.id
-         WHERE p.category_id = ?1
-         ORDER BY p.name"
+        &format!("{} WHERE {} AND p.archived_at IS NULL ORDER BY p.name", PRODUCT_QUERY, category_filter)
     ).map_err(|e| {
         println!("Backend error preparing get_products_by_category: {}", e);
         e.to_string()
     })?;
 
-    let products = stmt.query_map(params![category_id], |row| {
-        Ok(ProductWithCategory {
-            id: row.get(0)?,
-            name: row.get(1)?,
-            description: row.get(2)?,
-            sku: row.get(3)?,
-            category_id: row.get(4)?,
-            category_name: row.get(5)?,
-            unit_price: row.get(6)?,
-            price_bought: row.get(7)?,
-            current_stock: row.get(8)?,
-            minimum_stock: row.get(9)?,
-            supplier: row.get(10)?,
-            created_at: row.get(11)?,
-            updated_at: row.get(12)?,
-            thumbnail_url: row.get(13)?,
-            barcode: row.get(14)?,
-        })
-    }).map_err(|e| {
+    let products = stmt.query_map(params![category_id], map_product_with_category).map_err(|e| {
         println!("Backend error querying products by category: {}", e);
         e.to_string()
     })?;
@@ -201,7 +222,7 @@ pub fn get_products_by_category(state: tauri::State<DbState>, category_id: Optio
 }
 
 #[tauri::command]
-pub fn add_product(state: tauri::State<DbState>, product: NewProduct) -> Result<Product, String> {
+pub fn add_product(app: tauri::AppHandle, state: tauri::State<DbState>, product: NewProduct, token: String) -> Result<Product, String> {
     println!("Backend: Adding new product: {:?}", product.name);
     
     // Get a connection from the pool with proper error handling
@@ -213,6 +234,8 @@ pub fn add_product(state: tauri::State<DbState>, product: NewProduct) -> Result<
             return Err(error_msg);
         }
     };
+    let actor = authenticate(&conn, &token)?;
+    require_permission(&conn, &actor, EDIT_INVENTORY, "add products")?;
     
     // Wrap the entire operation in a transaction to ensure atomicity
     let tx = match conn.transaction() {
@@ -224,6 +247,14 @@ pub fn add_product(state: tauri::State<DbState>, product: NewProduct) -> Result<
         }
     };
     
+    check_category_active(&tx, product.category_id)?;
+
+    // Codes must be unique across product and variant barcodes and SKUs
+    ensure_code_available(&tx, &product.sku, None)?;
+    if let Some(barcode) = &product.barcode {
+        ensure_code_available(&tx, barcode, None)?;
+    }
+    
     // Insert the new product
     let product_id = match tx.execute(
         "INSERT INTO products (name, description, sku, category_id, unit_price, price_bought, current_stock, minimum_stock, supplier, thumbnailUrl, barcode) 
@@ -272,7 +303,7 @@ pub fn add_product(state: tauri::State<DbState>, product: NewProduct) -> Result<
     // Prepare a statement to get the inserted product
     {
         let mut stmt = match tx.prepare(
-            "SELECT id, name, description, sku, category_id, unit_price, price_bought, current_stock, minimum_stock, supplier, created_at, updated_at, thumbnailUrl, barcode
+            "SELECT id, name, description, sku, category_id, unit_price, price_bought, current_stock, minimum_stock, supplier, created_at, updated_at, thumbnailUrl, barcode, archived_at
              FROM products WHERE id = ?1"
         ) {
             Ok(stmt) => stmt,
@@ -302,6 +333,7 @@ pub fn add_product(state: tauri::State<DbState>, product: NewProduct) -> Result<
                     updated_at: row.get(11)?,
                     thumbnail_url: row.get(12)?,
                     barcode: row.get(13)?,
+                    archived_at: row.get(14)?,
                 })
             }
         ) {
@@ -314,19 +346,82 @@ pub fn add_product(state: tauri::State<DbState>, product: NewProduct) -> Result<
         };
     } // stmt goes out of scope here
     
+    // A product can be created already below its minimum stock
+    let alerts = evaluate_stock_alerts(&tx, &[product_id])?;
+
+    let after = snapshot(&tx, "products", product_id as i64)?;
+    record_audit(&tx, Some(&actor), CREATE, "product", Some(product_id as i64), None, after)?;
+    
     // Commit the transaction
     if let Err(e) = tx.commit() {
         let error_msg = format!("Failed to commit transaction: {}", e);
         println!("Backend error: {}", error_msg);
         return Err(error_msg);
     }
+    emit_stock_alerts(&app, &alerts);
 
     println!("Backend: Added product with id: {}", result.id);
     Ok(result)
 }
 
+/// Remove a product that has never been sold, along with its variants, modifiers, recipe,
+/// barcodes, units, stock and price history and promotions. Sold products stay for reports
+/// and can only be archived, and ingredients stay while a recipe still uses them.
+pub fn delete_unsold_product(conn: &rusqlite::Connection, id: i32) -> Result<usize, String> {
+    let sold: bool = conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM order_items WHERE product_id = ?1)",
+        params![id],
+        |row| row.get(0),
+    ).map_err(|e| format!("Failed to check product sales: {}", e))?;
+
+    if sold {
+        return Err("This product has been sold and is kept for reports; archive it instead".to_string());
+    }
+
+    // Other recipes would be left consuming a product that no longer exists
+    let used_in: Option<String> = conn.query_row(
+        "SELECT p.name FROM recipe_components rc JOIN products p ON p.id = rc.product_id
+         WHERE rc.component_id = ?1 AND rc.product_id != ?1 LIMIT 1",
+        params![id],
+        |row| row.get(0),
+    ).optional().map_err(|e| format!("Failed to check recipes: {}", e))?;
+
+    if let Some(recipe) = used_in {
+        return Err(format!("This product is an ingredient of {}; remove it from that recipe first", recipe));
+    }
+
+    // Foreign keys aren't enforced, so everything that belongs to the product goes with it.
+    // Alternate barcodes are freed up for reuse, and promotions limited to the product have
+    // nothing left to apply to.
+    let dependents = [
+        ("barcodes", "DELETE FROM product_barcodes
+                      WHERE product_id = ?1 OR variant_id IN (SELECT id FROM product_variants WHERE product_id = ?1)"),
+        ("variants", "DELETE FROM product_variants WHERE product_id = ?1"),
+        ("modifiers", "DELETE FROM modifiers WHERE group_id IN (SELECT id FROM modifier_groups WHERE product_id = ?1)"),
+        ("modifier groups", "DELETE FROM modifier_groups WHERE product_id = ?1"),
+        ("recipe", "DELETE FROM recipe_components WHERE product_id = ?1 OR component_id = ?1"),
+        ("recipe", "DELETE FROM recipes WHERE product_id = ?1"),
+        ("units", "DELETE FROM product_units WHERE product_id = ?1"),
+        ("stock movements", "DELETE FROM stock_movements WHERE product_id = ?1"),
+        ("price history", "DELETE FROM price_history WHERE product_id = ?1"),
+        ("scheduled price changes", "DELETE FROM scheduled_price_changes WHERE product_id = ?1"),
+        ("notifications", "DELETE FROM notification_states
+                           WHERE notification_id IN (SELECT id FROM notifications WHERE product_id = ?1)"),
+        ("notifications", "DELETE FROM notifications WHERE product_id = ?1"),
+        ("promotions", "DELETE FROM promotions WHERE product_id = ?1"),
+    ];
+
+    for (what, statement) in dependents.iter() {
+        conn.execute(statement, params![id])
+            .map_err(|e| format!("Failed to delete product {}: {}", what, e))?;
+    }
+
+    conn.execute("DELETE FROM products WHERE id = ?1", params![id])
+        .map_err(|e| format!("Failed to delete product: {}", e))
+}
+
 #[tauri::command]
-pub fn delete_product(state: tauri::State<DbState>, id: i32) -> Result<(), String> {
+pub fn delete_product(state: tauri::State<DbState>, id: i32, token: String) -> Result<(), String> {
     println!("Backend: Deleting product with ID: {}", id);
     
     // Get a connection from the pool with proper error handling
@@ -338,6 +433,8 @@ pub fn delete_product(state: tauri::State<DbState>, id: i32) -> Result<(), Strin
             return Err(error_msg);
         }
     };
+    let actor = authenticate(&conn, &token)?;
+    require_permission(&conn, &actor, EDIT_INVENTORY, "delete products")?;
     
     // Wrap the entire operation in a transaction to ensure atomicity
     let tx = match conn.transaction() {
@@ -349,23 +446,15 @@ pub fn delete_product(state: tauri::State<DbState>, id: i32) -> Result<(), Strin
         }
     };
     
-    // Delete the product
-    let deleted_count = match tx.execute(
-        "DELETE FROM products WHERE id = ?1",
-        params![id]
-    ) {
-        Ok(count) => count,
-        Err(e) => {
-            let error_msg = format!("Backend error deleting product: {}", e);
-            println!("{}", error_msg);
-            return Err(error_msg);
-        }
-    };
+    let before = snapshot(&tx, "products", id as i64)?;
+    let deleted_count = delete_unsold_product(&tx, id)?;
 
     if deleted_count == 0 {
         println!("Backend: No product found with ID: {}", id);
         return Err("Product not found".to_string());
     }
+
+    record_audit(&tx, Some(&actor), DELETE, "product", Some(id as i64), before, None)?;
     
     // Commit the transaction
     if let Err(e) = tx.commit() {
@@ -379,19 +468,30 @@ pub fn delete_product(state: tauri::State<DbState>, id: i32) -> Result<(), Strin
 }
 
 #[tauri::command]
-pub fn update_product_stock(state: tauri::State<DbState>, id: i32, new_stock: i32) -> Result<(), String> {
-    let conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+pub fn update_product_stock(app: tauri::AppHandle, state: tauri::State<DbState>, id: i32, new_stock: f64, token: String) -> Result<(), String> {
+    let mut conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let actor = authenticate(&conn, &token)?;
+    require_permission(&conn, &actor, EDIT_INVENTORY, "change stock levels")?;
+    let tx = conn.transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let before = snapshot(&tx, "products", id as i64)?;
     
-    conn.execute(
+    tx.execute(
         "UPDATE products SET current_stock = ?1, updated_at = datetime('now') WHERE id = ?2",
         params![new_stock, id]
     ).map_err(|e| e.to_string())?;
 
+    let after = snapshot(&tx, "products", id as i64)?;
+    record_audit(&tx, Some(&actor), UPDATE, "product", Some(id as i64), before, after)?;
+
+    let alerts = evaluate_stock_alerts(&tx, &[id])?;
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    emit_stock_alerts(&app, &alerts);
+
     Ok(())
 }
 
 #[tauri::command]
-pub fn update_product(state: tauri::State<DbState>, product: UpdateProduct) -> Result<ProductWithCategory, String> {
+pub fn update_product(app: tauri::AppHandle, state: tauri::State<DbState>, product: UpdateProduct, token: String) -> Result<ProductWithCategory, String> {
     println!("Backend: Updating product with ID: {}", product.id);
     
     // Get a connection from the pool with proper error handling
@@ -403,6 +503,8 @@ pub fn update_product(state: tauri::State<DbState>, product: UpdateProduct) -> R
             return Err(error_msg);
         }
     };
+    let actor = authenticate(&conn, &token)?;
+    require_permission(&conn, &actor, EDIT_INVENTORY, "change products")?;
     
     // Wrap the entire operation in a transaction to ensure atomicity
     let tx = match conn.transaction() {
@@ -417,9 +519,17 @@ pub fn update_product(state: tauri::State<DbState>, product: UpdateProduct) -> R
     // Use the minimum_stock from the update if provided, otherwise calculate it
     let minimum_stock = product.minimum_stock.unwrap_or_else(|| {
         // Set minimum to 20% of current stock or at least 1
-        std::cmp::max(1, (product.current_stock as f64 * 0.2) as i32)
+        (product.current_stock * 0.2).floor().max(1.0)
     });
     
+    if let Some(barcode) = &product.barcode {
+        ensure_code_available(&tx, barcode, Some(("products", product.id)))?;
+    }
+
+    check_category_active(&tx, product.category_id)?;
+    let before = snapshot(&tx, "products", product.id as i64)?;
+    let old_prices = load_prices(&tx, product.id)?;
+    
     // Update the product
     let updated_count = match tx.execute(
         "UPDATE products SET 
@@ -464,31 +574,9 @@ pub fn update_product(state: tauri::State<DbState>, product: UpdateProduct) -> R
     
     // Retrieve the updated product with category information
     let updated_product = match tx.query_row(
-        "SELECT p.id, p.name, p.description, p.sku, p.category_id, c.name, p.unit_price, p.price_bought, 
-                p.current_stock, p.minimum_stock, p.supplier, p.created_at, p.updated_at, p.thumbnailUrl, p.barcode
-         FROM products p
-         JOIN categories c ON p.category_id = c.id
-         WHERE p.id = ?1",
+        &format!("{} WHERE p.id = ?1", PRODUCT_QUERY),
         params![product.id],
-        |row| {
-            Ok(ProductWithCategory {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                description: row.get(2)?,
-                sku: row.get(3)?,
-                category_id: row.get(4)?,
-                category_name: row.get(5)?,
-                unit_price: row.get(6)?,
-                price_bought: row.get(7)?,
-                current_stock: row.get(8)?,
-                minimum_stock: row.get(9)?,
-                supplier: row.get(10)?,
-                created_at: row.get(11)?,
-                updated_at: row.get(12)?,
-                thumbnail_url: row.get(13)?,
-                barcode: row.get(14)?,
-            })
-        }
+        map_product_with_category
     ) {
         Ok(product) => product,
         Err(e) => {
@@ -498,13 +586,310 @@ pub fn update_product(state: tauri::State<DbState>, product: UpdateProduct) -> R
         }
     };
     
+    // Stock or minimum may have changed, so re-check the thresholds
+    let alerts = evaluate_stock_alerts(&tx, &[product.id])?;
+
+    record_price_change(
+        &tx,
+        product.id,
+        old_prices,
+        (product.unit_price, product.price_bought),
+        Some((actor.id, actor.username.as_str())),
+        product.price_change_reason.as_deref(),
+        None,
+    )?;
+
+    let after = snapshot(&tx, "products", product.id as i64)?;
+    record_audit(&tx, Some(&actor), UPDATE, "product", Some(product.id as i64), before, after)?;
+    
     // Commit the transaction
     if let Err(e) = tx.commit() {
         let error_msg = format!("Failed to commit transaction: {}", e);
         println!("Backend error: {}", error_msg);
         return Err(error_msg);
     }
+    emit_stock_alerts(&app, &alerts);
 
     println!("Backend: Successfully updated product: {}", updated_product.name);
     Ok(updated_product)
 }
+
+/// When something is archived. Restoring a category brings back the products stamped with the
+/// same time, so it is precise enough to tell apart products archived just before it.
+pub fn archive_timestamp() -> String {
+    chrono::Local::now().format("%Y-%m-%d %H:%M:%S%.3f").to_string()
+}
+
+/// Hide a product from the catalogue and the register. Its sales, stock history and codes
+/// are kept, and it can be restored.
+#[tauri::command]
+pub fn archive_product(state: tauri::State<DbState>, id: i32, token: String) -> Result<ProductWithCategory, String> {
+    println!("Backend: Archiving product {}", id);
+
+    let mut conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let actor = authenticate(&conn, &token)?;
+    require_permission(&conn, &actor, EDIT_INVENTORY, "archive products")?;
+    let tx = conn.transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let before = snapshot(&tx, "products", id as i64)?;
+
+    let updated = tx.execute(
+        "UPDATE products SET archived_at = ?1, updated_at = datetime('now') WHERE id = ?2 AND archived_at IS NULL",
+        params![archive_timestamp(), id],
+    ).map_err(|e| format!("Failed to archive product: {}", e))?;
+
+    if updated == 0 {
+        return Err("Product not found or already archived".to_string());
+    }
+
+    // Closes any stock alert still open for it
+    evaluate_stock_alerts(&tx, &[id])?;
+
+    let after = snapshot(&tx, "products", id as i64)?;
+    record_audit(&tx, Some(&actor), UPDATE, "product", Some(id as i64), before, after)?;
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+    load_product_with_category(&conn, id)
+}
+
+#[tauri::command]
+pub fn restore_product(app: tauri::AppHandle, state: tauri::State<DbState>, id: i32, token: String) -> Result<ProductWithCategory, String> {
+    println!("Backend: Restoring product {}", id);
+
+    let mut conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let actor = authenticate(&conn, &token)?;
+    require_permission(&conn, &actor, EDIT_INVENTORY, "restore products")?;
+    let tx = conn.transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let product = load_product_with_category(&tx, id)?;
+    if product.archived_at.is_none() {
+        return Err(format!("{} is not archived", product.name));
+    }
+    check_category_active(&tx, product.category_id)?;
+    let before = snapshot(&tx, "products", id as i64)?;
+
+    tx.execute(
+        "UPDATE products SET archived_at = NULL, updated_at = datetime('now') WHERE id = ?1",
+        params![id],
+    ).map_err(|e| format!("Failed to restore product: {}", e))?;
+
+    let alerts = evaluate_stock_alerts(&tx, &[id])?;
+
+    let after = snapshot(&tx, "products", id as i64)?;
+    record_audit(&tx, Some(&actor), UPDATE, "product", Some(id as i64), before, after)?;
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    emit_stock_alerts(&app, &alerts);
+
+    load_product_with_category(&conn, id)
+}
+
+#[tauri::command]
+pub fn get_archived_products(state: tauri::State<DbState>) -> Result<Vec<ProductWithCategory>, String> {
+    let conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+
+    let mut stmt = conn.prepare(&format!(
+        "{} WHERE p.archived_at IS NOT NULL ORDER BY p.archived_at DESC, p.name",
+        PRODUCT_QUERY
+    )).map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+    let rows = stmt.query_map([], map_product_with_category)
+        .map_err(|e| format!("Failed to query archived products: {}", e))?;
+
+    rows.collect::<Result<Vec<_>>>()
+        .map_err(|e| format!("Failed to collect archived products: {}", e))
+}
+
+const DEFAULT_SEARCH_LIMIT: i64 = 50;
+const MAX_SEARCH_LIMIT: i64 = 200;
+
+// Where the previous page of a search ended: the sort value and id of its last product
+#[derive(Serialize, Deserialize)]
+struct SearchCursor {
+    sort_by: String,
+    descending: bool,
+    key: Value,
+    id: i32,
+}
+
+// Turn what was typed into an FTS5 query where every word must match the start of an indexed
+// word. Quoting each word keeps FTS5 syntax characters from being interpreted.
+fn fts_query(text: &str) -> Option<String> {
+    let terms: Vec<String> = text
+        .split_whitespace()
+        .map(|word| word.replace('"', ""))
+        .filter(|word| word.chars().any(char::is_alphanumeric))
+        .map(|word| format!("\"{}\"*", word))
+        .collect();
+
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" "))
+    }
+}
+
+// Add a parameter to a query being built and return its placeholder
+fn bind(params: &mut Vec<Box<dyn ToSql>>, value: impl ToSql + 'static) -> String {
+    params.push(Box::new(value));
+    format!("?{}", params.len())
+}
+
+/// Search the catalogue one page at a time. Text is matched against names, descriptions, SKUs
+/// and barcodes; the other fields narrow the results down. Pass the returned next_cursor back
+/// with the same search to get the following page.
+#[tauri::command]
+pub fn search_products(state: tauri::State<DbState>, search: Option<ProductSearch>) -> Result<ProductSearchPage, String> {
+    let search = search.unwrap_or_default();
+    println!("Backend: Searching products: {:?}", search);
+
+    let mut conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    apply_due_price_changes(&mut conn)?;
+
+    let mut sql_params: Vec<Box<dyn ToSql>> = Vec::new();
+    let mut conditions = vec![if search.archived.unwrap_or(false) {
+        "p.archived_at IS NOT NULL".to_string()
+    } else {
+        "p.archived_at IS NULL".to_string()
+    }];
+
+    let text = search.query.as_deref().and_then(fts_query);
+    let fts_join = match &text {
+        Some(text) => format!(
+            "JOIN (SELECT rowid AS id, rank FROM products_fts WHERE products_fts MATCH {}) f ON f.id = p.id",
+            bind(&mut sql_params, text.clone())
+        ),
+        None => String::new(),
+    };
+
+    if let Some(category_id) = search.category_id {
+        let placeholder = bind(&mut sql_params, category_id);
+        if search.include_subcategories.unwrap_or(true) {
+            conditions.push(format!("p.category_id IN {}", SUBTREE_IDS.replace("?1", &placeholder)));
+        } else {
+            conditions.push(format!("p.category_id = {}", placeholder));
+        }
+    }
+
+    if let Some(supplier) = search.supplier.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+        conditions.push(format!("p.supplier = {} COLLATE NOCASE", bind(&mut sql_params, supplier.to_string())));
+    }
+
+    if let Some(status) = search.stock_status.as_deref() {
+        conditions.push(match status {
+            STOCK_OUT => "p.current_stock <= 0",
+            STOCK_LOW => "p.current_stock > 0 AND p.current_stock <= p.minimum_stock",
+            STOCK_OK => "p.current_stock > 0 AND p.current_stock > p.minimum_stock",
+            _ => return Err(format!("Unknown stock status: {}", status)),
+        }.to_string());
+    }
+
+    if let (Some(min), Some(max)) = (search.min_price, search.max_price) {
+        if min > max {
+            return Err("The minimum price can't be above the maximum price".to_string());
+        }
+    }
+    if let Some(min) = search.min_price {
+        conditions.push(format!("p.unit_price >= {}", bind(&mut sql_params, min)));
+    }
+    if let Some(max) = search.max_price {
+        conditions.push(format!("p.unit_price <= {}", bind(&mut sql_params, max)));
+    }
+
+    let filtered = format!("{} {} WHERE {}", PRODUCT_QUERY, fts_join, conditions.join(" AND "));
+
+    let total: i64 = {
+        let count_params: Vec<&dyn ToSql> = sql_params.iter().map(|p| p.as_ref()).collect();
+        conn.query_row(&format!("SELECT COUNT(*) FROM ({})", filtered), count_params.as_slice(), |row| row.get(0))
+            .map_err(|e| format!("Failed to count products: {}", e))?
+    };
+
+    let default_sort = if text.is_some() { SORT_RELEVANCE } else { SORT_NAME };
+    let sort_by = search.sort_by.as_deref().unwrap_or(default_sort).to_string();
+    let descending = search.descending.unwrap_or(false);
+    let sort_expr = match sort_by.as_str() {
+        SORT_RELEVANCE if text.is_some() => "f.rank",
+        SORT_RELEVANCE => return Err("Sorting by relevance needs something to search for".to_string()),
+        SORT_NAME => "p.name COLLATE NOCASE",
+        SORT_SKU => "p.sku COLLATE NOCASE",
+        SORT_PRICE => "p.unit_price",
+        SORT_STOCK => "p.current_stock",
+        SORT_CREATED => "p.created_at",
+        SORT_UPDATED => "p.updated_at",
+        _ => return Err(format!("Unknown sort order: {}", sort_by)),
+    };
+    let (direction, after) = if descending { ("DESC", "<") } else { ("ASC", ">") };
+
+    // Carry on after the last product of the previous page
+    let mut page_query = filtered;
+    if let Some(cursor) = &search.cursor {
+        let cursor: SearchCursor = general_purpose::URL_SAFE_NO_PAD.decode(cursor)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .ok_or("Invalid search cursor")?;
+
+        if cursor.sort_by != sort_by || cursor.descending != descending {
+            return Err("The search cursor is for a different sort order; start from the first page".to_string());
+        }
+
+        let key = match cursor.key {
+            Value::String(key) => bind(&mut sql_params, key),
+            Value::Number(key) => bind(&mut sql_params, key.as_f64().ok_or("Invalid search cursor")?),
+            _ => return Err("Invalid search cursor".to_string()),
+        };
+        let id = bind(&mut sql_params, cursor.id);
+        page_query.push_str(&format!(
+            " AND ({expr} {after} {key} OR ({expr} = {key} AND p.id {after} {id}))",
+            expr = sort_expr,
+            after = after,
+            key = key,
+            id = id
+        ));
+    }
+
+    let limit = search.limit.unwrap_or(DEFAULT_SEARCH_LIMIT).clamp(1, MAX_SEARCH_LIMIT);
+    // One extra row tells whether there is another page
+    page_query.push_str(&format!(
+        " ORDER BY {expr} {dir}, p.id {dir} LIMIT {limit}",
+        expr = sort_expr,
+        dir = direction,
+        limit = limit + 1
+    ));
+
+    let mut products = {
+        let page_params: Vec<&dyn ToSql> = sql_params.iter().map(|p| p.as_ref()).collect();
+        let mut stmt = conn.prepare(&page_query)
+            .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let rows = stmt.query_map(page_params.as_slice(), map_product_with_category)
+            .map_err(|e| format!("Failed to search products: {}", e))?;
+
+        rows.collect::<Result<Vec<_>>>()
+            .map_err(|e| format!("Failed to collect products: {}", e))?
+    };
+    let has_more = products.len() as i64 > limit;
+    products.truncate(limit as usize);
+
+    let next_cursor = match products.last() {
+        Some(last) if has_more => {
+            let key = match sort_by.as_str() {
+                SORT_RELEVANCE => Value::from(conn.query_row(
+                    "SELECT rank FROM products_fts WHERE products_fts MATCH ?1 AND rowid = ?2",
+                    params![text, last.id],
+                    |row| row.get::<_, f64>(0),
+                ).map_err(|e| format!("Failed to rank product: {}", e))?),
+                SORT_SKU => Value::from(last.sku.clone()),
+                SORT_PRICE => Value::from(last.unit_price),
+                SORT_STOCK => Value::from(last.current_stock),
+                SORT_CREATED => Value::from(last.created_at.clone()),
+                SORT_UPDATED => Value::from(last.updated_at.clone()),
+                _ => Value::from(last.name.clone()),
+            };
+            let cursor = SearchCursor { sort_by, descending, key, id: last.id };
+            let bytes = serde_json::to_vec(&cursor).map_err(|e| format!("Failed to build search cursor: {}", e))?;
+            Some(general_purpose::URL_SAFE_NO_PAD.encode(bytes))
+        }
+        _ => None,
+    };
+
+    println!("Backend: Search matched {} products, returning {}", total, products.len());
+    Ok(ProductSearchPage { products, total, next_cursor })
+}
@@ -0,0 +1,271 @@
+use crate::commands::audit::{record_audit, snapshot};
+use crate::commands::auth::authenticate;
+use crate::commands::role::require_permission;
+use crate::commands::transaction::load_order_with_items;
+use crate::db::models::audit::UPDATE;
+use crate::db::models::order::OrderWithItems;
+use crate::db::models::receipt::{
+    ReceiptOutput, ReceiptSettings, SaveReceiptSettings, PRINTER_FILE, PRINTER_NONE, PRINTER_TCP,
+};
+use crate::db::models::role::MANAGE_SETTINGS;
+use crate::db::DbState;
+use crate::printing::receipt;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::io::Write;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::Path;
+use std::time::Duration;
+
+const DEFAULT_PRINTER_PORT: u16 = 9100;
+const PRINTER_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub fn load_receipt_settings(conn: &Connection) -> Result<ReceiptSettings, String> {
+    let settings = conn.query_row(
+        "SELECT store_name, header, footer, paper_width, printer_type, printer_target, pdf_directory,
+                cut_paper, open_drawer, updated_at
+         FROM receipt_settings WHERE id = 1",
+        [],
+        |row| {
+            Ok(ReceiptSettings {
+                store_name: row.get(0)?,
+                header: row.get(1)?,
+                footer: row.get(2)?,
+                paper_width: row.get(3)?,
+                printer_type: row.get(4)?,
+                printer_target: row.get(5)?,
+                pdf_directory: row.get(6)?,
+                cut_paper: row.get(7)?,
+                open_drawer: row.get(8)?,
+                updated_at: row.get(9)?,
+            })
+        },
+    ).optional().map_err(|e| format!("Failed to get receipt settings: {}", e))?;
+
+    Ok(settings.unwrap_or(ReceiptSettings {
+        store_name: "Canteen".to_string(),
+        header: None,
+        footer: None,
+        paper_width: 42,
+        printer_type: PRINTER_NONE.to_string(),
+        printer_target: None,
+        pdf_directory: None,
+        cut_paper: true,
+        open_drawer: false,
+        updated_at: String::new(),
+    }))
+}
+
+// Raw ESC/POS bytes to a device node / spool file, or to a network printer's raw port
+fn send_to_printer(settings: &ReceiptSettings, bytes: &[u8]) -> Result<String, String> {
+    let target = settings.printer_target.as_deref().map(str::trim).filter(|t| !t.is_empty())
+        .ok_or_else(|| "No printer configured".to_string())?;
+
+    match settings.printer_type.as_str() {
+        PRINTER_FILE => {
+            let mut device = std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(target)
+                .map_err(|e| format!("Failed to open printer {}: {}", target, e))?;
+            device.write_all(bytes).map_err(|e| format!("Failed to write to printer {}: {}", target, e))?;
+        }
+        PRINTER_TCP => {
+            let address = if target.contains(':') {
+                target.to_string()
+            } else {
+                format!("{}:{}", target, DEFAULT_PRINTER_PORT)
+            };
+            let socket = address.to_socket_addrs()
+                .map_err(|e| format!("Invalid printer address {}: {}", address, e))?
+                .next()
+                .ok_or_else(|| format!("Invalid printer address {}", address))?;
+            let mut stream = TcpStream::connect_timeout(&socket, PRINTER_TIMEOUT)
+                .map_err(|e| format!("Failed to connect to printer {}: {}", address, e))?;
+            stream.set_write_timeout(Some(PRINTER_TIMEOUT))
+                .map_err(|e| format!("Failed to configure printer connection: {}", e))?;
+            stream.write_all(bytes).map_err(|e| format!("Failed to send to printer {}: {}", address, e))?;
+        }
+        other => return Err(format!("Unknown printer type: {}", other)),
+    }
+
+    Ok(target.to_string())
+}
+
+fn write_pdf(lines: &[receipt::ReceiptLine], width: usize, path: &Path) -> Result<usize, String> {
+    let bytes = receipt::to_pdf(lines, width);
+    std::fs::write(path, &bytes).map_err(|e| format!("Failed to write receipt PDF: {}", e))?;
+    Ok(bytes.len())
+}
+
+fn record_print(conn: &Connection, output: &ReceiptOutput) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO receipt_prints (order_id, reprint, format, destination, printer_error) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![output.order_id, output.reprint, output.format, output.destination, output.printer_error],
+    ).map_err(|e| format!("Failed to record receipt print: {}", e))?;
+    Ok(())
+}
+
+// Print on the configured printer; without one, or when it fails, save a PDF instead
+fn issue_receipt(conn: &Connection, order: &OrderWithItems, reprint: bool) -> Result<ReceiptOutput, String> {
+    let settings = load_receipt_settings(conn)?;
+    let width = settings.paper_width.clamp(24, 64) as usize;
+    let lines = receipt::build(&settings, order, reprint);
+
+    let mut printer_error = None;
+    if settings.printer_type != PRINTER_NONE {
+        let bytes = receipt::to_escpos(&lines, width, settings.cut_paper, settings.open_drawer);
+        match send_to_printer(&settings, &bytes) {
+            Ok(destination) => {
+                let output = ReceiptOutput {
+                    order_id: order.order.id,
+                    reprint,
+                    format: "escpos".to_string(),
+                    destination,
+                    bytes: bytes.len(),
+                    printer_error: None,
+                };
+                record_print(conn, &output)?;
+                return Ok(output);
+            }
+            Err(e) => {
+                println!("Backend error printing receipt: {}", e);
+                printer_error = Some(e);
+            }
+        }
+    }
+
+    let directory = match settings.pdf_directory.as_deref().map(str::trim).filter(|d| !d.is_empty()) {
+        Some(directory) => directory,
+        None => return Err(printer_error.unwrap_or_else(|| "No printer or PDF folder configured for receipts".to_string())),
+    };
+
+    let file_name = if reprint {
+        format!("receipt-{}-reprint.pdf", order.order.order_id)
+    } else {
+        format!("receipt-{}.pdf", order.order.order_id)
+    };
+    let path = Path::new(directory).join(file_name);
+    let bytes = write_pdf(&lines, width, &path)?;
+
+    let output = ReceiptOutput {
+        order_id: order.order.id,
+        reprint,
+        format: "pdf".to_string(),
+        destination: path.to_string_lossy().to_string(),
+        bytes,
+        printer_error,
+    };
+    record_print(conn, &output)?;
+    Ok(output)
+}
+
+#[tauri::command]
+pub fn get_receipt_settings(state: tauri::State<DbState>) -> Result<ReceiptSettings, String> {
+    let conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    load_receipt_settings(&conn)
+}
+
+#[tauri::command]
+pub fn save_receipt_settings(state: tauri::State<DbState>, settings: SaveReceiptSettings, token: String) -> Result<ReceiptSettings, String> {
+    println!("Backend: Saving receipt settings: {:?}", settings);
+
+    if ![PRINTER_NONE, PRINTER_FILE, PRINTER_TCP].contains(&settings.printer_type.as_str()) {
+        return Err(format!("Unknown printer type: {}", settings.printer_type));
+    }
+    if settings.printer_type != PRINTER_NONE && settings.printer_target.as_deref().unwrap_or("").trim().is_empty() {
+        return Err("Printer address or device path is required".to_string());
+    }
+
+    let paper_width = settings.paper_width.unwrap_or(42);
+    if !(24..=64).contains(&paper_width) {
+        return Err("Paper width must be between 24 and 64 characters".to_string());
+    }
+
+    let mut conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let actor = authenticate(&conn, &token)?;
+    require_permission(&conn, &actor, MANAGE_SETTINGS, "change receipt settings")?;
+    let tx = conn.transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let before = snapshot(&tx, "receipt_settings", 1)?;
+
+    tx.execute(
+        "INSERT INTO receipt_settings (id, store_name, header, footer, paper_width, printer_type, printer_target,
+                                       pdf_directory, cut_paper, open_drawer)
+         VALUES (1, ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+         ON CONFLICT (id) DO UPDATE SET
+            store_name = excluded.store_name,
+            header = excluded.header,
+            footer = excluded.footer,
+            paper_width = excluded.paper_width,
+            printer_type = excluded.printer_type,
+            printer_target = excluded.printer_target,
+            pdf_directory = excluded.pdf_directory,
+            cut_paper = excluded.cut_paper,
+            open_drawer = excluded.open_drawer,
+            updated_at = datetime('now')",
+        params![
+            settings.store_name,
+            settings.header,
+            settings.footer,
+            paper_width,
+            settings.printer_type,
+            settings.printer_target,
+            settings.pdf_directory,
+            settings.cut_paper.unwrap_or(true),
+            settings.open_drawer.unwrap_or(false)
+        ],
+    ).map_err(|e| format!("Failed to save receipt settings: {}", e))?;
+
+    let after = snapshot(&tx, "receipt_settings", 1)?;
+    record_audit(&tx, Some(&actor), UPDATE, "receipt_settings", Some(1), before, after)?;
+
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    load_receipt_settings(&conn)
+}
+
+// Print the receipt for a just-completed order
+#[tauri::command]
+pub fn print_receipt(state: tauri::State<DbState>, order_id: i32) -> Result<ReceiptOutput, String> {
+    println!("Backend: Printing receipt for order {}", order_id);
+    let conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let order = load_order_with_items(&conn, order_id)?;
+    issue_receipt(&conn, &order, false)
+}
+
+#[tauri::command]
+pub fn reprint_receipt(state: tauri::State<DbState>, order_id: i32) -> Result<ReceiptOutput, String> {
+    println!("Backend: Reprinting receipt for order {}", order_id);
+    let conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let order = load_order_with_items(&conn, order_id)?;
+    issue_receipt(&conn, &order, true)
+}
+
+// Save a receipt as PDF at a chosen path, regardless of the printer settings
+#[tauri::command]
+pub fn save_receipt_pdf(state: tauri::State<DbState>, order_id: i32, path: String) -> Result<ReceiptOutput, String> {
+    println!("Backend: Saving receipt PDF for order {} to {}", order_id, path);
+
+    // Validate path directory exists
+    let pdf_path = Path::new(&path);
+    if let Some(parent) = pdf_path.parent() {
+        if !parent.as_os_str().is_empty() && !parent.exists() {
+            return Err("Invalid export path".into());
+        }
+    }
+
+    let conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let order = load_order_with_items(&conn, order_id)?;
+    let settings = load_receipt_settings(&conn)?;
+    let width = settings.paper_width.clamp(24, 64) as usize;
+
+    let lines = receipt::build(&settings, &order, false);
+    let bytes = write_pdf(&lines, width, pdf_path)?;
+
+    Ok(ReceiptOutput {
+        order_id,
+        reprint: false,
+        format: "pdf".to_string(),
+        destination: path,
+        bytes,
+        printer_error: None,
+    })
+}
@@ -0,0 +1,238 @@
+use crate::commands::audit::record_audit;
+use crate::commands::auth::authenticate;
+use crate::commands::role::require_permission;
+use crate::commands::unit::{load_product_units, unit_factor};
+use crate::db::models::audit::{CREATE, DELETE, UPDATE};
+use crate::db::models::recipe::{NewRecipeComponent, Recipe, RecipeComponent, SaveRecipe};
+use crate::db::models::role::EDIT_INVENTORY;
+use crate::db::DbState;
+use rusqlite::{params, Connection, OptionalExtension, Result};
+
+fn load_recipe(conn: &Connection, product_id: i32) -> Result<Option<Recipe>, String> {
+    let header = conn.query_row(
+        "SELECT r.product_id, p.name, r.deduct_finished_item, r.updated_at
+         FROM recipes r
+         JOIN products p ON r.product_id = p.id
+         WHERE r.product_id = ?1",
+        params![product_id],
+        |row| Ok((row.get::<_, i32>(0)?, row.get::<_, String>(1)?, row.get::<_, bool>(2)?, row.get::<_, String>(3)?)),
+    ).optional().map_err(|e| format!("Failed to get recipe: {}", e))?;
+
+    let (product_id, product_name, deduct_finished_item, updated_at) = match header {
+        Some(header) => header,
+        None => return Ok(None),
+    };
+
+    let mut stmt = conn.prepare(
+        "SELECT rc.id, rc.product_id, rc.component_id, p.name, rc.quantity, rc.unit, p.price_bought
+         FROM recipe_components rc
+         JOIN products p ON rc.component_id = p.id
+         WHERE rc.product_id = ?1
+         ORDER BY rc.id"
+    ).map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+    let mut components = stmt.query_map(params![product_id], |row| {
+        Ok(RecipeComponent {
+            id: row.get(0)?,
+            product_id: row.get(1)?,
+            component_id: row.get(2)?,
+            component_name: row.get(3)?,
+            quantity: row.get(4)?,
+            unit: row.get(5)?,
+            base_quantity: 0.0,
+            unit_cost: row.get(6)?,
+            line_cost: 0.0,
+        })
+    }).map_err(|e| format!("Failed to query recipe components: {}", e))?
+    .collect::<Result<Vec<_>>>()
+    .map_err(|e| format!("Failed to collect recipe components: {}", e))?;
+
+    // Stock and cost are kept per base unit, so convert what the recipe measures in
+    for component in &mut components {
+        component.base_quantity = component.quantity * component_factor(conn, component.component_id, component.unit.as_deref())?;
+        component.line_cost = component.unit_cost * component.base_quantity;
+    }
+
+    let total_cost = components.iter().map(|c| c.line_cost).sum();
+
+    Ok(Some(Recipe {
+        product_id,
+        product_name,
+        deduct_finished_item,
+        components,
+        total_cost,
+        updated_at,
+    }))
+}
+
+// Base units of a component in one `unit` of it; no unit means the base unit
+fn component_factor(conn: &Connection, component_id: i32, unit: Option<&str>) -> Result<f64, String> {
+    let unit = match unit {
+        Some(unit) => unit,
+        None => return Ok(1.0),
+    };
+
+    let units = load_product_units(conn, component_id)?;
+    unit_factor(&units, unit).ok_or_else(|| {
+        let mut accepted = units.base_unit.clone();
+        if let Some(purchase_unit) = &units.purchase_unit {
+            accepted = format!("{} or {}", accepted, purchase_unit);
+        }
+        format!("Component product {} is measured in {}, not {}", component_id, accepted, unit)
+    })
+}
+
+/// Cost of one unit of a prepared item, rolled up from its ingredients' current cost.
+/// Returns `None` for products without a recipe.
+pub fn recipe_cost(conn: &Connection, product_id: i32) -> Result<Option<f64>, String> {
+    Ok(load_recipe(conn, product_id)?.map(|recipe| recipe.total_cost))
+}
+
+/// Deduct the ingredients of `quantity` units of a prepared item.
+///
+/// Returns the ids of the consumed components (for stock alerts) and whether the finished
+/// item's own stock should still be deducted. Products without a recipe consume nothing
+/// and keep deducting their own stock.
+pub fn consume_recipe(conn: &Connection, product_id: i32, quantity: f64) -> Result<(Vec<i32>, bool), String> {
+    let recipe = match load_recipe(conn, product_id)? {
+        Some(recipe) => recipe,
+        None => return Ok((Vec::new(), true)),
+    };
+
+    let mut consumed = Vec::new();
+    for component in &recipe.components {
+        conn.execute(
+            "UPDATE products SET
+             current_stock = current_stock - ?1,
+             updated_at = CURRENT_TIMESTAMP
+             WHERE id = ?2",
+            params![component.base_quantity * quantity, component.component_id],
+        ).map_err(|e| format!("Failed to consume {}: {}", component.component_name, e))?;
+
+        consumed.push(component.component_id);
+    }
+
+    Ok((consumed, recipe.deduct_finished_item))
+}
+
+#[tauri::command]
+pub fn get_recipe(state: tauri::State<DbState>, product_id: i32) -> Result<Option<Recipe>, String> {
+    println!("Backend: Getting recipe for product {}", product_id);
+    let conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    load_recipe(&conn, product_id)
+}
+
+// The unit a component is entered in, as stored; blank means the component's base unit
+fn unit_of(component: &NewRecipeComponent) -> Option<&str> {
+    component.unit.as_deref().map(str::trim).filter(|unit| !unit.is_empty())
+}
+
+#[tauri::command]
+pub fn save_recipe(state: tauri::State<DbState>, recipe: SaveRecipe, token: String) -> Result<Recipe, String> {
+    println!("Backend: Saving recipe for product {} with {} components", recipe.product_id, recipe.components.len());
+
+    if recipe.components.is_empty() {
+        return Err("A recipe needs at least one component".to_string());
+    }
+
+    let mut conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let actor = authenticate(&conn, &token)?;
+    require_permission(&conn, &actor, EDIT_INVENTORY, "change recipes")?;
+    let tx = conn.transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let product = tx.query_row(
+        "SELECT id FROM products WHERE id = ?1",
+        params![recipe.product_id],
+        |row| row.get::<_, i32>(0),
+    ).optional().map_err(|e| format!("Failed to check product: {}", e))?;
+
+    if product.is_none() {
+        return Err(format!("Product {} not found", recipe.product_id));
+    }
+
+    let before = load_recipe(&tx, recipe.product_id)?;
+
+    for component in &recipe.components {
+        if component.component_id == recipe.product_id {
+            return Err("A product cannot be a component of its own recipe".to_string());
+        }
+        if component.quantity <= 0.0 {
+            return Err("Component quantities must be greater than zero".to_string());
+        }
+
+        let exists = tx.query_row(
+            "SELECT id FROM products WHERE id = ?1",
+            params![component.component_id],
+            |row| row.get::<_, i32>(0),
+        ).optional().map_err(|e| format!("Failed to check component: {}", e))?;
+
+        if exists.is_none() {
+            return Err(format!("Component product {} not found", component.component_id));
+        }
+
+        component_factor(&tx, component.component_id, unit_of(component))?;
+    }
+
+    // Replace the whole recipe so the caller always sends the full component list
+    tx.execute(
+        "INSERT INTO recipes (product_id, deduct_finished_item) VALUES (?1, ?2)
+         ON CONFLICT (product_id) DO UPDATE SET
+            deduct_finished_item = excluded.deduct_finished_item,
+            updated_at = datetime('now')",
+        params![recipe.product_id, recipe.deduct_finished_item.unwrap_or(false)],
+    ).map_err(|e| format!("Failed to save recipe: {}", e))?;
+
+    tx.execute("DELETE FROM recipe_components WHERE product_id = ?1", params![recipe.product_id])
+        .map_err(|e| format!("Failed to clear recipe components: {}", e))?;
+
+    for component in &recipe.components {
+        tx.execute(
+            "INSERT INTO recipe_components (product_id, component_id, quantity, unit) VALUES (?1, ?2, ?3, ?4)",
+            params![recipe.product_id, component.component_id, component.quantity, unit_of(component)],
+        ).map_err(|e| format!("Failed to insert recipe component: {}", e))?;
+    }
+
+    let saved = load_recipe(&tx, recipe.product_id)?
+        .ok_or_else(|| "Failed to read saved recipe".to_string())?;
+
+    record_audit(
+        &tx,
+        Some(&actor),
+        if before.is_some() { UPDATE } else { CREATE },
+        "recipe",
+        Some(recipe.product_id as i64),
+        before.and_then(|before| serde_json::to_value(&before).ok()),
+        serde_json::to_value(&saved).ok(),
+    )?;
+
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+    println!("Backend: Saved recipe for {} costing {:.2}", saved.product_name, saved.total_cost);
+    Ok(saved)
+}
+
+#[tauri::command]
+pub fn delete_recipe(state: tauri::State<DbState>, product_id: i32, token: String) -> Result<(), String> {
+    println!("Backend: Deleting recipe for product {}", product_id);
+    let mut conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let actor = authenticate(&conn, &token)?;
+    require_permission(&conn, &actor, EDIT_INVENTORY, "delete recipes")?;
+    let tx = conn.transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let before = load_recipe(&tx, product_id)?;
+
+    tx.execute("DELETE FROM recipe_components WHERE product_id = ?1", params![product_id])
+        .map_err(|e| format!("Failed to delete recipe components: {}", e))?;
+
+    let deleted = tx.execute("DELETE FROM recipes WHERE product_id = ?1", params![product_id])
+        .map_err(|e| format!("Failed to delete recipe: {}", e))?;
+
+    if deleted == 0 {
+        return Err("Recipe not found".to_string());
+    }
+
+    let before = before.and_then(|before| serde_json::to_value(&before).ok());
+    record_audit(&tx, Some(&actor), DELETE, "recipe", Some(product_id as i64), before, None)?;
+
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    Ok(())
+}
@@ -0,0 +1,441 @@
+use crate::commands::audit::record_audit;
+use crate::commands::auth::authenticate;
+use crate::db::models::audit::{CREATE, DELETE, UPDATE};
+use crate::db::models::role::{
+    NewRole, PermissionOverride, Role, UpdateRole, UserAccess, ADMIN_ROLE, MANAGE_STAFF, PERMISSIONS,
+};
+use crate::db::models::user::User;
+use crate::db::DbState;
+use rusqlite::{params, Connection, OptionalExtension, Result};
+
+const ROLE_QUERY: &str =
+    "SELECT r.id, r.name, r.description, r.built_in, r.created_at, r.updated_at,
+            (SELECT COUNT(*) FROM users u WHERE u.role = r.name) AS users
+     FROM roles r";
+
+fn role_permissions(conn: &Connection, role_id: i32) -> Result<Vec<String>, String> {
+    let mut stmt = conn.prepare("SELECT permission FROM role_permissions WHERE role_id = ?1")
+        .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+    let rows = stmt.query_map(params![role_id], |row| row.get::<_, String>(0))
+        .map_err(|e| format!("Failed to query role permissions: {}", e))?;
+
+    let granted = rows.collect::<Result<Vec<_>>>()
+        .map_err(|e| format!("Failed to collect role permissions: {}", e))?;
+
+    // Listed in the order of PERMISSIONS rather than the order they were granted in
+    Ok(PERMISSIONS.iter().filter(|p| granted.iter().any(|g| g == *p)).map(|p| p.to_string()).collect())
+}
+
+fn query_roles(conn: &Connection, condition: &str, param: Option<i32>) -> Result<Vec<Role>, String> {
+    let mut stmt = conn.prepare(&format!("{} {} ORDER BY r.built_in DESC, r.name", ROLE_QUERY, condition))
+        .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+    let rows = stmt.query_map(rusqlite::params_from_iter(param), |row| {
+        Ok(Role {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            description: row.get(2)?,
+            permissions: Vec::new(),
+            built_in: row.get(3)?,
+            users: row.get(6)?,
+            created_at: row.get(4)?,
+            updated_at: row.get(5)?,
+        })
+    }).map_err(|e| format!("Failed to query roles: {}", e))?;
+
+    let mut roles = rows.collect::<Result<Vec<_>>>()
+        .map_err(|e| format!("Failed to collect roles: {}", e))?;
+
+    for role in &mut roles {
+        role.permissions = if role.name == ADMIN_ROLE {
+            PERMISSIONS.iter().map(|p| p.to_string()).collect()
+        } else {
+            role_permissions(conn, role.id)?
+        };
+    }
+    Ok(roles)
+}
+
+fn load_role(conn: &Connection, id: i32) -> Result<Role, String> {
+    query_roles(conn, "WHERE r.id = ?1", Some(id))?
+        .pop()
+        .ok_or_else(|| format!("Role {} not found", id))
+}
+
+/// Everything a user may do: their role's permissions with their own overrides applied.
+/// Admins can always do everything.
+pub fn user_permissions(conn: &Connection, user_id: i32, role: &str) -> Result<Vec<String>, String> {
+    if role == ADMIN_ROLE {
+        return Ok(PERMISSIONS.iter().map(|p| p.to_string()).collect());
+    }
+
+    let role_id: Option<i32> = conn.query_row(
+        "SELECT id FROM roles WHERE name = ?1",
+        params![role],
+        |row| row.get(0),
+    ).optional().map_err(|e| format!("Failed to get role: {}", e))?;
+
+    let mut permissions = match role_id {
+        Some(role_id) => role_permissions(conn, role_id)?,
+        None => Vec::new(),
+    };
+
+    for change in load_overrides(conn, user_id)? {
+        permissions.retain(|p| *p != change.permission);
+        if change.granted {
+            permissions.push(change.permission);
+        }
+    }
+
+    Ok(PERMISSIONS.iter().filter(|p| permissions.iter().any(|g| g == *p)).map(|p| p.to_string()).collect())
+}
+
+/// Refuse an action the signed-in user doesn't have the permission for
+pub fn require_permission(conn: &Connection, user: &User, permission: &str, action: &str) -> Result<(), String> {
+    if !user_permissions(conn, user.id, &user.role)?.iter().any(|p| p == permission) {
+        return Err(format!("You don't have permission to {}", action));
+    }
+    Ok(())
+}
+
+/// Refuse an action only admins may take. Admin rights and the right to manage staff can only
+/// be handed out or taken away by an admin, so managing staff can't be used to climb higher.
+pub fn require_admin(user: &User, action: &str) -> Result<(), String> {
+    if user.role != ADMIN_ROLE {
+        return Err(format!("Only an admin can {}", action));
+    }
+    Ok(())
+}
+
+// Whether a user is an admin or can manage staff
+fn manages_staff(access: &UserAccess) -> bool {
+    access.role == ADMIN_ROLE || access.permissions.iter().any(|p| p == MANAGE_STAFF)
+}
+
+/// Refuse to act on an admin's account unless the signed-in user is an admin too
+pub fn check_can_manage_user(conn: &Connection, user: &User, user_id: i32, action: &str) -> Result<(), String> {
+    if load_user_access(conn, user_id)?.role == ADMIN_ROLE {
+        require_admin(user, action)?;
+    }
+    Ok(())
+}
+
+/// The name of an existing role, as stored
+pub fn check_role(conn: &Connection, role: &str) -> Result<String, String> {
+    let role = role.trim().to_lowercase();
+
+    conn.query_row("SELECT name FROM roles WHERE name = ?1", params![role], |row| row.get(0))
+        .optional()
+        .map_err(|e| format!("Failed to get role: {}", e))?
+        .ok_or_else(|| format!("Unknown role: {}", role))
+}
+
+fn load_overrides(conn: &Connection, user_id: i32) -> Result<Vec<PermissionOverride>, String> {
+    let mut stmt = conn.prepare(
+        "SELECT permission, granted FROM user_permission_overrides WHERE user_id = ?1 ORDER BY permission"
+    ).map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+    let rows = stmt.query_map(params![user_id], |row| {
+        Ok(PermissionOverride {
+            permission: row.get(0)?,
+            granted: row.get(1)?,
+        })
+    }).map_err(|e| format!("Failed to query permission overrides: {}", e))?;
+
+    rows.collect::<Result<Vec<_>>>()
+        .map_err(|e| format!("Failed to collect permission overrides: {}", e))
+}
+
+fn load_user_access(conn: &Connection, user_id: i32) -> Result<UserAccess, String> {
+    let (username, full_name, role, active): (String, String, String, bool) = conn.query_row(
+        "SELECT username, full_name, role, active FROM users WHERE id = ?1",
+        params![user_id],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+    ).map_err(|_| "User not found".to_string())?;
+
+    Ok(UserAccess {
+        user_id,
+        permissions: user_permissions(conn, user_id, &role)?,
+        overrides: load_overrides(conn, user_id)?,
+        username,
+        full_name,
+        role,
+        active,
+    })
+}
+
+fn check_permission_name(permission: &str) -> Result<(), String> {
+    if !PERMISSIONS.contains(&permission) {
+        return Err(format!("Unknown permission: {}", permission));
+    }
+    Ok(())
+}
+
+// Clean up a role's name and permissions before saving it
+fn validate_role(role: &NewRole) -> Result<(String, Vec<String>), String> {
+    let name = role.name.trim().to_lowercase();
+    if name.is_empty() {
+        return Err("Role name is required".to_string());
+    }
+    if name == ADMIN_ROLE {
+        return Err("The admin role is reserved".to_string());
+    }
+
+    let mut permissions = Vec::new();
+    for permission in &role.permissions {
+        check_permission_name(permission)?;
+        if !permissions.contains(permission) {
+            permissions.push(permission.clone());
+        }
+    }
+    Ok((name, permissions))
+}
+
+fn save_role_permissions(conn: &Connection, role_id: i32, permissions: &[String]) -> Result<(), String> {
+    conn.execute("DELETE FROM role_permissions WHERE role_id = ?1", params![role_id])
+        .map_err(|e| format!("Failed to clear role permissions: {}", e))?;
+
+    for permission in permissions {
+        conn.execute(
+            "INSERT INTO role_permissions (role_id, permission) VALUES (?1, ?2)",
+            params![role_id, permission],
+        ).map_err(|e| format!("Failed to save role permission: {}", e))?;
+    }
+    Ok(())
+}
+
+// Someone has to be left who can get into ManageAccess
+fn check_admins_left(conn: &Connection) -> Result<(), String> {
+    let admins: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM users WHERE role = ?1 AND active = 1",
+        params![ADMIN_ROLE],
+        |row| row.get(0),
+    ).map_err(|e| format!("Database error: {}", e))?;
+
+    if admins == 0 {
+        return Err("At least one active admin is required".to_string());
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_permissions() -> Vec<String> {
+    PERMISSIONS.iter().map(|p| p.to_string()).collect()
+}
+
+#[tauri::command]
+pub fn get_roles(state: tauri::State<DbState>) -> Result<Vec<Role>, String> {
+    let conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    query_roles(&conn, "", None)
+}
+
+#[tauri::command]
+pub fn add_role(state: tauri::State<DbState>, token: String, role: NewRole) -> Result<Role, String> {
+    let mut conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let user = authenticate(&conn, &token)?;
+    println!("Backend: {} adding role {}", user.username, role.name);
+    require_permission(&conn, &user, MANAGE_STAFF, "manage roles")?;
+
+    let (name, permissions) = validate_role(&role)?;
+    if permissions.iter().any(|p| p == MANAGE_STAFF) {
+        require_admin(&user, "create a role that can manage staff")?;
+    }
+
+    let tx = conn.transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+    tx.execute(
+        "INSERT INTO roles (name, description) VALUES (?1, ?2)",
+        params![name, role.description],
+    ).map_err(|e| format!("Failed to add role (is the name already taken?): {}", e))?;
+
+    let role_id = tx.last_insert_rowid() as i32;
+    save_role_permissions(&tx, role_id, &permissions)?;
+
+    let after = serde_json::to_value(load_role(&tx, role_id)?).ok();
+    record_audit(&tx, Some(&user), CREATE, "role", Some(role_id as i64), None, after)?;
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+    load_role(&conn, role_id)
+}
+
+/// Rename a role or change what it allows. Users with the role move with a rename.
+#[tauri::command]
+pub fn update_role(state: tauri::State<DbState>, token: String, role: UpdateRole) -> Result<Role, String> {
+    let mut conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let user = authenticate(&conn, &token)?;
+    println!("Backend: {} updating role {}", user.username, role.id);
+    require_permission(&conn, &user, MANAGE_STAFF, "manage roles")?;
+
+    let existing = load_role(&conn, role.id)?;
+    if existing.name == ADMIN_ROLE {
+        return Err("The admin role always has every permission and can't be changed".to_string());
+    }
+    let (name, permissions) = validate_role(&role.role)?;
+    if existing.permissions.iter().chain(&permissions).any(|p| p == MANAGE_STAFF) {
+        require_admin(&user, "change a role that can manage staff")?;
+    }
+
+    let tx = conn.transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+    tx.execute(
+        "UPDATE roles SET name = ?1, description = ?2, updated_at = datetime('now') WHERE id = ?3",
+        params![name, role.role.description, role.id],
+    ).map_err(|e| format!("Failed to update role (is the name already taken?): {}", e))?;
+
+    if name != existing.name {
+        tx.execute(
+            "UPDATE users SET role = ?1, updated_at = datetime('now') WHERE role = ?2",
+            params![name, existing.name],
+        ).map_err(|e| format!("Failed to move users to the renamed role: {}", e))?;
+    }
+
+    save_role_permissions(&tx, role.id, &permissions)?;
+
+    let after = serde_json::to_value(load_role(&tx, role.id)?).ok();
+    record_audit(&tx, Some(&user), UPDATE, "role", Some(role.id as i64), serde_json::to_value(&existing).ok(), after)?;
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+    load_role(&conn, role.id)
+}
+
+#[tauri::command]
+pub fn delete_role(state: tauri::State<DbState>, token: String, id: i32) -> Result<(), String> {
+    let mut conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let user = authenticate(&conn, &token)?;
+    println!("Backend: {} deleting role {}", user.username, id);
+    require_permission(&conn, &user, MANAGE_STAFF, "manage roles")?;
+
+    let role = load_role(&conn, id)?;
+    if role.built_in {
+        return Err(format!("The {} role is built in and can't be deleted", role.name));
+    }
+    if role.users > 0 {
+        return Err(format!("{} user(s) still have the {} role; give them another role first", role.users, role.name));
+    }
+
+    let tx = conn.transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+    tx.execute("DELETE FROM role_permissions WHERE role_id = ?1", params![id])
+        .map_err(|e| format!("Failed to delete role permissions: {}", e))?;
+    tx.execute("DELETE FROM roles WHERE id = ?1", params![id])
+        .map_err(|e| format!("Failed to delete role: {}", e))?;
+    record_audit(&tx, Some(&user), DELETE, "role", Some(id as i64), serde_json::to_value(&role).ok(), None)?;
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+    Ok(())
+}
+
+/// Every user with their role and effective permissions, for ManageAccess
+#[tauri::command]
+pub fn get_users(state: tauri::State<DbState>, token: String) -> Result<Vec<UserAccess>, String> {
+    let conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let user = authenticate(&conn, &token)?;
+    require_permission(&conn, &user, MANAGE_STAFF, "view staff accounts")?;
+
+    let ids = {
+        let mut stmt = conn.prepare("SELECT id FROM users ORDER BY active DESC, full_name")
+            .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let rows = stmt.query_map([], |row| row.get::<_, i32>(0))
+            .map_err(|e| format!("Failed to query users: {}", e))?;
+
+        rows.collect::<Result<Vec<_>>>()
+            .map_err(|e| format!("Failed to collect users: {}", e))?
+    };
+
+    ids.into_iter().map(|id| load_user_access(&conn, id)).collect()
+}
+
+/// A user's role and permissions. Anyone can look up their own, e.g. to decide which
+/// screens to show.
+#[tauri::command]
+pub fn get_user_access(state: tauri::State<DbState>, token: String, user_id: i32) -> Result<UserAccess, String> {
+    let conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let user = authenticate(&conn, &token)?;
+    if user.id != user_id {
+        require_permission(&conn, &user, MANAGE_STAFF, "view other users' access")?;
+    }
+
+    load_user_access(&conn, user_id)
+}
+
+#[tauri::command]
+pub fn set_user_role(state: tauri::State<DbState>, token: String, user_id: i32, role: String) -> Result<UserAccess, String> {
+    let mut conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let user = authenticate(&conn, &token)?;
+    println!("Backend: {} giving user {} the role {}", user.username, user_id, role);
+    require_permission(&conn, &user, MANAGE_STAFF, "change roles")?;
+
+    let tx = conn.transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let role = check_role(&tx, &role)?;
+    let before = load_user_access(&tx, user_id)?;
+
+    tx.execute(
+        "UPDATE users SET role = ?1, updated_at = datetime('now') WHERE id = ?2",
+        params![role, user_id],
+    ).map_err(|e| format!("Failed to set role: {}", e))?;
+    check_admins_left(&tx)?;
+
+    let after = load_user_access(&tx, user_id)?;
+    if manages_staff(&before) || manages_staff(&after) {
+        require_admin(&user, "give or take away admin or staff management rights")?;
+    }
+
+    record_audit(
+        &tx,
+        Some(&user),
+        UPDATE,
+        "user",
+        Some(user_id as i64),
+        serde_json::to_value(&before).ok(),
+        serde_json::to_value(&after).ok(),
+    )?;
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+    load_user_access(&conn, user_id)
+}
+
+/// Grant (true) or take away (false) one permission for a user regardless of their role;
+/// no value goes back to what the role allows
+#[tauri::command]
+pub fn set_permission_override(
+    state: tauri::State<DbState>,
+    token: String,
+    user_id: i32,
+    permission: String,
+    granted: Option<bool>,
+) -> Result<UserAccess, String> {
+    let conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let user = authenticate(&conn, &token)?;
+    println!("Backend: {} overriding {} for user {}: {:?}", user.username, permission, user_id, granted);
+    require_permission(&conn, &user, MANAGE_STAFF, "change permissions")?;
+    check_permission_name(&permission)?;
+
+    // Checked here so a missing user gives an error rather than a stray override
+    let before = load_user_access(&conn, user_id)?;
+    if permission == MANAGE_STAFF || before.role == ADMIN_ROLE {
+        require_admin(&user, "give or take away admin or staff management rights")?;
+    }
+
+    match granted {
+        Some(granted) => conn.execute(
+            "INSERT INTO user_permission_overrides (user_id, permission, granted) VALUES (?1, ?2, ?3)
+             ON CONFLICT (user_id, permission) DO UPDATE SET granted = excluded.granted",
+            params![user_id, permission, granted],
+        ),
+        None => conn.execute(
+            "DELETE FROM user_permission_overrides WHERE user_id = ?1 AND permission = ?2",
+            params![user_id, permission],
+        ),
+    }.map_err(|e| format!("Failed to save permission override: {}", e))?;
+
+    let access = load_user_access(&conn, user_id)?;
+    record_audit(
+        &conn,
+        Some(&user),
+        UPDATE,
+        "user",
+        Some(user_id as i64),
+        serde_json::to_value(&before).ok(),
+        serde_json::to_value(&access).ok(),
+    )?;
+    Ok(access)
+}
@@ -0,0 +1,127 @@
+use crate::commands::audit::{record_audit, snapshot};
+use crate::commands::auth::authenticate;
+use crate::commands::role::require_permission;
+use crate::db::models::audit::UPDATE;
+use crate::db::models::role::MANAGE_SETTINGS;
+use crate::db::models::security::{SaveSecuritySettings, SecuritySettings};
+use crate::db::DbState;
+use rusqlite::{params, Connection, OptionalExtension};
+
+pub fn load_security_settings(conn: &Connection) -> Result<SecuritySettings, String> {
+    let settings = conn.query_row(
+        "SELECT min_password_length, require_letter, require_digit, require_mixed_case, require_symbol,
+                max_failed_logins, lockout_minutes, updated_at
+         FROM security_settings WHERE id = 1",
+        [],
+        |row| {
+            Ok(SecuritySettings {
+                min_password_length: row.get(0)?,
+                require_letter: row.get(1)?,
+                require_digit: row.get(2)?,
+                require_mixed_case: row.get(3)?,
+                require_symbol: row.get(4)?,
+                max_failed_logins: row.get(5)?,
+                lockout_minutes: row.get(6)?,
+                updated_at: row.get(7)?,
+            })
+        },
+    ).optional().map_err(|e| format!("Failed to get security settings: {}", e))?;
+
+    Ok(settings.unwrap_or(SecuritySettings {
+        min_password_length: 8,
+        require_letter: true,
+        require_digit: true,
+        require_mixed_case: false,
+        require_symbol: false,
+        max_failed_logins: 5,
+        lockout_minutes: 15,
+        updated_at: String::new(),
+    }))
+}
+
+/// Check a new password against the password policy, naming every rule it breaks
+pub fn check_password_policy(conn: &Connection, password: &str) -> Result<(), String> {
+    let settings = load_security_settings(conn)?;
+    let mut problems = Vec::new();
+
+    if (password.chars().count() as i32) < settings.min_password_length {
+        problems.push(format!("be at least {} characters long", settings.min_password_length));
+    }
+    if settings.require_letter && !password.chars().any(|c| c.is_alphabetic()) {
+        problems.push("contain a letter".to_string());
+    }
+    if settings.require_digit && !password.chars().any(|c| c.is_ascii_digit()) {
+        problems.push("contain a digit".to_string());
+    }
+    if settings.require_mixed_case
+        && !(password.chars().any(|c| c.is_uppercase()) && password.chars().any(|c| c.is_lowercase()))
+    {
+        problems.push("mix upper and lower case letters".to_string());
+    }
+    if settings.require_symbol && password.chars().all(|c| c.is_alphanumeric()) {
+        problems.push("contain a symbol".to_string());
+    }
+
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("Password must {}", problems.join(", ")))
+    }
+}
+
+#[tauri::command]
+pub fn get_security_settings(state: tauri::State<DbState>) -> Result<SecuritySettings, String> {
+    let conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    load_security_settings(&conn)
+}
+
+#[tauri::command]
+pub fn save_security_settings(state: tauri::State<DbState>, token: String, settings: SaveSecuritySettings) -> Result<SecuritySettings, String> {
+    let mut conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let user = authenticate(&conn, &token)?;
+    println!("Backend: {} saving security settings: {:?}", user.username, settings);
+
+    require_permission(&conn, &user, MANAGE_SETTINGS, "change security settings")?;
+    if !(4..=128).contains(&settings.min_password_length) {
+        return Err("Minimum password length must be between 4 and 128".to_string());
+    }
+    if settings.max_failed_logins < 1 {
+        return Err("Allow at least one failed login before locking an account".to_string());
+    }
+    if settings.lockout_minutes < 1 {
+        return Err("Lockout must last at least a minute".to_string());
+    }
+
+    let tx = conn.transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let before = snapshot(&tx, "security_settings", 1)?;
+
+    tx.execute(
+        "INSERT INTO security_settings (id, min_password_length, require_letter, require_digit, require_mixed_case,
+                                        require_symbol, max_failed_logins, lockout_minutes)
+         VALUES (1, ?1, ?2, ?3, ?4, ?5, ?6, ?7)
+         ON CONFLICT (id) DO UPDATE SET
+            min_password_length = excluded.min_password_length,
+            require_letter = excluded.require_letter,
+            require_digit = excluded.require_digit,
+            require_mixed_case = excluded.require_mixed_case,
+            require_symbol = excluded.require_symbol,
+            max_failed_logins = excluded.max_failed_logins,
+            lockout_minutes = excluded.lockout_minutes,
+            updated_at = datetime('now')",
+        params![
+            settings.min_password_length,
+            settings.require_letter,
+            settings.require_digit,
+            settings.require_mixed_case,
+            settings.require_symbol,
+            settings.max_failed_logins,
+            settings.lockout_minutes
+        ],
+    ).map_err(|e| format!("Failed to save security settings: {}", e))?;
+
+    let after = snapshot(&tx, "security_settings", 1)?;
+    record_audit(&tx, Some(&user), UPDATE, "security_settings", Some(1), before, after)?;
+
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    load_security_settings(&conn)
+}
@@ -0,0 +1,391 @@
+use crate::commands::audit::{record_audit, snapshot};
+use crate::commands::auth::authenticate;
+use crate::commands::role::require_permission;
+use crate::db::models::audit::{CREATE, UPDATE};
+use crate::db::models::payment::{PaymentMethodSales, CASH};
+use crate::db::models::role::{SELL, VIEW_REPORTS};
+use crate::db::models::shift::{
+    CashMovement, NewCashMovement, OrderTotals, Shift, ShiftReport, CASH_IN, CASH_OUT, X_REPORT, Z_REPORT,
+};
+use crate::db::models::user::User;
+use crate::db::DbState;
+use rusqlite::{params, Connection, OptionalExtension, Result};
+use std::path::Path;
+
+// Order statuses that don't count as sales
+const REFUNDED: &str = "refunded";
+const VOIDED: [&str; 2] = ["voided", "cancelled"];
+
+const SHIFT_COLUMNS: &str =
+    "id, user_id, cashier, opening_float, opened_at, closed_at, counted_cash, expected_cash, over_short, notes";
+
+fn map_shift(row: &rusqlite::Row) -> Result<Shift> {
+    Ok(Shift {
+        id: row.get(0)?,
+        user_id: row.get(1)?,
+        cashier: row.get(2)?,
+        opening_float: row.get(3)?,
+        opened_at: row.get(4)?,
+        closed_at: row.get(5)?,
+        counted_cash: row.get(6)?,
+        expected_cash: row.get(7)?,
+        over_short: row.get(8)?,
+        notes: row.get(9)?,
+    })
+}
+
+fn load_shift(conn: &Connection, id: i32) -> Result<Shift, String> {
+    conn.query_row(
+        &format!("SELECT {} FROM shifts WHERE id = ?1", SHIFT_COLUMNS),
+        params![id],
+        map_shift,
+    ).map_err(|e| format!("Failed to get shift {}: {}", id, e))
+}
+
+fn open_shift_for_user(conn: &Connection, user_id: i32) -> Result<Option<Shift>, String> {
+    conn.query_row(
+        &format!("SELECT {} FROM shifts WHERE user_id = ?1 AND closed_at IS NULL", SHIFT_COLUMNS),
+        params![user_id],
+        map_shift,
+    ).optional().map_err(|e| format!("Failed to get open shift: {}", e))
+}
+
+/// The open shift an order belongs to: the one the register names, or else the signed-in
+/// user's open shift. A named shift must be open and belong to that user. Orders outside
+/// any shift get `None`.
+pub fn resolve_order_shift(conn: &Connection, shift_id: Option<i32>, user: &User) -> Result<Option<i32>, String> {
+    if let Some(shift_id) = shift_id {
+        let shift = load_shift(conn, shift_id)?;
+        if shift.closed_at.is_some() {
+            return Err(format!("Shift {} is already closed", shift_id));
+        }
+        if shift.user_id != user.id {
+            return Err(format!("Shift {} belongs to {}", shift_id, shift.cashier));
+        }
+        return Ok(Some(shift_id));
+    }
+
+    Ok(open_shift_for_user(conn, user.id)?.map(|shift| shift.id))
+}
+
+fn order_totals(conn: &Connection, shift_id: i32, statuses: &[&str]) -> Result<OrderTotals, String> {
+    let placeholders = vec!["?"; statuses.len()].join(", ");
+    let mut query_params: Vec<&dyn rusqlite::ToSql> = vec![&shift_id];
+    for status in statuses {
+        query_params.push(status);
+    }
+
+    conn.query_row(
+        &format!(
+            "SELECT COUNT(*), COALESCE(SUM(total), 0) FROM orders WHERE shift_id = ? AND status IN ({})",
+            placeholders
+        ),
+        query_params.as_slice(),
+        |row| Ok(OrderTotals { count: row.get(0)?, amount: row.get(1)? }),
+    ).map_err(|e| format!("Failed to total shift orders: {}", e))
+}
+
+/// Build the X (mid-shift) or Z (closing) report of a shift from its orders and cash movements
+fn build_report(conn: &Connection, shift: Shift, kind: &str) -> Result<ShiftReport, String> {
+    let excluded = format!("'{}', '{}', '{}'", REFUNDED, VOIDED[0], VOIDED[1]);
+
+    let (orders, gross_sales, tax): (i64, f64, f64) = conn.query_row(
+        &format!(
+            "SELECT COUNT(*), COALESCE(SUM(total), 0), COALESCE(SUM(tax), 0)
+             FROM orders WHERE shift_id = ?1 AND status NOT IN ({})",
+            excluded
+        ),
+        params![shift.id],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+    ).map_err(|e| format!("Failed to total shift sales: {}", e))?;
+
+    let payments = {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT op.method, COUNT(DISTINCT op.order_id), SUM(op.amount)
+             FROM order_payments op
+             JOIN orders o ON o.id = op.order_id
+             WHERE o.shift_id = ?1 AND o.status NOT IN ({})
+             GROUP BY op.method
+             ORDER BY SUM(op.amount) DESC",
+            excluded
+        )).map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let rows = stmt.query_map(params![shift.id], |row| {
+            Ok(PaymentMethodSales {
+                method: row.get(0)?,
+                transactions: row.get(1)?,
+                amount: row.get(2)?,
+            })
+        }).map_err(|e| format!("Failed to query shift payments: {}", e))?;
+
+        rows.collect::<Result<Vec<_>>>()
+            .map_err(|e| format!("Failed to collect shift payments: {}", e))?
+    };
+
+    let (cash_in, cash_out): (f64, f64) = conn.query_row(
+        "SELECT COALESCE(SUM(CASE WHEN kind = ?2 THEN amount END), 0),
+                COALESCE(SUM(CASE WHEN kind = ?3 THEN amount END), 0)
+         FROM cash_movements WHERE shift_id = ?1",
+        params![shift.id, CASH_IN, CASH_OUT],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    ).map_err(|e| format!("Failed to total cash movements: {}", e))?;
+
+    let cash_sales: f64 = payments.iter().filter(|p| p.method == CASH).map(|p| p.amount).sum();
+    let expected_cash = shift.opening_float + cash_sales + cash_in - cash_out;
+
+    Ok(ShiftReport {
+        kind: kind.to_string(),
+        generated_at: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        orders,
+        gross_sales,
+        tax,
+        net_sales: gross_sales - tax,
+        payments,
+        refunds: order_totals(conn, shift.id, &[REFUNDED])?,
+        voids: order_totals(conn, shift.id, &VOIDED)?,
+        cash_sales,
+        cash_in,
+        cash_out,
+        expected_cash,
+        counted_cash: shift.counted_cash,
+        over_short: shift.over_short,
+        shift,
+    })
+}
+
+#[tauri::command]
+pub fn open_shift(state: tauri::State<DbState>, token: String, opening_float: f64, notes: Option<String>) -> Result<Shift, String> {
+    let mut conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let user = authenticate(&conn, &token)?;
+    require_permission(&conn, &user, SELL, "open a shift")?;
+    println!("Backend: Opening shift for {} with float {:.2}", user.username, opening_float);
+
+    if !opening_float.is_finite() || opening_float < 0.0 {
+        return Err("Opening float cannot be negative".to_string());
+    }
+
+    let tx = conn.transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+    if open_shift_for_user(&tx, user.id)?.is_some() {
+        return Err("You already have an open shift".to_string());
+    }
+
+    let opened_at = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    tx.execute(
+        "INSERT INTO shifts (user_id, cashier, opening_float, opened_at, notes) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![user.id, user.full_name, opening_float, opened_at, notes],
+    ).map_err(|e| format!("Failed to open shift: {}", e))?;
+
+    let id = tx.last_insert_rowid();
+    let after = snapshot(&tx, "shifts", id)?;
+    record_audit(&tx, Some(&user), CREATE, "shift", Some(id), None, after)?;
+
+    let shift = load_shift(&tx, id as i32)?;
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    Ok(shift)
+}
+
+#[tauri::command]
+pub fn get_current_shift(state: tauri::State<DbState>, token: String) -> Result<Option<Shift>, String> {
+    let conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let user = authenticate(&conn, &token)?;
+    open_shift_for_user(&conn, user.id)
+}
+
+#[tauri::command]
+pub fn get_shifts(state: tauri::State<DbState>, token: String, limit: Option<i32>) -> Result<Vec<Shift>, String> {
+    let conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let user = authenticate(&conn, &token)?;
+    require_permission(&conn, &user, VIEW_REPORTS, "view shifts")?;
+
+    let mut stmt = conn.prepare(
+        &format!("SELECT {} FROM shifts ORDER BY opened_at DESC, id DESC LIMIT ?1", SHIFT_COLUMNS)
+    ).map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+    let rows = stmt.query_map(params![limit.unwrap_or(50)], map_shift)
+        .map_err(|e| format!("Failed to query shifts: {}", e))?;
+
+    rows.collect::<Result<Vec<_>>>()
+        .map_err(|e| format!("Failed to collect shifts: {}", e))
+}
+
+// Money put into or taken out of the drawer outside of sales, e.g. change top-ups or payouts
+#[tauri::command]
+pub fn add_cash_movement(state: tauri::State<DbState>, token: String, movement: NewCashMovement) -> Result<CashMovement, String> {
+    let mut conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let user = authenticate(&conn, &token)?;
+    require_permission(&conn, &user, SELL, "record cash in or out")?;
+    println!("Backend: Recording {} of {:.2} for {}", movement.kind, movement.amount, user.username);
+
+    if movement.kind != CASH_IN && movement.kind != CASH_OUT {
+        return Err(format!("Unknown cash movement: {}", movement.kind));
+    }
+    if !movement.amount.is_finite() || movement.amount <= 0.0 {
+        return Err("Amount must be greater than zero".to_string());
+    }
+
+    let tx = conn.transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let shift = open_shift_for_user(&tx, user.id)?
+        .ok_or_else(|| "Open a shift before recording cash in or out".to_string())?;
+
+    let created_at = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    tx.execute(
+        "INSERT INTO cash_movements (shift_id, kind, amount, reason, created_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![shift.id, movement.kind, movement.amount, movement.reason, created_at],
+    ).map_err(|e| format!("Failed to record cash movement: {}", e))?;
+
+    let id = tx.last_insert_rowid();
+    let after = snapshot(&tx, "cash_movements", id)?;
+    record_audit(&tx, Some(&user), CREATE, "cash_movement", Some(id), None, after)?;
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+    Ok(CashMovement {
+        id: id as i32,
+        shift_id: shift.id,
+        kind: movement.kind,
+        amount: movement.amount,
+        reason: movement.reason,
+        created_at,
+    })
+}
+
+#[tauri::command]
+pub fn get_cash_movements(state: tauri::State<DbState>, shift_id: i32) -> Result<Vec<CashMovement>, String> {
+    let conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+
+    let mut stmt = conn.prepare(
+        "SELECT id, shift_id, kind, amount, reason, created_at FROM cash_movements WHERE shift_id = ?1 ORDER BY id"
+    ).map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+    let rows = stmt.query_map(params![shift_id], |row| {
+        Ok(CashMovement {
+            id: row.get(0)?,
+            shift_id: row.get(1)?,
+            kind: row.get(2)?,
+            amount: row.get(3)?,
+            reason: row.get(4)?,
+            created_at: row.get(5)?,
+        })
+    }).map_err(|e| format!("Failed to query cash movements: {}", e))?;
+
+    rows.collect::<Result<Vec<_>>>()
+        .map_err(|e| format!("Failed to collect cash movements: {}", e))
+}
+
+/// X report of an open shift, or the stored Z report of a closed one
+#[tauri::command]
+pub fn get_shift_report(state: tauri::State<DbState>, token: String, shift_id: i32) -> Result<ShiftReport, String> {
+    let conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let user = authenticate(&conn, &token)?;
+    require_permission(&conn, &user, VIEW_REPORTS, "view shift reports")?;
+
+    let stored: Option<String> = conn.query_row(
+        "SELECT z_report FROM shifts WHERE id = ?1",
+        params![shift_id],
+        |row| row.get(0),
+    ).map_err(|e| format!("Failed to get shift {}: {}", shift_id, e))?;
+
+    match stored {
+        Some(report) => serde_json::from_str(&report).map_err(|e| format!("Failed to read Z report: {}", e)),
+        None => build_report(&conn, load_shift(&conn, shift_id)?, X_REPORT),
+    }
+}
+
+#[tauri::command]
+pub fn close_shift(state: tauri::State<DbState>, token: String, counted_cash: f64, notes: Option<String>) -> Result<ShiftReport, String> {
+    let mut conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let user = authenticate(&conn, &token)?;
+    require_permission(&conn, &user, SELL, "close a shift")?;
+    println!("Backend: Closing shift for {} with {:.2} counted", user.username, counted_cash);
+
+    if !counted_cash.is_finite() || counted_cash < 0.0 {
+        return Err("Counted cash cannot be negative".to_string());
+    }
+
+    let tx = conn.transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let shift = open_shift_for_user(&tx, user.id)?
+        .ok_or_else(|| "You have no open shift".to_string())?;
+    let before = snapshot(&tx, "shifts", shift.id as i64)?;
+
+    let mut report = build_report(&tx, shift, Z_REPORT)?;
+    report.counted_cash = Some(counted_cash);
+    report.over_short = Some(counted_cash - report.expected_cash);
+
+    let shift = &mut report.shift;
+    shift.closed_at = Some(report.generated_at.clone());
+    shift.counted_cash = report.counted_cash;
+    shift.expected_cash = Some(report.expected_cash);
+    shift.over_short = report.over_short;
+    if notes.is_some() {
+        shift.notes = notes;
+    }
+
+    let stored = serde_json::to_string(&report).map_err(|e| format!("Failed to store Z report: {}", e))?;
+    let shift = &report.shift;
+
+    tx.execute(
+        "UPDATE shifts SET closed_at = ?1, counted_cash = ?2, expected_cash = ?3, over_short = ?4, notes = ?5, z_report = ?6
+         WHERE id = ?7",
+        params![shift.closed_at, shift.counted_cash, shift.expected_cash, shift.over_short, shift.notes, stored, shift.id],
+    ).map_err(|e| format!("Failed to close shift: {}", e))?;
+
+    let after = snapshot(&tx, "shifts", shift.id as i64)?;
+    record_audit(&tx, Some(&user), UPDATE, "shift", Some(shift.id as i64), before, after)?;
+
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+    println!("Backend: Closed shift {} with over/short {:.2}", shift.id, counted_cash - report.expected_cash);
+    Ok(report)
+}
+
+// Write a shift report as CSV for the back office
+#[tauri::command]
+pub fn export_shift_report(state: tauri::State<DbState>, token: String, shift_id: i32, path: String) -> Result<String, String> {
+    // Validate path directory exists
+    let export_path = Path::new(&path);
+    if let Some(parent) = export_path.parent() {
+        if !parent.as_os_str().is_empty() && !parent.exists() {
+            return Err("Invalid export path".into());
+        }
+    }
+
+    let report = get_shift_report(state, token, shift_id)?;
+    let shift = &report.shift;
+
+    let money = |value: f64| format!("{:.2}", value);
+    let optional = |value: Option<f64>| value.map(money).unwrap_or_default();
+
+    let mut rows: Vec<(String, String)> = vec![
+        ("Report".into(), format!("{} report", report.kind)),
+        ("Generated".into(), report.generated_at.clone()),
+        ("Shift".into(), shift.id.to_string()),
+        ("Cashier".into(), shift.cashier.clone()),
+        ("Opened".into(), shift.opened_at.clone()),
+        ("Closed".into(), shift.closed_at.clone().unwrap_or_default()),
+        ("Orders".into(), report.orders.to_string()),
+        ("Gross sales".into(), money(report.gross_sales)),
+        ("Tax".into(), money(report.tax)),
+        ("Net sales".into(), money(report.net_sales)),
+    ];
+    for payment in &report.payments {
+        rows.push((format!("Payments: {}", payment.method), money(payment.amount)));
+    }
+    rows.extend([
+        ("Refunds".into(), format!("{} ({})", money(report.refunds.amount), report.refunds.count)),
+        ("Voids".into(), format!("{} ({})", money(report.voids.amount), report.voids.count)),
+        ("Opening float".into(), money(shift.opening_float)),
+        ("Cash sales".into(), money(report.cash_sales)),
+        ("Cash in".into(), money(report.cash_in)),
+        ("Cash out".into(), money(report.cash_out)),
+        ("Expected cash".into(), money(report.expected_cash)),
+        ("Counted cash".into(), optional(report.counted_cash)),
+        ("Over/short".into(), optional(report.over_short)),
+    ]);
+
+    let csv: String = rows
+        .iter()
+        .map(|(label, value)| format!("\"{}\",\"{}\"\n", label.replace('"', "\"\""), value.replace('"', "\"\"")))
+        .collect();
+
+    std::fs::write(export_path, csv).map_err(|e| format!("Failed to write shift report: {}", e))?;
+    Ok(path)
+}
@@ -1,12 +1,52 @@
+use crate::commands::audit::{record_audit, snapshot};
+use crate::commands::auth::{authenticate, terminal_cashier};
+use crate::commands::customer::{charge_account, check_order_customer};
+use crate::commands::discount::{apply_discounts, line_discounts, load_order_discounts, record_discounts};
+use crate::commands::notification::{emit_stock_alerts, evaluate_stock_alerts, evaluate_variant_stock_alerts};
+use crate::commands::payment::{load_order_payments, record_payments};
+use crate::commands::price::apply_due_price_changes;
+use crate::commands::role::require_permission;
+use crate::commands::wallet::pay_from_wallets;
+use crate::commands::recipe::consume_recipe;
+use crate::commands::shift::resolve_order_shift;
+use crate::commands::unit::check_quantity;
+use crate::commands::variant::{price_order_line, PricedLine};
+use crate::db::models::audit::CREATE;
+use crate::db::models::discount::{DiscountRequest, DiscountSales, PricedOrder, PricedOrderLine};
+use crate::db::models::notification::Notification;
 use crate::db::models::order::{NewOrder, NewOrderItem, Order, OrderItem, OrderWithItems};
+use crate::db::models::payment::{NewPayment, PaymentMethodSales, ACCOUNT};
+use crate::db::models::role::{SELL, VIEW_REPORTS};
+use crate::db::models::user::User;
+use crate::db::models::variant::OrderItemModifier;
 use crate::db::DbState;
-use rusqlite::{params, Result};
+use rusqlite::{params, Connection, OptionalExtension, Result};
 use serde::{Deserialize, Serialize};
 
+const DEFAULT_TERMINAL: &str = "POS";
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CreateOrderRequest {
     pub order: NewOrder,
     pub items: Vec<NewOrderItem>,
+    // One or more payments (split across methods) that together settle the total
+    #[serde(default)]
+    pub payments: Vec<NewPayment>,
+    // Register shift the sale belongs to; defaults to the cashier's open shift
+    #[serde(default)]
+    pub shift_id: Option<i32>,
+    // Discount on the whole order, split over its lines
+    #[serde(default)]
+    pub discount: Option<DiscountRequest>,
+    // Register the order is rung up on; its order numbers run separately from other terminals
+    #[serde(default)]
+    pub terminal: Option<String>,
+    // Generated by the register once per sale; a retry with the same key gets the same order back
+    #[serde(default)]
+    pub idempotency_key: Option<String>,
+    // Customer the sale is for; needed when any of it is charged to an account
+    #[serde(default)]
+    pub customer_id: Option<i32>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -31,8 +71,15 @@ pub struct SalesSummary {
     pub total_sales: i64,
     pub total_revenue: f64,
     pub total_profit: f64,
-    pub items_sold: i64,
+    pub items_sold: f64,
     pub transactions: i64,
+    #[serde(default)]
+    pub payment_methods: Vec<PaymentMethodSales>,
+    // Revenue and profit are net of discounts; these are the discounts given
+    #[serde(default)]
+    pub total_discounts: f64,
+    #[serde(default)]
+    pub discounts: Vec<DiscountSales>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -49,14 +96,21 @@ pub struct CategorySales {
     pub revenue: f64,
     pub profit: f64,
     pub percentage: f64,
+    // Empty for sales of products whose category no longer exists
+    #[serde(default)]
+    pub category_id: Option<i32>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ProductSales {
     pub product: String,
-    pub quantity: i64,
+    pub quantity: f64,
     pub revenue: f64,
     pub profit: f64,
+    // Quantity shown in both the base unit and, when defined, the purchase unit
+    pub unit: String,
+    pub purchase_unit: Option<String>,
+    pub purchase_quantity: Option<f64>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -69,35 +123,205 @@ pub struct DetailedSale {
     pub profit: f64,
     pub margin: String,
     pub revenue: f64,
-    pub quantity: i64,
+    pub quantity: f64,
+    pub unit: String,
+    pub purchase_unit: Option<String>,
+    pub purchase_quantity: Option<f64>,
+    // Revenue is net of this line discount
+    pub discount: f64,
+}
+
+fn round_cents(amount: f64) -> f64 {
+    (amount * 100.0).round() / 100.0
+}
+
+/// Price every line of an order and apply its discounts.
+///
+/// The subtotal is the sum of the priced lines after their discounts; the register's
+/// totals are only used for its tax rate, which is applied to that subtotal.
+pub fn price_order(conn: &Connection, request: &CreateOrderRequest) -> Result<(Vec<PricedLine>, PricedOrder), String> {
+    let mut lines = Vec::with_capacity(request.items.len());
+    for item in &request.items {
+        // Whole-unit products can't be sold in fractions
+        check_quantity(conn, item.product_id, item.quantity)?;
+        
+        // Price the line from the catalogue when it uses a variant or modifiers
+        lines.push(price_order_line(conn, item)?);
+    }
+    
+    let unit_prices: Vec<f64> = lines.iter().map(|line| line.unit_price).collect();
+    let discounts = apply_discounts(conn, &request.items, &unit_prices, request.discount.as_ref())?;
+    let per_line = line_discounts(&discounts, lines.len());
+    let discount = round_cents(per_line.iter().sum());
+    
+    let priced_lines: Vec<PricedOrderLine> = request.items.iter().zip(&lines).zip(&per_line).map(|((item, line), discount)| {
+        PricedOrderLine {
+            product_id: item.product_id,
+            variant_id: item.variant_id,
+            product_name: line.product_name.clone(),
+            variant_name: line.variant_name.clone(),
+            quantity: item.quantity,
+            unit_price: line.unit_price,
+            discount: *discount,
+            total: round_cents(item.quantity * line.unit_price - discount),
+        }
+    }).collect();
+    
+    let order = &request.order;
+    let subtotal = round_cents(priced_lines.iter().map(|line| line.total).sum::<f64>().max(0.0));
+    let tax = if order.subtotal > 0.0 { round_cents(order.tax * subtotal / order.subtotal) } else { 0.0 };
+    let total = round_cents(subtotal + tax);
+    
+    Ok((lines, PricedOrder {
+        lines: priced_lines,
+        discounts,
+        subtotal,
+        discount,
+        tax,
+        total,
+    }))
 }
 
+// Lets the register show discounts and the amount due before taking payment
 #[tauri::command]
-pub fn create_order(state: tauri::State<DbState>, request: CreateOrderRequest) -> Result<Order, String> {
-    println!("Backend: Creating new order: {}", request.order.order_id);
+pub fn preview_order(state: tauri::State<DbState>, request: CreateOrderRequest) -> Result<PricedOrder, String> {
+    let mut conn = state.pool.get()
+        .map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    apply_due_price_changes(&mut conn)?;
+    
+    price_order(&conn, &request).map(|(_, priced)| priced)
+}
+
+#[tauri::command]
+pub fn create_order(app: tauri::AppHandle, state: tauri::State<DbState>, request: CreateOrderRequest, token: String) -> Result<Order, String> {
+    println!("Backend: Creating new order for {}", request.order.cashier);
+    
+    let key = match request.idempotency_key.as_deref().map(str::trim) {
+        Some("") => return Err("Idempotency key cannot be empty".to_string()),
+        Some(key) if key.len() > 100 => return Err("Idempotency key is too long".to_string()),
+        key => key,
+    };
     
     let mut conn = state.pool.get()
         .map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let actor = authenticate(&conn, &token)?;
+    require_permission(&conn, &actor, SELL, "sell")?;
+    
+    // A retried submission returns the order the first attempt created
+    if let Some(id) = find_order_by_idempotency_key(&conn, key)? {
+        println!("Backend: Order {} already created for this submission", id);
+        drop(conn);
+        return get_order_by_id(state, id);
+    }
+    
+    // Sell at prices scheduled to have changed by now
+    apply_due_price_changes(&mut conn)?;
     
     // Start a transaction to ensure all operations succeed or fail together
     let tx = conn.transaction()
         .map_err(|e| format!("Failed to start transaction: {}", e))?;
     
+    let (order_id, alerts) = match insert_order(&tx, &request, &actor) {
+        Ok(created) => created,
+        Err(e) => {
+            drop(tx);
+            // Another attempt with the same key may have committed in the meantime
+            if let Some(id) = find_order_by_idempotency_key(&conn, key)? {
+                drop(conn);
+                return get_order_by_id(state, id);
+            }
+            return Err(e);
+        }
+    };
+
+    let after = snapshot(&tx, "orders", order_id as i64)?;
+    record_audit(&tx, Some(&actor), CREATE, "order", Some(order_id as i64), None, after)?;
+    
+    // Commit the transaction
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    drop(conn);
+    emit_stock_alerts(&app, &alerts);
+    
+    // Return the created order
+    get_order_by_id(state, order_id)
+}
+
+fn find_order_by_idempotency_key(conn: &Connection, key: Option<&str>) -> Result<Option<i32>, String> {
+    let key = match key {
+        Some(key) => key,
+        None => return Ok(None),
+    };
+    
+    conn.query_row(
+        "SELECT id FROM orders WHERE idempotency_key = ?1",
+        params![key],
+        |row| row.get(0),
+    ).optional().map_err(|e| format!("Failed to check for an existing order: {}", e))
+}
+
+// Terminal names are used in order numbers, so keep them short and printable
+pub fn terminal_code(terminal: Option<&str>) -> String {
+    let code: String = terminal
+        .unwrap_or("")
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .take(8)
+        .collect::<String>()
+        .to_ascii_uppercase();
+    
+    if code.is_empty() { DEFAULT_TERMINAL.to_string() } else { code }
+}
+
+/// Allocate the next order number for a terminal, e.g. POS-20240726-0001.
+///
+/// Numbers run per terminal and per day. The counter is bumped inside the caller's
+/// transaction, so two terminals can never be handed the same number.
+pub fn allocate_order_number(conn: &Connection, terminal: Option<&str>) -> Result<String, String> {
+    let prefix = format!("{}-{}", terminal_code(terminal), chrono::Local::now().format("%Y%m%d"));
+    
+    let number: i64 = conn.query_row(
+        "INSERT INTO order_sequences (prefix, last_number) VALUES (?1, 1)
+         ON CONFLICT(prefix) DO UPDATE SET last_number = last_number + 1
+         RETURNING last_number",
+        params![prefix],
+        |row| row.get(0),
+    ).map_err(|e| format!("Failed to allocate order number: {}", e))?;
+    
+    Ok(format!("{}-{:04}", prefix, number))
+}
+
+/// Insert an order with its lines, discounts and payments and take what was sold out of stock.
+/// Runs in the caller's transaction for the signed-in `user`, whose open shift the order joins;
+/// returns the new order id and the stock alerts to emit.
+pub fn insert_order(tx: &Connection, request: &CreateOrderRequest, user: &User) -> Result<(i32, Vec<Notification>), String> {
     // Get local timestamp for created_at in user's local timezone
     let current_time = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
     
+    // A cashier switched in by badge takes the terminal's orders
+    let cashier = terminal_cashier(tx, request.terminal.as_deref())?
+        .unwrap_or_else(|| request.order.cashier.clone());
+    
+    let shift_id = resolve_order_shift(tx, request.shift_id, user)?;
+    let (lines, priced) = price_order(tx, request)?;
+    let order_number = allocate_order_number(tx, request.terminal.as_deref())?;
+    check_order_customer(tx, request.customer_id)?;
+    
     // Insert the order with explicit created_at using local time
     tx.execute(
-        "INSERT INTO orders (order_id, cashier, subtotal, tax, total, status, created_at) 
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        "INSERT INTO orders (order_id, cashier, subtotal, tax, total, status, created_at, shift_id, discount, idempotency_key, customer_id) 
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
         params![
-            request.order.order_id,
-            request.order.cashier,
-            request.order.subtotal,
-            request.order.tax,
-            request.order.total,
+            order_number,
+            cashier,
+            priced.subtotal,
+            priced.tax,
+            priced.total,
             request.order.status,
-            current_time
+            current_time,
+            shift_id,
+            priced.discount,
+            request.idempotency_key.as_deref().map(str::trim),
+            request.customer_id
         ]
     ).map_err(|e| format!("Failed to insert order: {}", e))?;
     
@@ -105,49 +329,83 @@ pub fn create_order(state: tauri::State<DbState>, request: CreateOrderRequest) -
     let order_id = tx.last_insert_rowid() as i32;
     
     // Insert each order item
-    for item in request.items {
-        // Get the product name to store it
-        let product_name: String = match tx.query_row(
-            "SELECT name FROM products WHERE id = ?1",
-            params![item.product_id],
-            |row| row.get(0)
-        ) {
-            Ok(name) => name,
-            Err(e) => {
-                let error_msg = format!("Failed to get product name: {}", e);
-                println!("Backend error: {}", error_msg);
-                return Err(error_msg);
-            }
-        };
-        
+    let mut touched_products = Vec::new();
+    let mut touched_variants = Vec::new();
+    let mut item_ids = Vec::with_capacity(lines.len());
+    for ((item, line), priced_line) in request.items.iter().zip(lines).zip(&priced.lines) {
         tx.execute(
-            "INSERT INTO order_items (order_id, product_id, quantity, price, product_name, created_at) 
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            "INSERT INTO order_items (order_id, product_id, quantity, price, product_name, created_at, variant_id, variant_name, cost, discount) 
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
             params![
                 order_id,
                 item.product_id,
                 item.quantity,
-                item.price,
-                product_name,
-                current_time
+                line.unit_price,
+                line.product_name,
+                current_time,
+                item.variant_id,
+                line.variant_name,
+                line.unit_cost,
+                priced_line.discount
             ]
         ).map_err(|e| format!("Failed to insert order item: {}", e))?;
         
-        // Update the product stock (subtract the ordered quantity)
-        tx.execute(
-            "UPDATE products SET 
-             current_stock = current_stock - ?1, 
-             updated_at = CURRENT_TIMESTAMP
-             WHERE id = ?2",
-            params![item.quantity, item.product_id]
-        ).map_err(|e| format!("Failed to update product stock: {}", e))?;
+        let order_item_id = tx.last_insert_rowid();
+        item_ids.push(order_item_id);
+        for (modifier_id, modifier_name, modifier_price) in &line.modifiers {
+            tx.execute(
+                "INSERT INTO order_item_modifiers (order_item_id, modifier_id, modifier_name, price)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![order_item_id, modifier_id, modifier_name, modifier_price]
+            ).map_err(|e| format!("Failed to insert order item modifier: {}", e))?;
+        }
+        
+        // Prepared items take their ingredients out of stock
+        let (consumed, deduct_finished_item) = consume_recipe(tx, item.product_id, item.quantity)?;
+        touched_products.extend(consumed);
+        
+        // Update the stock (subtract the ordered quantity) of the variant if one was sold, else the product
+        if let Some(variant_id) = item.variant_id {
+            tx.execute(
+                "UPDATE product_variants SET 
+                 current_stock = current_stock - ?1, 
+                 updated_at = CURRENT_TIMESTAMP
+                 WHERE id = ?2",
+                params![item.quantity, variant_id]
+            ).map_err(|e| format!("Failed to update variant stock: {}", e))?;
+            
+            touched_variants.push(variant_id);
+        } else if deduct_finished_item {
+            tx.execute(
+                "UPDATE products SET 
+                 current_stock = current_stock - ?1, 
+                 updated_at = CURRENT_TIMESTAMP
+                 WHERE id = ?2",
+                params![item.quantity, item.product_id]
+            ).map_err(|e| format!("Failed to update product stock: {}", e))?;
+            
+            touched_products.push(item.product_id);
+        }
     }
     
-    // Commit the transaction
-    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    // Keep each discount against the line it was given on
+    record_discounts(tx, order_id, &item_ids, &priced.discounts)?;
     
-    // Return the created order
-    get_order_by_id(state, order_id)
+    // Record how the order was paid; fails the whole order if payments don't cover the total
+    let payments = record_payments(tx, order_id, priced.total, &request.payments)?;
+    
+    // Whatever was charged to an account goes on the customer's tab, within their credit limit
+    let charged: f64 = payments.iter().filter(|p| p.method == ACCOUNT).map(|p| p.amount).sum();
+    charge_account(tx, request.customer_id, order_id, charged)?;
+    
+    // Wallet payments come off the scanned cards' prepaid balances
+    pay_from_wallets(tx, order_id, &payments)?;
+    
+    // Check the stock thresholds of everything that was sold
+    let mut alerts = evaluate_stock_alerts(tx, &touched_products)?;
+    alerts.extend(evaluate_variant_stock_alerts(tx, &touched_variants)?);
+    
+    Ok((order_id, alerts))
 }
 
 #[tauri::command]
@@ -156,9 +414,12 @@ pub fn get_order_by_id(state: tauri::State<DbState>, id: i32) -> Result<Order, S
     
     let conn = state.pool.get()
         .map_err(|e| format!("Failed to get connection from pool: {}", e))?;
-    
+    load_order(&conn, id)
+}
+
+fn load_order(conn: &rusqlite::Connection, id: i32) -> Result<Order, String> {
     let mut stmt = conn.prepare(
-        "SELECT id, order_id, cashier, subtotal, tax, total, status, created_at
+        "SELECT id, order_id, cashier, subtotal, tax, total, status, created_at, discount, customer_id
          FROM orders WHERE id = ?1"
     ).map_err(|e| format!("Failed to prepare statement: {}", e))?;
     
@@ -172,6 +433,8 @@ pub fn get_order_by_id(state: tauri::State<DbState>, id: i32) -> Result<Order, S
             total: row.get(5)?,
             status: row.get(6)?,
             created_at: row.get(7)?,
+            discount: row.get(8)?,
+            customer_id: row.get(9)?,
         })
     }).map_err(|e| format!("Failed to get order: {}", e))?;
     
@@ -184,10 +447,14 @@ pub fn get_order_items(state: tauri::State<DbState>, order_id: i32) -> Result<Ve
     
     let conn = state.pool.get()
         .map_err(|e| format!("Failed to get connection from pool: {}", e))?;
-    
+    load_order_items(&conn, order_id)
+}
+
+fn load_order_items(conn: &rusqlite::Connection, order_id: i32) -> Result<Vec<OrderItem>, String> {
     let mut stmt = conn.prepare(
         "SELECT oi.id, oi.order_id, oi.product_id, oi.quantity, oi.price, oi.created_at, 
-                COALESCE(p.name, oi.product_name, 'Deleted Product') as product_name
+                COALESCE(p.name, oi.product_name, 'Deleted Product') as product_name,
+                oi.variant_id, oi.variant_name, oi.discount
          FROM order_items oi
          LEFT JOIN products p ON oi.product_id = p.id
          WHERE oi.order_id = ?1"
@@ -202,21 +469,84 @@ pub fn get_order_items(state: tauri::State<DbState>, order_id: i32) -> Result<Ve
             price: row.get(4)?,
             created_at: row.get(5)?,
             product_name: Some(row.get(6)?),
+            variant_id: row.get(7)?,
+            variant_name: row.get(8)?,
+            modifiers: Vec::new(),
+            discount: row.get(9)?,
         })
     }).map_err(|e| format!("Failed to query order items: {}", e))?;
     
-    let result = items.collect::<Result<Vec<_>>>()
+    let mut result = items.collect::<Result<Vec<_>>>()
         .map_err(|e| format!("Failed to collect order items: {}", e))?;
     
+    // Attach the modifiers chosen on each line
+    let mut modifier_stmt = conn.prepare(
+        "SELECT m.id, m.order_item_id, m.modifier_id, m.modifier_name, m.price
+         FROM order_item_modifiers m
+         JOIN order_items oi ON m.order_item_id = oi.id
+         WHERE oi.order_id = ?1
+         ORDER BY m.id"
+    ).map_err(|e| format!("Failed to prepare statement: {}", e))?;
+    
+    let modifiers = modifier_stmt.query_map(params![order_id], |row| {
+        Ok(OrderItemModifier {
+            id: row.get(0)?,
+            order_item_id: row.get(1)?,
+            modifier_id: row.get(2)?,
+            modifier_name: row.get(3)?,
+            price: row.get(4)?,
+        })
+    }).map_err(|e| format!("Failed to query order item modifiers: {}", e))?
+    .collect::<Result<Vec<_>>>()
+    .map_err(|e| format!("Failed to collect order item modifiers: {}", e))?;
+    
+    for modifier in modifiers {
+        if let Some(item) = result.iter_mut().find(|i| i.id == modifier.order_item_id) {
+            item.modifiers.push(modifier);
+        }
+    }
+    
     Ok(result)
 }
 
 #[tauri::command]
 pub fn get_order_with_items(state: tauri::State<DbState>, order_id: i32) -> Result<OrderWithItems, String> {
-    let order = get_order_by_id(state.clone(), order_id)?;
-    let items = get_order_items(state, order_id)?;
+    let conn = state.pool.get()
+        .map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    load_order_with_items(&conn, order_id)
+}
+
+/// An order with its lines, payments and discounts
+pub fn load_order_with_items(conn: &rusqlite::Connection, order_id: i32) -> Result<OrderWithItems, String> {
+    let order = load_order(conn, order_id)?;
+    let items = load_order_items(conn, order_id)?;
+    let payments = load_order_payments(conn, order_id)?;
+    let discounts = load_order_discounts(conn, order_id)?;
+    
+    Ok(OrderWithItems { order, items, payments, discounts })
+}
+
+// Look an order up by the number printed on its receipt
+#[tauri::command]
+pub fn find_order_by_number(state: tauri::State<DbState>, order_number: String) -> Result<Option<OrderWithItems>, String> {
+    let order_number = order_number.trim();
+    println!("Backend: Looking up order by number {:?}", order_number);
+    
+    let id: Option<i32> = {
+        let conn = state.pool.get()
+            .map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+        
+        conn.query_row(
+            "SELECT id FROM orders WHERE order_id = ?1 COLLATE NOCASE",
+            params![order_number],
+            |row| row.get(0),
+        ).optional().map_err(|e| format!("Failed to look up order: {}", e))?
+    };
     
-    Ok(OrderWithItems { order, items })
+    match id {
+        Some(id) => get_order_with_items(state, id).map(Some),
+        None => Ok(None),
+    }
 }
 
 #[tauri::command]
@@ -228,7 +558,7 @@ pub fn get_recent_orders(state: tauri::State<DbState>, limit: Option<i32>) -> Re
         .map_err(|e| format!("Failed to get connection from pool: {}", e))?;
     
     let mut stmt = conn.prepare(
-        "SELECT id, order_id, cashier, subtotal, tax, total, status, created_at
+        "SELECT id, order_id, cashier, subtotal, tax, total, status, created_at, discount, customer_id
          FROM orders
          ORDER BY created_at DESC
          LIMIT ?1"
@@ -244,6 +574,8 @@ pub fn get_recent_orders(state: tauri::State<DbState>, limit: Option<i32>) -> Re
             total: row.get(5)?,
             status: row.get(6)?,
             created_at: row.get(7)?,
+            discount: row.get(8)?,
+            customer_id: row.get(9)?,
         })
     }).map_err(|e| format!("Failed to query orders: {}", e))?;
     
@@ -254,13 +586,15 @@ pub fn get_recent_orders(state: tauri::State<DbState>, limit: Option<i32>) -> Re
 }
 
 #[tauri::command]
-pub fn get_order_history(state: tauri::State<DbState>, request: OrderHistoryRequest) -> Result<Vec<Order>, String> {
+pub fn get_order_history(state: tauri::State<DbState>, request: OrderHistoryRequest, token: String) -> Result<Vec<Order>, String> {
     let conn = state.pool.get()
         .map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let user = authenticate(&conn, &token)?;
+    require_permission(&conn, &user, VIEW_REPORTS, "view order history")?;
     
     // Build query based on filters
     let mut query = String::from(
-        "SELECT id, order_id, cashier, subtotal, tax, total, status, created_at
+        "SELECT id, order_id, cashier, subtotal, tax, total, status, created_at, discount, customer_id
          FROM orders
          WHERE 1=1"
     );
@@ -318,6 +652,8 @@ pub fn get_order_history(state: tauri::State<DbState>, request: OrderHistoryRequ
             total: row.get(5)?,
             status: row.get(6)?,
             created_at: row.get(7)?,
+            discount: row.get(8)?,
+            customer_id: row.get(9)?,
         })
     }).map_err(|e| format!("Failed to query order history: {}", e))?;
     
@@ -330,9 +666,16 @@ pub fn get_order_history(state: tauri::State<DbState>, request: OrderHistoryRequ
 }
 
 #[tauri::command]
-pub fn get_order_statistics(state: tauri::State<DbState>, start_date: Option<String>, end_date: Option<String>) -> Result<OrderStatistics, String> {
+pub fn get_order_statistics(
+    state: tauri::State<DbState>,
+    start_date: Option<String>,
+    end_date: Option<String>,
+    token: String,
+) -> Result<OrderStatistics, String> {
     let conn = state.pool.get()
         .map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let user = authenticate(&conn, &token)?;
+    require_permission(&conn, &user, VIEW_REPORTS, "view sales statistics")?;
     
     // Build query based on filters
     let mut query = String::from(
@@ -389,8 +732,17 @@ pub struct OrderStatistics {
     pub unique_cashiers: i64,
 }
 
+/// `category_level` rolls category sales up to the categories that many levels below the top
+/// (0 for top-level categories); without it sales are shown by each product's own category.
 #[tauri::command]
-pub fn get_sales_report_data(state: tauri::State<DbState>, start_date: Option<String>, end_date: Option<String>, period: String) -> Result<SalesReportData, String> {
+pub fn get_sales_report_data(
+    state: tauri::State<DbState>,
+    start_date: Option<String>,
+    end_date: Option<String>,
+    period: String,
+    category_level: Option<u32>,
+    token: String,
+) -> Result<SalesReportData, String> {
     println!("Backend: Getting sales report data for dates: {:?} to {:?}, period: {}", start_date, end_date, period);
     
     // Print out detailed date information for debugging
@@ -423,6 +775,8 @@ pub fn get_sales_report_data(state: tauri::State<DbState>, start_date: Option<St
     
     let conn = state.pool.get()
         .map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let user = authenticate(&conn, &token)?;
+    require_permission(&conn, &user, VIEW_REPORTS, "view sales reports")?;
     
     // Debug: Check if there are any orders in the database for the given date range
     println!("DEBUG: Checking for orders with date filtering");
@@ -435,7 +789,7 @@ pub fn get_sales_report_data(state: tauri::State<DbState>, start_date: Option<St
     let sales_by_period = get_sales_by_period(&conn, adjusted_start_date.clone(), adjusted_end_date.clone(), &period)?;
     println!("DEBUG: Sales by period count: {}", sales_by_period.len());
 
-    let sales_by_category = get_sales_by_category(&conn, adjusted_start_date.clone(), adjusted_end_date.clone())?;
+    let sales_by_category = get_sales_by_category(&conn, adjusted_start_date.clone(), adjusted_end_date.clone(), category_level)?;
     println!("DEBUG: Sales by category count: {}", sales_by_category.len());
 
     let top_products = get_top_products(&conn, adjusted_start_date.clone(), adjusted_end_date.clone(), 10)?;
@@ -462,10 +816,11 @@ fn get_sales_summary(conn: &rusqlite::Connection, start_date: Option<String>, en
     // Build query based on filters
     let mut query = String::from(
         "SELECT COUNT(DISTINCT o.id) as total_sales,
-                SUM(oi.price * oi.quantity) as total_revenue,
-                SUM((oi.price - COALESCE(p.price_bought, oi.price * 0.6)) * oi.quantity) as total_profit,
+                SUM(oi.price * oi.quantity - oi.discount) as total_revenue,
+                SUM((oi.price - COALESCE(oi.cost, p.price_bought, oi.price * 0.6)) * oi.quantity - oi.discount) as total_profit,
                 SUM(oi.quantity) as items_sold,
-                COUNT(DISTINCT o.id) as transactions
+                COUNT(DISTINCT o.id) as transactions,
+                COALESCE(SUM(oi.discount), 0) as total_discounts
          FROM order_items oi
          JOIN orders o ON o.id = oi.order_id
          LEFT JOIN products p ON oi.product_id = p.id
@@ -517,17 +872,137 @@ fn get_sales_summary(conn: &rusqlite::Connection, start_date: Option<String>, en
     let mut stmt = conn.prepare(&query)
         .map_err(|e| format!("Failed to prepare statement: {}", e))?;
     
-    let row = stmt.query_row(params_slice.as_slice(), |row| {
+    let mut summary = stmt.query_row(params_slice.as_slice(), |row| {
         Ok(SalesSummary {
             total_sales: row.get(0)?,
             total_revenue: row.get(1)?,
             total_profit: row.get(2)?,
             items_sold: row.get(3)?,
             transactions: row.get(4)?,
+            payment_methods: Vec::new(),
+            total_discounts: row.get(5)?,
+            discounts: Vec::new(),
         })
     }).map_err(|e| format!("Failed to query sales summary: {}", e))?;
     
-    Ok(row)
+    summary.payment_methods = get_payment_breakdown(conn, start_date.clone(), end_date.clone())?;
+    summary.discounts = get_discount_breakdown(conn, start_date, end_date)?;
+    
+    Ok(summary)
+}
+
+fn get_payment_breakdown(conn: &rusqlite::Connection, start_date: Option<String>, end_date: Option<String>) -> Result<Vec<PaymentMethodSales>, String> {
+    let mut query = String::from(
+        "SELECT op.method,
+                COUNT(DISTINCT op.order_id) as transactions,
+                SUM(op.amount) as amount
+         FROM order_payments op
+         JOIN orders o ON o.id = op.order_id
+         WHERE 1=1"
+    );
+    
+    let mut query_params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+    
+    // Same date filtering as the sales summary
+    if let (Some(start), Some(end)) = (&start_date, &end_date) {
+        if start == end {
+            query.push_str(" AND o.created_at LIKE ? || '%'");
+            query_params.push(Box::new(start.clone()));
+        } else {
+            query.push_str(" AND date(o.created_at) >= date(?) AND date(o.created_at) <= date(?)");
+            query_params.push(Box::new(start.clone()));
+            query_params.push(Box::new(end.clone()));
+        }
+    } else {
+        if let Some(start) = &start_date {
+            query.push_str(" AND date(o.created_at) >= date(?)");
+            query_params.push(Box::new(start.clone()));
+        }
+        
+        if let Some(end) = &end_date {
+            query.push_str(" AND date(o.created_at) <= date(?)");
+            query_params.push(Box::new(end.clone()));
+        }
+    }
+    
+    query.push_str(" GROUP BY op.method ORDER BY amount DESC");
+    
+    let params_slice: Vec<&dyn rusqlite::ToSql> = query_params
+        .iter()
+        .map(|p| p.as_ref() as &dyn rusqlite::ToSql)
+        .collect();
+    
+    let mut stmt = conn.prepare(&query)
+        .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+    
+    let rows = stmt.query_map(params_slice.as_slice(), |row| {
+        Ok(PaymentMethodSales {
+            method: row.get(0)?,
+            transactions: row.get(1)?,
+            amount: row.get(2)?,
+        })
+    }).map_err(|e| format!("Failed to query payment breakdown: {}", e))?;
+    
+    rows.collect::<Result<Vec<_>>>()
+        .map_err(|e| format!("Failed to collect payment breakdown: {}", e))
+}
+
+fn get_discount_breakdown(conn: &rusqlite::Connection, start_date: Option<String>, end_date: Option<String>) -> Result<Vec<DiscountSales>, String> {
+    let mut query = String::from(
+        "SELECT od.source, od.kind, od.description,
+                COUNT(DISTINCT od.order_id) as orders,
+                SUM(od.amount) as amount
+         FROM order_discounts od
+         JOIN orders o ON o.id = od.order_id
+         WHERE 1=1"
+    );
+    
+    let mut query_params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+    
+    // Same date filtering as the sales summary
+    if let (Some(start), Some(end)) = (&start_date, &end_date) {
+        if start == end {
+            query.push_str(" AND o.created_at LIKE ? || '%'");
+            query_params.push(Box::new(start.clone()));
+        } else {
+            query.push_str(" AND date(o.created_at) >= date(?) AND date(o.created_at) <= date(?)");
+            query_params.push(Box::new(start.clone()));
+            query_params.push(Box::new(end.clone()));
+        }
+    } else {
+        if let Some(start) = &start_date {
+            query.push_str(" AND date(o.created_at) >= date(?)");
+            query_params.push(Box::new(start.clone()));
+        }
+        
+        if let Some(end) = &end_date {
+            query.push_str(" AND date(o.created_at) <= date(?)");
+            query_params.push(Box::new(end.clone()));
+        }
+    }
+    
+    query.push_str(" GROUP BY od.source, od.kind, od.description ORDER BY amount DESC");
+    
+    let params_slice: Vec<&dyn rusqlite::ToSql> = query_params
+        .iter()
+        .map(|p| p.as_ref() as &dyn rusqlite::ToSql)
+        .collect();
+    
+    let mut stmt = conn.prepare(&query)
+        .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+    
+    let rows = stmt.query_map(params_slice.as_slice(), |row| {
+        Ok(DiscountSales {
+            source: row.get(0)?,
+            kind: row.get(1)?,
+            description: row.get(2)?,
+            orders: row.get(3)?,
+            amount: row.get(4)?,
+        })
+    }).map_err(|e| format!("Failed to query discount breakdown: {}", e))?;
+    
+    rows.collect::<Result<Vec<_>>>()
+        .map_err(|e| format!("Failed to collect discount breakdown: {}", e))
 }
 
 fn get_sales_by_period(conn: &rusqlite::Connection, start_date: Option<String>, end_date: Option<String>, period_type: &str) -> Result<Vec<PeriodSales>, String> {
@@ -545,8 +1020,8 @@ fn get_sales_by_period(conn: &rusqlite::Connection, start_date: Option<String>,
         format!(
             "SELECT strftime('{}', o.created_at) as period,
                     COUNT(DISTINCT o.id) as sales,
-                    SUM(oi.price * oi.quantity) as revenue,
-                    SUM((oi.price - COALESCE(p.price_bought, oi.price * 0.6)) * oi.quantity) as profit
+                    SUM(oi.price * oi.quantity - oi.discount) as revenue,
+                    SUM((oi.price - COALESCE(oi.cost, p.price_bought, oi.price * 0.6)) * oi.quantity - oi.discount) as profit
              FROM orders o
              JOIN order_items oi ON o.id = oi.order_id
              LEFT JOIN products p ON oi.product_id = p.id
@@ -620,14 +1095,37 @@ fn get_sales_by_period(conn: &rusqlite::Connection, start_date: Option<String>,
     Ok(result)
 }
 
-fn get_sales_by_category(conn: &rusqlite::Connection, start_date: Option<String>, end_date: Option<String>) -> Result<Vec<CategorySales>, String> {
+// Each category with its depth and the path of ids down to it, e.g. /1/4/9/, for rolling sales
+// up to an ancestor. Categories whose parent is gone count as top-level.
+const CATEGORY_TREE: &str =
+    "WITH RECURSIVE category_tree(id, depth, path) AS (
+        SELECT id, 0, '/' || id || '/' FROM categories
+        WHERE parent_id IS NULL OR parent_id NOT IN (SELECT id FROM categories)
+        UNION ALL
+        SELECT c.id, t.depth + 1, t.path || c.id || '/' FROM categories c JOIN category_tree t ON c.parent_id = t.id
+     ) ";
+
+fn get_sales_by_category(conn: &rusqlite::Connection, start_date: Option<String>, end_date: Option<String>, level: Option<u32>) -> Result<Vec<CategorySales>, String> {
+    // Sales are grouped under each product's own category, or its ancestor at the given level
+    let category_join = match level {
+        Some(level) => format!(
+            "LEFT JOIN category_tree ct ON ct.id = p.category_id
+         LEFT JOIN category_tree rt ON ct.path LIKE rt.path || '%' AND rt.depth = MIN(ct.depth, {})
+         LEFT JOIN categories c ON c.id = rt.id",
+            level
+        ),
+        None => "LEFT JOIN categories c ON p.category_id = c.id".to_string(),
+    };
+
     // Build query based on filters
-    let mut query = String::from(
+    let mut query = String::from(if level.is_some() { CATEGORY_TREE } else { "" });
+    query.push_str(
         "SELECT
             COALESCE(c.name, 'Uncategorized') as category_name,
-            SUM(oi.price * oi.quantity) as revenue,
-            SUM((oi.price - COALESCE(p.price_bought, oi.price * 0.6)) * oi.quantity) as profit,
-            ROUND(SUM(oi.price * oi.quantity) * 100.0 / (SELECT SUM(oi2.price * oi2.quantity) FROM order_items oi2
+            c.id as category_id,
+            SUM(oi.price * oi.quantity - oi.discount) as revenue,
+            SUM((oi.price - COALESCE(oi.cost, p.price_bought, oi.price * 0.6)) * oi.quantity - oi.discount) as profit,
+            ROUND(SUM(oi.price * oi.quantity - oi.discount) * 100.0 / (SELECT SUM(oi2.price * oi2.quantity - oi2.discount) FROM order_items oi2
             JOIN orders o2 ON o2.id = oi2.order_id"
     );
     
@@ -654,12 +1152,12 @@ fn get_sales_by_category(conn: &rusqlite::Connection, start_date: Option<String>
     }
     
     // Continue with the main query
-    query.push_str(")) * 100 as percentage
+    query.push_str(&format!(")) * 100 as percentage
          FROM order_items oi
          JOIN orders o ON o.id = oi.order_id
          LEFT JOIN products p ON oi.product_id = p.id
-         LEFT JOIN categories c ON p.category_id = c.id
-         WHERE 1=1");
+         {}
+         WHERE 1=1", category_join));
     
     let mut query_params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
     
@@ -706,7 +1204,7 @@ fn get_sales_by_category(conn: &rusqlite::Connection, start_date: Option<String>
     }
     
     // Add group by and having clauses
-    query.push_str(" GROUP BY c.name HAVING SUM(oi.price * oi.quantity) > 0 ORDER BY revenue DESC");
+    query.push_str(" GROUP BY c.id, c.name HAVING SUM(oi.price * oi.quantity - oi.discount) > 0 ORDER BY revenue DESC");
     
     // Log the query
     println!("Category sales query: {}", query);
@@ -723,9 +1221,10 @@ fn get_sales_by_category(conn: &rusqlite::Connection, start_date: Option<String>
     let rows = stmt.query_map(params_slice.as_slice(), |row| {
         Ok(CategorySales {
             category: row.get(0)?,
-            revenue: row.get(1)?,
-            profit: row.get(2)?,
-            percentage: row.get(3)?,
+            category_id: row.get(1)?,
+            revenue: row.get(2)?,
+            profit: row.get(3)?,
+            percentage: row.get(4)?,
         })
     }).map_err(|e| format!("Failed to query sales by category: {}", e))?;
     
@@ -741,11 +1240,15 @@ fn get_top_products(conn: &rusqlite::Connection, start_date: Option<String>, end
         "SELECT
             COALESCE(p.name, 'Unknown Product') as product_name,
             SUM(oi.quantity) as quantity,
-            SUM(oi.price * oi.quantity) as revenue,
-            SUM((oi.price - COALESCE(p.price_bought, oi.price * 0.6)) * oi.quantity) as profit
+            SUM(oi.price * oi.quantity - oi.discount) as revenue,
+            SUM((oi.price - COALESCE(oi.cost, p.price_bought, oi.price * 0.6)) * oi.quantity - oi.discount) as profit,
+            COALESCE(pu.base_unit, 'pc') as unit,
+            pu.purchase_unit,
+            CASE WHEN pu.purchase_unit IS NOT NULL THEN SUM(oi.quantity) / pu.purchase_factor END as purchase_quantity
          FROM order_items oi
          JOIN orders o ON oi.order_id = o.id
          LEFT JOIN products p ON oi.product_id = p.id
+         LEFT JOIN product_units pu ON pu.product_id = p.id
          WHERE 1=1"
     );
     
@@ -803,6 +1306,9 @@ fn get_top_products(conn: &rusqlite::Connection, start_date: Option<String>, end
             quantity: row.get(1)?,
             revenue: row.get(2)?,
             profit: row.get(3)?,
+            unit: row.get(4)?,
+            purchase_unit: row.get(5)?,
+            purchase_quantity: row.get(6)?,
         })
     }).map_err(|e| format!("Failed to query top products: {}", e))?;
     
@@ -822,17 +1328,22 @@ fn get_detailed_sales(conn: &rusqlite::Connection, start_date: Option<String>, e
                 COALESCE(c.name, 'Uncategorized') as category_name,
                 o.created_at as sale_date,
                 oi.price as unit_price,
-                (oi.price - COALESCE(p.price_bought, oi.price * 0.6)) as unit_profit,
+                (oi.price - COALESCE(oi.cost, p.price_bought, oi.price * 0.6)) as unit_profit,
                 CASE
-                    WHEN oi.price > 0 THEN ROUND(((oi.price - COALESCE(p.price_bought, oi.price * 0.6)) / oi.price) * 100, 1) || '%'
+                    WHEN oi.price > 0 THEN ROUND(((oi.price - COALESCE(oi.cost, p.price_bought, oi.price * 0.6)) / oi.price) * 100, 1) || '%'
                     ELSE '0%'
                 END as profit_margin,
-                (oi.price * oi.quantity) as revenue,
-                oi.quantity
+                (oi.price * oi.quantity - oi.discount) as revenue,
+                oi.quantity,
+                COALESCE(pu.base_unit, 'pc') as unit,
+                pu.purchase_unit,
+                CASE WHEN pu.purchase_unit IS NOT NULL THEN oi.quantity / pu.purchase_factor END as purchase_quantity,
+                oi.discount
          FROM order_items oi
          JOIN orders o ON oi.order_id = o.id
          LEFT JOIN products p ON oi.product_id = p.id
          LEFT JOIN categories c ON p.category_id = c.id
+         LEFT JOIN product_units pu ON pu.product_id = p.id
          WHERE 1=1"
     );
     
@@ -894,6 +1405,10 @@ fn get_detailed_sales(conn: &rusqlite::Connection, start_date: Option<String>, e
             margin: row.get(6)?,
             revenue: row.get(7)?,
             quantity: row.get(8)?,
+            unit: row.get(9)?,
+            purchase_unit: row.get(10)?,
+            purchase_quantity: row.get(11)?,
+            discount: row.get(12)?,
         })
     }).map_err(|e| format!("Failed to query detailed sales: {}", e))?;
     
@@ -1244,7 +1759,7 @@ pub fn debug_daily_sales(state: tauri::State<DbState>) -> Result<String, String>
     let query = format!(
         "SELECT COUNT(DISTINCT o.id) as total_sales,
                 SUM(oi.price * oi.quantity) as total_revenue,
-                SUM((oi.price - COALESCE(p.price_bought, oi.price * 0.6)) * oi.quantity) as total_profit
+                SUM((oi.price - COALESCE(oi.cost, p.price_bought, oi.price * 0.6)) * oi.quantity) as total_profit
          FROM order_items oi
          JOIN orders o ON o.id = oi.order_id
          LEFT JOIN products p ON oi.product_id = p.id
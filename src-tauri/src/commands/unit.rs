@@ -0,0 +1,350 @@
+use crate::commands::audit::{record_audit, snapshot};
+use crate::commands::auth::authenticate;
+use crate::commands::notification::{emit_stock_alerts, evaluate_stock_alerts};
+use crate::commands::price::{load_prices, record_price_change};
+use crate::commands::role::require_permission;
+use crate::db::models::audit::UPDATE;
+use crate::db::models::role::EDIT_INVENTORY;
+use crate::db::models::unit::{
+    ProductUnits, ReceiveStock, SetProductUnits, StockMovement, StocktakeCount, DEFAULT_BASE_UNIT,
+};
+use crate::db::DbState;
+use rusqlite::{params, Connection, OptionalExtension, Result};
+
+/// Unit definition of a product; products without one are counted in whole pieces
+pub fn load_product_units(conn: &Connection, product_id: i32) -> Result<ProductUnits, String> {
+    let units = conn.query_row(
+        "SELECT product_id, base_unit, purchase_unit, purchase_factor, allow_fractional
+         FROM product_units WHERE product_id = ?1",
+        params![product_id],
+        |row| {
+            Ok(ProductUnits {
+                product_id: row.get(0)?,
+                base_unit: row.get(1)?,
+                purchase_unit: row.get(2)?,
+                purchase_factor: row.get(3)?,
+                allow_fractional: row.get(4)?,
+            })
+        },
+    ).optional().map_err(|e| format!("Failed to get product units: {}", e))?;
+
+    Ok(units.unwrap_or(ProductUnits {
+        product_id,
+        base_unit: DEFAULT_BASE_UNIT.to_string(),
+        purchase_unit: None,
+        purchase_factor: 1.0,
+        allow_fractional: false,
+    }))
+}
+
+// Measures that convert into each other whatever the product, sized in their smallest unit
+const MEASURES: [&[(&str, f64)]; 2] = [
+    &[("mg", 1.0), ("g", 1_000.0), ("kg", 1_000_000.0)],
+    &[("ml", 1.0), ("cl", 10.0), ("l", 1_000.0)],
+];
+
+/// How many base units one `unit` of a product is worth. Accepts the product's base and
+/// purchase units, and metric measures of the same kind as the base unit, e.g. grams of a
+/// product counted in kg. Returns `None` for units the product can't be measured in.
+pub fn unit_factor(units: &ProductUnits, unit: &str) -> Option<f64> {
+    let unit = unit.trim().to_lowercase();
+    let base_unit = units.base_unit.trim().to_lowercase();
+
+    if unit == base_unit {
+        return Some(1.0);
+    }
+    if units.purchase_unit.as_deref().is_some_and(|u| u.trim().to_lowercase() == unit) {
+        return Some(units.purchase_factor);
+    }
+
+    MEASURES.iter().find_map(|measure| {
+        let size = |name: &str| measure.iter().find(|(u, _)| *u == name).map(|(_, size)| *size);
+        Some(size(&unit)? / size(&base_unit)?)
+    })
+}
+
+/// Reject non-positive quantities, and fractions of products sold in whole units
+pub fn check_quantity(conn: &Connection, product_id: i32, quantity: f64) -> Result<(), String> {
+    if !quantity.is_finite() || quantity <= 0.0 {
+        return Err(format!("Quantity must be greater than zero, got {}", quantity));
+    }
+
+    let units = load_product_units(conn, product_id)?;
+    if !units.allow_fractional && quantity.fract() != 0.0 {
+        return Err(format!("Product {} is counted in whole {}, got {}", product_id, units.base_unit, quantity));
+    }
+
+    Ok(())
+}
+
+fn record_movement(
+    conn: &Connection,
+    product_id: i32,
+    kind: &str,
+    quantity: f64,
+    (entered_quantity, entered_unit): (f64, &str),
+    unit_cost: Option<f64>,
+    reference: Option<&str>,
+) -> Result<StockMovement, String> {
+    let stock_after: f64 = conn.query_row(
+        "SELECT current_stock FROM products WHERE id = ?1",
+        params![product_id],
+        |row| row.get(0),
+    ).map_err(|e| format!("Failed to read product stock: {}", e))?;
+
+    conn.execute(
+        "INSERT INTO stock_movements (product_id, kind, quantity, entered_quantity, entered_unit, unit_cost, stock_after, reference)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![product_id, kind, quantity, entered_quantity, entered_unit, unit_cost, stock_after, reference],
+    ).map_err(|e| format!("Failed to record stock movement: {}", e))?;
+
+    load_movement(conn, conn.last_insert_rowid() as i32)
+}
+
+const MOVEMENT_COLUMNS: &str =
+    "m.id, m.product_id, p.name, m.kind, m.quantity, m.entered_quantity, m.entered_unit, m.unit_cost,
+     m.stock_after, m.reference, m.created_at";
+
+fn map_movement(row: &rusqlite::Row) -> Result<StockMovement> {
+    Ok(StockMovement {
+        id: row.get(0)?,
+        product_id: row.get(1)?,
+        product_name: row.get(2)?,
+        kind: row.get(3)?,
+        quantity: row.get(4)?,
+        entered_quantity: row.get(5)?,
+        entered_unit: row.get(6)?,
+        unit_cost: row.get(7)?,
+        stock_after: row.get(8)?,
+        reference: row.get(9)?,
+        created_at: row.get(10)?,
+    })
+}
+
+fn load_movement(conn: &Connection, id: i32) -> Result<StockMovement, String> {
+    conn.query_row(
+        &format!(
+            "SELECT {} FROM stock_movements m JOIN products p ON m.product_id = p.id WHERE m.id = ?1",
+            MOVEMENT_COLUMNS
+        ),
+        params![id],
+        map_movement,
+    ).map_err(|e| format!("Failed to get stock movement: {}", e))
+}
+
+#[tauri::command]
+pub fn get_product_units(state: tauri::State<DbState>, product_id: i32) -> Result<ProductUnits, String> {
+    let conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    load_product_units(&conn, product_id)
+}
+
+#[tauri::command]
+pub fn set_product_units(state: tauri::State<DbState>, units: SetProductUnits, token: String) -> Result<ProductUnits, String> {
+    println!("Backend: Setting units for product {}: {:?}", units.product_id, units);
+
+    let base_unit = units.base_unit.trim();
+    if base_unit.is_empty() {
+        return Err("Base unit is required".to_string());
+    }
+
+    let purchase_unit = units.purchase_unit.as_deref().map(str::trim).filter(|u| !u.is_empty());
+    let purchase_factor = units.purchase_factor.unwrap_or(1.0);
+    if purchase_unit.is_some() && (!purchase_factor.is_finite() || purchase_factor <= 0.0) {
+        return Err("Purchase unit conversion factor must be greater than zero".to_string());
+    }
+
+    let mut conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let actor = authenticate(&conn, &token)?;
+    require_permission(&conn, &actor, EDIT_INVENTORY, "change product units")?;
+    let tx = conn.transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let before = load_product_units(&tx, units.product_id)?;
+    tx.execute(
+        "INSERT INTO product_units (product_id, base_unit, purchase_unit, purchase_factor, allow_fractional)
+         VALUES (?1, ?2, ?3, ?4, ?5)
+         ON CONFLICT (product_id) DO UPDATE SET
+            base_unit = excluded.base_unit,
+            purchase_unit = excluded.purchase_unit,
+            purchase_factor = excluded.purchase_factor,
+            allow_fractional = excluded.allow_fractional,
+            updated_at = datetime('now')",
+        params![
+            units.product_id,
+            base_unit,
+            purchase_unit,
+            if purchase_unit.is_some() { purchase_factor } else { 1.0 },
+            units.allow_fractional.unwrap_or(false)
+        ],
+    ).map_err(|e| format!("Failed to save product units: {}", e))?;
+
+    let after = load_product_units(&tx, units.product_id)?;
+    record_audit(
+        &tx,
+        Some(&actor),
+        UPDATE,
+        "product_units",
+        Some(units.product_id as i64),
+        serde_json::to_value(&before).ok(),
+        serde_json::to_value(&after).ok(),
+    )?;
+
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    Ok(after)
+}
+
+#[tauri::command]
+pub fn receive_stock(app: tauri::AppHandle, state: tauri::State<DbState>, receipt: ReceiveStock, token: String) -> Result<StockMovement, String> {
+    println!("Backend: Receiving stock: {:?}", receipt);
+
+    let mut conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let actor = authenticate(&conn, &token)?;
+    require_permission(&conn, &actor, EDIT_INVENTORY, "receive stock")?;
+    let tx = conn.transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let units = load_product_units(&tx, receipt.product_id)?;
+    let in_purchase_unit = receipt.in_purchase_unit.unwrap_or(units.purchase_unit.is_some());
+
+    let (factor, entered_unit) = match (&units.purchase_unit, in_purchase_unit) {
+        (Some(purchase_unit), true) => (units.purchase_factor, purchase_unit.clone()),
+        (None, true) => return Err("Product has no purchase unit defined".to_string()),
+        (_, false) => (1.0, units.base_unit.clone()),
+    };
+
+    let base_quantity = receipt.quantity * factor;
+    check_quantity(&tx, receipt.product_id, base_quantity)?;
+
+    let before = snapshot(&tx, "products", receipt.product_id as i64)?;
+
+    tx.execute(
+        "UPDATE products SET current_stock = current_stock + ?1, updated_at = datetime('now') WHERE id = ?2",
+        params![base_quantity, receipt.product_id],
+    ).map_err(|e| format!("Failed to update product stock: {}", e))?;
+
+    // The product's cost is kept per base unit
+    if let Some(unit_cost) = receipt.unit_cost {
+        let old_prices = load_prices(&tx, receipt.product_id)?;
+        tx.execute(
+            "UPDATE products SET price_bought = ?1 WHERE id = ?2",
+            params![unit_cost / factor, receipt.product_id],
+        ).map_err(|e| format!("Failed to update product cost: {}", e))?;
+
+        let new_prices = (old_prices.0, unit_cost / factor);
+        record_price_change(
+            &tx,
+            receipt.product_id,
+            old_prices,
+            new_prices,
+            Some((actor.id, actor.username.as_str())),
+            Some("Stock received"),
+            None,
+        )?;
+    }
+
+    let movement = record_movement(
+        &tx,
+        receipt.product_id,
+        "receipt",
+        base_quantity,
+        (receipt.quantity, &entered_unit),
+        receipt.unit_cost,
+        receipt.reference.as_deref(),
+    )?;
+
+    let after = snapshot(&tx, "products", receipt.product_id as i64)?;
+    record_audit(&tx, Some(&actor), UPDATE, "product", Some(receipt.product_id as i64), before, after)?;
+
+    let alerts = evaluate_stock_alerts(&tx, &[receipt.product_id])?;
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    emit_stock_alerts(&app, &alerts);
+
+    Ok(movement)
+}
+
+#[tauri::command]
+pub fn record_stocktake(
+    app: tauri::AppHandle,
+    state: tauri::State<DbState>,
+    counts: Vec<StocktakeCount>,
+    token: String,
+) -> Result<Vec<StockMovement>, String> {
+    println!("Backend: Recording stocktake for {} products", counts.len());
+
+    let mut conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let actor = authenticate(&conn, &token)?;
+    require_permission(&conn, &actor, EDIT_INVENTORY, "record stocktakes")?;
+    let tx = conn.transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let mut movements = Vec::new();
+    let mut counted_products = Vec::new();
+
+    for count in counts {
+        let units = load_product_units(&tx, count.product_id)?;
+        let purchase_quantity = count.purchase_quantity.unwrap_or(0.0);
+        let base_quantity = count.base_quantity.unwrap_or(0.0);
+
+        if purchase_quantity < 0.0 || base_quantity < 0.0 {
+            return Err(format!("Counted quantities for product {} cannot be negative", count.product_id));
+        }
+        if purchase_quantity > 0.0 && units.purchase_unit.is_none() {
+            return Err(format!("Product {} has no purchase unit defined", count.product_id));
+        }
+
+        let counted = purchase_quantity * units.purchase_factor + base_quantity;
+        if !units.allow_fractional && counted.fract() != 0.0 {
+            return Err(format!("Product {} is counted in whole {}, got {}", count.product_id, units.base_unit, counted));
+        }
+
+        let current: f64 = tx.query_row(
+            "SELECT current_stock FROM products WHERE id = ?1",
+            params![count.product_id],
+            |row| row.get(0),
+        ).map_err(|e| format!("Failed to get product {}: {}", count.product_id, e))?;
+
+        let before = snapshot(&tx, "products", count.product_id as i64)?;
+        tx.execute(
+            "UPDATE products SET current_stock = ?1, updated_at = datetime('now') WHERE id = ?2",
+            params![counted, count.product_id],
+        ).map_err(|e| format!("Failed to update product stock: {}", e))?;
+        let after = snapshot(&tx, "products", count.product_id as i64)?;
+        record_audit(&tx, Some(&actor), UPDATE, "product", Some(count.product_id as i64), before, after)?;
+
+        movements.push(record_movement(
+            &tx,
+            count.product_id,
+            "stocktake",
+            counted - current,
+            (counted, &units.base_unit),
+            None,
+            count.note.as_deref(),
+        )?);
+        counted_products.push(count.product_id);
+    }
+
+    let alerts = evaluate_stock_alerts(&tx, &counted_products)?;
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    emit_stock_alerts(&app, &alerts);
+
+    Ok(movements)
+}
+
+#[tauri::command]
+pub fn get_stock_movements(state: tauri::State<DbState>, product_id: Option<i32>, limit: Option<i32>) -> Result<Vec<StockMovement>, String> {
+    let conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+
+    let mut stmt = conn.prepare(
+        &format!(
+            "SELECT {} FROM stock_movements m
+             JOIN products p ON m.product_id = p.id
+             WHERE ?1 IS NULL OR m.product_id = ?1
+             ORDER BY m.created_at DESC, m.id DESC
+             LIMIT ?2",
+            MOVEMENT_COLUMNS
+        )
+    ).map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+    let rows = stmt.query_map(params![product_id, limit.unwrap_or(100)], map_movement)
+        .map_err(|e| format!("Failed to query stock movements: {}", e))?;
+
+    rows.collect::<Result<Vec<_>>>()
+        .map_err(|e| format!("Failed to collect stock movements: {}", e))
+}
@@ -0,0 +1,422 @@
+use crate::commands::audit::{record_audit, snapshot};
+use crate::commands::auth::authenticate;
+use crate::commands::barcode::ensure_code_available;
+use crate::commands::recipe::recipe_cost;
+use crate::commands::role::require_permission;
+use crate::db::models::audit::{CREATE, DELETE, UPDATE};
+use crate::db::models::order::NewOrderItem;
+use crate::db::models::role::EDIT_INVENTORY;
+use crate::db::models::variant::{
+    Modifier, ModifierGroup, NewModifier, NewModifierGroup, NewProductVariant, ProductVariant, UpdateProductVariant,
+};
+use crate::db::DbState;
+use rusqlite::{params, Connection, OptionalExtension, Result};
+use std::collections::HashMap;
+
+const VARIANT_COLUMNS: &str =
+    "id, product_id, name, sku, barcode, unit_price, price_bought, current_stock, minimum_stock, created_at, updated_at";
+
+fn map_variant(row: &rusqlite::Row) -> Result<ProductVariant> {
+    Ok(ProductVariant {
+        id: row.get(0)?,
+        product_id: row.get(1)?,
+        name: row.get(2)?,
+        sku: row.get(3)?,
+        barcode: row.get(4)?,
+        unit_price: row.get(5)?,
+        price_bought: row.get(6)?,
+        current_stock: row.get(7)?,
+        minimum_stock: row.get(8)?,
+        created_at: row.get(9)?,
+        updated_at: row.get(10)?,
+    })
+}
+
+pub fn get_variant(conn: &Connection, id: i32) -> Result<ProductVariant, String> {
+    conn.query_row(
+        &format!("SELECT {} FROM product_variants WHERE id = ?1", VARIANT_COLUMNS),
+        params![id],
+        map_variant,
+    ).map_err(|e| format!("Failed to get variant: {}", e))
+}
+
+/// A sale line priced by the backend from the product, its variant and chosen modifiers
+pub struct PricedLine {
+    pub product_name: String,
+    pub variant_name: Option<String>,
+    pub unit_price: f64,
+    pub unit_cost: f64,
+    // (modifier id, name, price) as they were at the time of sale
+    pub modifiers: Vec<(i32, String, f64)>,
+}
+
+/// Work out the unit price and cost of an order line.
+///
+/// Plain product lines keep the price sent by the register. Lines that reference a
+/// variant or modifiers are priced here: the variant's price (or the product's) plus the
+/// price of every chosen modifier, so the register cannot drift from the catalogue.
+pub fn price_order_line(conn: &Connection, item: &NewOrderItem) -> Result<PricedLine, String> {
+    let (product_name, product_price, product_cost, archived): (String, f64, f64, bool) = conn.query_row(
+        "SELECT name, unit_price, price_bought, archived_at IS NOT NULL FROM products WHERE id = ?1",
+        params![item.product_id],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+    ).map_err(|e| format!("Failed to get product {}: {}", item.product_id, e))?;
+
+    if archived {
+        return Err(format!("{} is archived and can't be sold", product_name));
+    }
+
+    // Prepared items cost what their ingredients cost
+    let product_cost = recipe_cost(conn, item.product_id)?.unwrap_or(product_cost);
+
+    let (variant_name, mut unit_price, mut unit_cost) = match item.variant_id {
+        Some(variant_id) => {
+            let variant = get_variant(conn, variant_id)?;
+            if variant.product_id != item.product_id {
+                return Err(format!("Variant {} does not belong to product {}", variant_id, item.product_id));
+            }
+            (Some(variant.name), variant.unit_price, variant.price_bought)
+        }
+        None if item.modifier_ids.is_empty() => (None, item.price, product_cost),
+        None => (None, product_price, product_cost),
+    };
+
+    // Load every modifier group of the product so required groups can be checked too
+    let groups = load_modifier_groups(conn, item.product_id)?;
+    let mut chosen_per_group: HashMap<i32, i32> = HashMap::new();
+    let mut modifiers = Vec::new();
+
+    for modifier_id in &item.modifier_ids {
+        let (group, modifier) = groups
+            .iter()
+            .find_map(|g| g.modifiers.iter().find(|m| m.id == *modifier_id).map(|m| (g, m)))
+            .ok_or_else(|| format!("Modifier {} is not available for product {}", modifier_id, item.product_id))?;
+
+        *chosen_per_group.entry(group.id).or_insert(0) += 1;
+        unit_price += modifier.price;
+        unit_cost += modifier.cost;
+        modifiers.push((modifier.id, modifier.name.clone(), modifier.price));
+    }
+
+    for group in &groups {
+        let chosen = chosen_per_group.get(&group.id).copied().unwrap_or(0);
+        if chosen < group.min_select {
+            return Err(format!("{} requires at least {} choice(s) from {}", product_name, group.min_select, group.name));
+        }
+        if chosen > group.max_select {
+            return Err(format!("{} allows at most {} choice(s) from {}", product_name, group.max_select, group.name));
+        }
+    }
+
+    Ok(PricedLine {
+        product_name,
+        variant_name,
+        unit_price,
+        unit_cost,
+        modifiers,
+    })
+}
+
+fn load_modifier_groups(conn: &Connection, product_id: i32) -> Result<Vec<ModifierGroup>, String> {
+    let mut groups = {
+        let mut stmt = conn.prepare(
+            "SELECT id, product_id, name, min_select, max_select FROM modifier_groups WHERE product_id = ?1 ORDER BY id"
+        ).map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let rows = stmt.query_map(params![product_id], |row| {
+            Ok(ModifierGroup {
+                id: row.get(0)?,
+                product_id: row.get(1)?,
+                name: row.get(2)?,
+                min_select: row.get(3)?,
+                max_select: row.get(4)?,
+                modifiers: Vec::new(),
+            })
+        }).map_err(|e| format!("Failed to query modifier groups: {}", e))?;
+
+        rows.collect::<Result<Vec<_>>>()
+            .map_err(|e| format!("Failed to collect modifier groups: {}", e))?
+    };
+
+    let mut stmt = conn.prepare(
+        "SELECT m.id, m.group_id, m.name, m.price, m.cost
+         FROM modifiers m
+         JOIN modifier_groups g ON m.group_id = g.id
+         WHERE g.product_id = ?1
+         ORDER BY m.id"
+    ).map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+    let modifiers = stmt.query_map(params![product_id], |row| {
+        Ok(Modifier {
+            id: row.get(0)?,
+            group_id: row.get(1)?,
+            name: row.get(2)?,
+            price: row.get(3)?,
+            cost: row.get(4)?,
+        })
+    }).map_err(|e| format!("Failed to query modifiers: {}", e))?
+    .collect::<Result<Vec<_>>>()
+    .map_err(|e| format!("Failed to collect modifiers: {}", e))?;
+
+    for modifier in modifiers {
+        if let Some(group) = groups.iter_mut().find(|g| g.id == modifier.group_id) {
+            group.modifiers.push(modifier);
+        }
+    }
+
+    Ok(groups)
+}
+
+#[tauri::command]
+pub fn get_product_variants(state: tauri::State<DbState>, product_id: i32) -> Result<Vec<ProductVariant>, String> {
+    println!("Backend: Getting variants for product {}", product_id);
+    let conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+
+    let mut stmt = conn.prepare(
+        &format!("SELECT {} FROM product_variants WHERE product_id = ?1 ORDER BY unit_price, name", VARIANT_COLUMNS)
+    ).map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+    let rows = stmt.query_map(params![product_id], map_variant)
+        .map_err(|e| format!("Failed to query variants: {}", e))?;
+
+    rows.collect::<Result<Vec<_>>>()
+        .map_err(|e| format!("Failed to collect variants: {}", e))
+}
+
+#[tauri::command]
+pub fn add_product_variant(state: tauri::State<DbState>, variant: NewProductVariant, token: String) -> Result<ProductVariant, String> {
+    println!("Backend: Adding variant {:?} to product {}", variant.name, variant.product_id);
+    let mut conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let actor = authenticate(&conn, &token)?;
+    require_permission(&conn, &actor, EDIT_INVENTORY, "add variants")?;
+    let tx = conn.transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    ensure_code_available(&tx, &variant.sku, None)?;
+    if let Some(barcode) = &variant.barcode {
+        ensure_code_available(&tx, barcode, None)?;
+    }
+
+    tx.execute(
+        "INSERT INTO product_variants (product_id, name, sku, barcode, unit_price, price_bought, current_stock, minimum_stock)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            variant.product_id,
+            variant.name,
+            variant.sku,
+            variant.barcode,
+            variant.unit_price,
+            variant.price_bought,
+            variant.current_stock,
+            variant.minimum_stock
+        ],
+    ).map_err(|e| format!("Failed to insert variant: {}", e))?;
+
+    let id = tx.last_insert_rowid();
+    let after = snapshot(&tx, "product_variants", id)?;
+    record_audit(&tx, Some(&actor), CREATE, "product_variant", Some(id), None, after)?;
+
+    let created = get_variant(&tx, id as i32)?;
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    Ok(created)
+}
+
+#[tauri::command]
+pub fn update_product_variant(state: tauri::State<DbState>, variant: UpdateProductVariant, token: String) -> Result<ProductVariant, String> {
+    println!("Backend: Updating variant {}", variant.id);
+    let mut conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let actor = authenticate(&conn, &token)?;
+    require_permission(&conn, &actor, EDIT_INVENTORY, "change variants")?;
+    let tx = conn.transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    ensure_code_available(&tx, &variant.sku, Some(("product_variants", variant.id)))?;
+    if let Some(barcode) = &variant.barcode {
+        ensure_code_available(&tx, barcode, Some(("product_variants", variant.id)))?;
+    }
+
+    let before = snapshot(&tx, "product_variants", variant.id as i64)?;
+    let updated = tx.execute(
+        "UPDATE product_variants SET
+            name = ?1,
+            sku = ?2,
+            barcode = ?3,
+            unit_price = ?4,
+            price_bought = ?5,
+            current_stock = ?6,
+            minimum_stock = ?7,
+            updated_at = datetime('now')
+         WHERE id = ?8",
+        params![
+            variant.name,
+            variant.sku,
+            variant.barcode,
+            variant.unit_price,
+            variant.price_bought,
+            variant.current_stock,
+            variant.minimum_stock,
+            variant.id
+        ],
+    ).map_err(|e| format!("Failed to update variant: {}", e))?;
+
+    if updated == 0 {
+        return Err("Variant not found".to_string());
+    }
+
+    let after = snapshot(&tx, "product_variants", variant.id as i64)?;
+    record_audit(&tx, Some(&actor), UPDATE, "product_variant", Some(variant.id as i64), before, after)?;
+
+    let updated = get_variant(&tx, variant.id)?;
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    Ok(updated)
+}
+
+#[tauri::command]
+pub fn delete_product_variant(state: tauri::State<DbState>, id: i32, token: String) -> Result<(), String> {
+    println!("Backend: Deleting variant {}", id);
+    let mut conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let actor = authenticate(&conn, &token)?;
+    require_permission(&conn, &actor, EDIT_INVENTORY, "delete variants")?;
+    let tx = conn.transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let before = snapshot(&tx, "product_variants", id as i64)?;
+
+    // Past order lines keep the variant name, only the reference is cleared
+    tx.execute("UPDATE order_items SET variant_id = NULL WHERE variant_id = ?1", params![id])
+        .map_err(|e| format!("Failed to detach variant from order items: {}", e))?;
+
+    tx.execute("DELETE FROM product_barcodes WHERE variant_id = ?1", params![id])
+        .map_err(|e| format!("Failed to delete variant barcodes: {}", e))?;
+
+    let deleted = tx.execute("DELETE FROM product_variants WHERE id = ?1", params![id])
+        .map_err(|e| format!("Failed to delete variant: {}", e))?;
+
+    if deleted == 0 {
+        return Err("Variant not found".to_string());
+    }
+
+    record_audit(&tx, Some(&actor), DELETE, "product_variant", Some(id as i64), before, None)?;
+
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_modifier_groups(state: tauri::State<DbState>, product_id: i32) -> Result<Vec<ModifierGroup>, String> {
+    let conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    load_modifier_groups(&conn, product_id)
+}
+
+#[tauri::command]
+pub fn add_modifier_group(state: tauri::State<DbState>, group: NewModifierGroup, token: String) -> Result<ModifierGroup, String> {
+    println!("Backend: Adding modifier group {:?} to product {}", group.name, group.product_id);
+    let mut conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let actor = authenticate(&conn, &token)?;
+    require_permission(&conn, &actor, EDIT_INVENTORY, "add modifier groups")?;
+
+    let min_select = group.min_select.unwrap_or(0);
+    let max_select = group.max_select.unwrap_or(1);
+    if min_select < 0 || max_select < 1 || min_select > max_select {
+        return Err("Invalid selection limits for modifier group".to_string());
+    }
+
+    let tx = conn.transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+    tx.execute(
+        "INSERT INTO modifier_groups (product_id, name, min_select, max_select) VALUES (?1, ?2, ?3, ?4)",
+        params![group.product_id, group.name, min_select, max_select],
+    ).map_err(|e| format!("Failed to insert modifier group: {}", e))?;
+
+    let id = tx.last_insert_rowid();
+    let after = snapshot(&tx, "modifier_groups", id)?;
+    record_audit(&tx, Some(&actor), CREATE, "modifier_group", Some(id), None, after)?;
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+    Ok(ModifierGroup {
+        id: id as i32,
+        product_id: group.product_id,
+        name: group.name,
+        min_select,
+        max_select,
+        modifiers: Vec::new(),
+    })
+}
+
+#[tauri::command]
+pub fn delete_modifier_group(state: tauri::State<DbState>, id: i32, token: String) -> Result<(), String> {
+    let mut conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let actor = authenticate(&conn, &token)?;
+    require_permission(&conn, &actor, EDIT_INVENTORY, "delete modifier groups")?;
+    let tx = conn.transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let before = snapshot(&tx, "modifier_groups", id as i64)?;
+
+    tx.execute("DELETE FROM modifiers WHERE group_id = ?1", params![id])
+        .map_err(|e| format!("Failed to delete modifiers: {}", e))?;
+
+    let deleted = tx.execute("DELETE FROM modifier_groups WHERE id = ?1", params![id])
+        .map_err(|e| format!("Failed to delete modifier group: {}", e))?;
+
+    if deleted == 0 {
+        return Err("Modifier group not found".to_string());
+    }
+
+    record_audit(&tx, Some(&actor), DELETE, "modifier_group", Some(id as i64), before, None)?;
+
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn add_modifier(state: tauri::State<DbState>, modifier: NewModifier, token: String) -> Result<Modifier, String> {
+    println!("Backend: Adding modifier {:?} to group {}", modifier.name, modifier.group_id);
+    let mut conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let actor = authenticate(&conn, &token)?;
+    require_permission(&conn, &actor, EDIT_INVENTORY, "add modifiers")?;
+    let tx = conn.transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let group_exists = tx.query_row(
+        "SELECT id FROM modifier_groups WHERE id = ?1",
+        params![modifier.group_id],
+        |row| row.get::<_, i32>(0),
+    ).optional().map_err(|e| format!("Failed to check modifier group: {}", e))?;
+
+    if group_exists.is_none() {
+        return Err("Modifier group not found".to_string());
+    }
+
+    let cost = modifier.cost.unwrap_or(0.0);
+    tx.execute(
+        "INSERT INTO modifiers (group_id, name, price, cost) VALUES (?1, ?2, ?3, ?4)",
+        params![modifier.group_id, modifier.name, modifier.price, cost],
+    ).map_err(|e| format!("Failed to insert modifier: {}", e))?;
+
+    let id = tx.last_insert_rowid();
+    let after = snapshot(&tx, "modifiers", id)?;
+    record_audit(&tx, Some(&actor), CREATE, "modifier", Some(id), None, after)?;
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+    Ok(Modifier {
+        id: id as i32,
+        group_id: modifier.group_id,
+        name: modifier.name,
+        price: modifier.price,
+        cost,
+    })
+}
+
+#[tauri::command]
+pub fn delete_modifier(state: tauri::State<DbState>, id: i32, token: String) -> Result<(), String> {
+    let mut conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let actor = authenticate(&conn, &token)?;
+    require_permission(&conn, &actor, EDIT_INVENTORY, "delete modifiers")?;
+    let tx = conn.transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let before = snapshot(&tx, "modifiers", id as i64)?;
+
+    let deleted = tx.execute("DELETE FROM modifiers WHERE id = ?1", params![id])
+        .map_err(|e| format!("Failed to delete modifier: {}", e))?;
+
+    if deleted == 0 {
+        return Err("Modifier not found".to_string());
+    }
+
+    record_audit(&tx, Some(&actor), DELETE, "modifier", Some(id as i64), before, None)?;
+
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    Ok(())
+}
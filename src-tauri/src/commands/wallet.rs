@@ -0,0 +1,260 @@
+use crate::commands::audit::{record_audit, snapshot};
+use crate::commands::auth::authenticate;
+use crate::commands::customer::{load_customer, map_customer, CUSTOMER_COLUMNS};
+use crate::commands::role::require_permission;
+use crate::db::models::audit::UPDATE;
+use crate::db::models::customer::Customer;
+use crate::db::models::payment::{OrderPayment, ACCOUNT, PAYMENT_METHODS, WALLET};
+use crate::db::models::role::{self, SELL};
+use crate::db::models::wallet::{WalletEntry, WalletFunds, PURCHASE, REFUND, TOP_UP};
+use crate::db::DbState;
+use rusqlite::{params, Connection, OptionalExtension, Result};
+
+// Amounts are compared to the centavo
+const TOLERANCE: f64 = 0.005;
+
+const ENTRY_QUERY: &str =
+    "SELECT w.id, w.customer_id, w.kind, w.order_id, o.order_id, w.amount, w.method, w.reference, w.notes,
+            w.balance_after, w.created_at
+     FROM wallet_ledger w
+     LEFT JOIN orders o ON o.id = w.order_id";
+
+fn map_entry(row: &rusqlite::Row) -> Result<WalletEntry> {
+    Ok(WalletEntry {
+        id: row.get(0)?,
+        customer_id: row.get(1)?,
+        kind: row.get(2)?,
+        order_id: row.get(3)?,
+        order_number: row.get(4)?,
+        amount: row.get(5)?,
+        method: row.get(6)?,
+        reference: row.get(7)?,
+        notes: row.get(8)?,
+        balance_after: row.get(9)?,
+        created_at: row.get(10)?,
+    })
+}
+
+// Readers differ in the case they send hex codes in, so codes are kept upper-cased
+fn normalize_card(card_code: &str) -> Result<String, String> {
+    let card_code = card_code.trim().to_uppercase();
+    if card_code.is_empty() {
+        return Err("Card code is required".to_string());
+    }
+    Ok(card_code)
+}
+
+fn load_card_customer(conn: &Connection, card_code: &str) -> Result<Customer, String> {
+    let card_code = normalize_card(card_code)?;
+    conn.query_row(
+        &format!("SELECT {} FROM customers WHERE card_code = ?1", CUSTOMER_COLUMNS),
+        params![card_code],
+        map_customer,
+    ).optional()
+        .map_err(|e| format!("Failed to look up card: {}", e))?
+        .ok_or_else(|| format!("Card {} is not linked to a customer", card_code))
+}
+
+fn validate_funds(funds: &WalletFunds) -> Result<String, String> {
+    if !funds.amount.is_finite() || funds.amount <= 0.0 {
+        return Err("Amount must be greater than zero".to_string());
+    }
+    let method = funds.method.to_lowercase();
+    if method == ACCOUNT || method == WALLET || !PAYMENT_METHODS.contains(&method.as_str()) {
+        return Err(format!("Unknown payment method: {}", funds.method));
+    }
+    Ok(method)
+}
+
+// Add an entry to a customer's wallet and move their prepaid balance by its amount;
+// details are the (method, reference, notes) of the entry
+fn post_entry(
+    conn: &Connection,
+    customer: &Customer,
+    kind: &str,
+    order_id: Option<i32>,
+    amount: f64,
+    details: (Option<&str>, Option<&str>, Option<&str>),
+) -> Result<WalletEntry, String> {
+    let (method, reference, notes) = details;
+    let balance_after = ((customer.wallet_balance + amount) * 100.0).round() / 100.0;
+    let created_at = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+
+    conn.execute(
+        "INSERT INTO wallet_ledger (customer_id, kind, order_id, amount, method, reference, notes, balance_after, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        params![customer.id, kind, order_id, amount, method, reference, notes, balance_after, created_at],
+    ).map_err(|e| format!("Failed to record wallet entry: {}", e))?;
+    let entry_id = conn.last_insert_rowid();
+
+    conn.execute(
+        "UPDATE customers SET wallet_balance = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
+        params![balance_after, customer.id],
+    ).map_err(|e| format!("Failed to update wallet balance: {}", e))?;
+
+    conn.query_row(&format!("{} WHERE w.id = ?1", ENTRY_QUERY), params![entry_id], map_entry)
+        .map_err(|e| format!("Failed to get wallet entry: {}", e))
+}
+
+/// Take an order's wallet payments out of the prepaid balance of each scanned card, failing
+/// the order if a card doesn't hold enough. Runs in the order's transaction.
+pub fn pay_from_wallets(conn: &Connection, order_id: i32, payments: &[OrderPayment]) -> Result<(), String> {
+    for payment in payments.iter().filter(|p| p.method == WALLET) {
+        let card_code = payment.reference.as_deref().unwrap_or_default();
+        let customer = load_card_customer(conn, card_code)?;
+
+        if !customer.active {
+            return Err(format!("{}'s account is closed", customer.name));
+        }
+        if customer.wallet_balance + TOLERANCE < payment.amount {
+            return Err(format!(
+                "Insufficient wallet balance: {} has {:.2} but the payment is {:.2}",
+                customer.name, customer.wallet_balance, payment.amount
+            ));
+        }
+
+        post_entry(conn, &customer, PURCHASE, Some(order_id), -payment.amount, (None, None, None))?;
+    }
+    Ok(())
+}
+
+/// Link a card to a customer's wallet, replacing any card they had; no card unlinks it.
+/// The balance stays with the customer, so a lost card can be swapped for a new one.
+#[tauri::command]
+pub fn assign_wallet_card(
+    state: tauri::State<DbState>,
+    customer_id: i32,
+    card_code: Option<String>,
+    token: String,
+) -> Result<Customer, String> {
+    println!("Backend: Assigning wallet card to customer {}", customer_id);
+
+    let mut conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let actor = authenticate(&conn, &token)?;
+    require_permission(&conn, &actor, SELL, "assign wallet cards")?;
+    let tx = conn.transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let customer = load_customer(&tx, customer_id)?;
+
+    let card_code = match card_code.as_deref().map(str::trim).filter(|c| !c.is_empty()) {
+        Some(card_code) => Some(normalize_card(card_code)?),
+        None => None,
+    };
+
+    if let Some(card_code) = &card_code {
+        let holder: Option<String> = tx.query_row(
+            "SELECT name FROM customers WHERE card_code = ?1 AND id != ?2",
+            params![card_code, customer.id],
+            |row| row.get(0),
+        ).optional()
+            .map_err(|e| format!("Failed to look up card: {}", e))?;
+
+        if let Some(holder) = holder {
+            return Err(format!("Card {} is already linked to {}", card_code, holder));
+        }
+    }
+
+    let before = snapshot(&tx, "customers", customer.id as i64)?;
+    tx.execute(
+        "UPDATE customers SET card_code = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
+        params![card_code, customer.id],
+    ).map_err(|e| format!("Failed to assign card: {}", e))?;
+
+    let after = snapshot(&tx, "customers", customer.id as i64)?;
+    record_audit(&tx, Some(&actor), UPDATE, "customer", Some(customer.id as i64), before, after)?;
+
+    let updated = load_customer(&tx, customer.id)?;
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    Ok(updated)
+}
+
+/// The customer a scanned card belongs to, with their prepaid balance
+#[tauri::command]
+pub fn get_wallet_by_card(state: tauri::State<DbState>, card_code: String) -> Result<Customer, String> {
+    let conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    load_card_customer(&conn, &card_code)
+}
+
+#[tauri::command]
+pub fn top_up_wallet(state: tauri::State<DbState>, funds: WalletFunds, token: String) -> Result<WalletEntry, String> {
+    println!("Backend: Topping up card {} with {:.2}", funds.card_code, funds.amount);
+
+    let method = validate_funds(&funds)?;
+
+    let mut conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let actor = authenticate(&conn, &token)?;
+    require_permission(&conn, &actor, SELL, "top up wallets")?;
+    let tx = conn.transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let customer = load_card_customer(&tx, &funds.card_code)?;
+    let before = snapshot(&tx, "customers", customer.id as i64)?;
+    if !customer.active {
+        return Err(format!("{}'s account is closed", customer.name));
+    }
+
+    let reference = funds.reference.as_deref().map(str::trim).filter(|r| !r.is_empty());
+    let entry = post_entry(&tx, &customer, TOP_UP, None, funds.amount, (Some(&method), reference, funds.notes.as_deref()))?;
+
+    let after = snapshot(&tx, "customers", customer.id as i64)?;
+    record_audit(&tx, Some(&actor), UPDATE, "customer", Some(customer.id as i64), before, after)?;
+
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    Ok(entry)
+}
+
+/// Pay part or all of a wallet's balance back to the customer. Closed accounts can still be
+/// refunded so whatever is left on them can be cashed out.
+#[tauri::command]
+pub fn refund_wallet(state: tauri::State<DbState>, funds: WalletFunds, token: String) -> Result<WalletEntry, String> {
+    println!("Backend: Refunding {:.2} from card {}", funds.amount, funds.card_code);
+
+    let method = validate_funds(&funds)?;
+
+    let mut conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+    let actor = authenticate(&conn, &token)?;
+    require_permission(&conn, &actor, role::REFUND, "refund wallets")?;
+    let tx = conn.transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let customer = load_card_customer(&tx, &funds.card_code)?;
+    let before = snapshot(&tx, "customers", customer.id as i64)?;
+    if customer.wallet_balance + TOLERANCE < funds.amount {
+        return Err(format!(
+            "Cannot refund {:.2}; {}'s wallet only holds {:.2}",
+            funds.amount, customer.name, customer.wallet_balance
+        ));
+    }
+
+    let reference = funds.reference.as_deref().map(str::trim).filter(|r| !r.is_empty());
+    let entry = post_entry(&tx, &customer, REFUND, None, -funds.amount, (Some(&method), reference, funds.notes.as_deref()))?;
+
+    let after = snapshot(&tx, "customers", customer.id as i64)?;
+    record_audit(&tx, Some(&actor), UPDATE, "customer", Some(customer.id as i64), before, after)?;
+
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    Ok(entry)
+}
+
+/// Top-ups, purchases and refunds on a customer's wallet between two optional dates
+/// (inclusive), newest first, each with the balance it left
+#[tauri::command]
+pub fn get_wallet_history(
+    state: tauri::State<DbState>,
+    customer_id: i32,
+    start_date: Option<String>,
+    end_date: Option<String>,
+) -> Result<Vec<WalletEntry>, String> {
+    let conn = state.pool.get().map_err(|e| format!("Failed to get connection from pool: {}", e))?;
+
+    let mut stmt = conn.prepare(&format!(
+        "{} WHERE w.customer_id = ?1
+           AND (?2 IS NULL OR date(w.created_at) >= date(?2))
+           AND (?3 IS NULL OR date(w.created_at) <= date(?3))
+         ORDER BY w.id DESC",
+        ENTRY_QUERY
+    )).map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+    let rows = stmt.query_map(params![customer_id, start_date, end_date], map_entry)
+        .map_err(|e| format!("Failed to query wallet history: {}", e))?;
+
+    rows.collect::<Result<Vec<_>>>()
+        .map_err(|e| format!("Failed to collect wallet history: {}", e))
+}
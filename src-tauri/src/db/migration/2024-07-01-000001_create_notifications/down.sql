@@ -0,0 +1,3 @@
+DROP TABLE notification_states;
+DROP INDEX idx_notifications_open;
+DROP TABLE notifications;
@@ -0,0 +1,8 @@
+ALTER TABLE order_items DROP COLUMN cost;
+ALTER TABLE order_items DROP COLUMN variant_name;
+ALTER TABLE order_items DROP COLUMN variant_id;
+
+DROP TABLE order_item_modifiers;
+DROP TABLE modifiers;
+DROP TABLE modifier_groups;
+DROP TABLE product_variants;
@@ -0,0 +1,49 @@
+-- Variants of a product, each with its own price, cost, SKU, barcode and stock
+CREATE TABLE product_variants (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    product_id INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    sku TEXT NOT NULL UNIQUE,
+    barcode TEXT,
+    unit_price REAL NOT NULL,
+    price_bought REAL NOT NULL DEFAULT 0,
+    current_stock INTEGER NOT NULL DEFAULT 0,
+    minimum_stock INTEGER NOT NULL DEFAULT 0,
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    updated_at TEXT NOT NULL DEFAULT (datetime('now')),
+    FOREIGN KEY (product_id) REFERENCES products(id) ON DELETE CASCADE
+);
+
+-- Optional add-ons (e.g. extra shot) grouped per product
+CREATE TABLE modifier_groups (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    product_id INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    min_select INTEGER NOT NULL DEFAULT 0,
+    max_select INTEGER NOT NULL DEFAULT 1,
+    FOREIGN KEY (product_id) REFERENCES products(id) ON DELETE CASCADE
+);
+
+CREATE TABLE modifiers (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    group_id INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    price REAL NOT NULL DEFAULT 0,
+    cost REAL NOT NULL DEFAULT 0,
+    FOREIGN KEY (group_id) REFERENCES modifier_groups(id) ON DELETE CASCADE
+);
+
+-- Modifiers chosen on a sale line, copied so later catalogue edits don't change history
+CREATE TABLE order_item_modifiers (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    order_item_id INTEGER NOT NULL,
+    modifier_id INTEGER,
+    modifier_name TEXT NOT NULL,
+    price REAL NOT NULL,
+    FOREIGN KEY (order_item_id) REFERENCES order_items(id) ON DELETE CASCADE,
+    FOREIGN KEY (modifier_id) REFERENCES modifiers(id) ON DELETE SET NULL
+);
+
+ALTER TABLE order_items ADD COLUMN variant_id INTEGER REFERENCES product_variants(id) ON DELETE SET NULL;
+ALTER TABLE order_items ADD COLUMN variant_name TEXT;
+ALTER TABLE order_items ADD COLUMN cost REAL;
@@ -0,0 +1,2 @@
+DROP TABLE recipe_components;
+DROP TABLE recipes;
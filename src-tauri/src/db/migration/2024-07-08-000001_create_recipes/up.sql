@@ -0,0 +1,19 @@
+-- Prepared items (e.g. a sandwich) and the ingredient products they consume
+CREATE TABLE recipes (
+    product_id INTEGER PRIMARY KEY NOT NULL,
+    deduct_finished_item INTEGER NOT NULL DEFAULT 0,
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    updated_at TEXT NOT NULL DEFAULT (datetime('now')),
+    FOREIGN KEY (product_id) REFERENCES products(id) ON DELETE CASCADE
+);
+
+CREATE TABLE recipe_components (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    product_id INTEGER NOT NULL,
+    component_id INTEGER NOT NULL,
+    quantity INTEGER NOT NULL,
+    unit TEXT,
+    UNIQUE (product_id, component_id),
+    FOREIGN KEY (product_id) REFERENCES recipes(product_id) ON DELETE CASCADE,
+    FOREIGN KEY (component_id) REFERENCES products(id) ON DELETE RESTRICT
+);
@@ -0,0 +1,27 @@
+-- Units of measure per product; products without a row are counted in whole pieces.
+-- Stock and quantity columns keep their INTEGER affinity: SQLite stores fractional
+-- values in them as REAL, so existing tables do not need to be rebuilt.
+CREATE TABLE product_units (
+    product_id INTEGER PRIMARY KEY NOT NULL,
+    base_unit TEXT NOT NULL DEFAULT 'pc',
+    purchase_unit TEXT,
+    purchase_factor REAL NOT NULL DEFAULT 1,
+    allow_fractional INTEGER NOT NULL DEFAULT 0,
+    updated_at TEXT NOT NULL DEFAULT (datetime('now')),
+    FOREIGN KEY (product_id) REFERENCES products(id) ON DELETE CASCADE
+);
+
+-- Stock received or counted, kept in both the entered unit and the base unit
+CREATE TABLE stock_movements (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    product_id INTEGER NOT NULL,
+    kind TEXT NOT NULL,
+    quantity REAL NOT NULL,
+    entered_quantity REAL NOT NULL,
+    entered_unit TEXT NOT NULL,
+    unit_cost REAL,
+    stock_after REAL NOT NULL,
+    reference TEXT,
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    FOREIGN KEY (product_id) REFERENCES products(id) ON DELETE CASCADE
+);
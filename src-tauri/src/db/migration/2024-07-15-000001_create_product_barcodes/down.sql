@@ -0,0 +1,4 @@
+DROP INDEX idx_products_barcode;
+DROP INDEX idx_product_variants_barcode;
+DROP TABLE barcode_conflicts;
+DROP TABLE product_barcodes;
@@ -0,0 +1,65 @@
+-- Alternate barcodes for a product, optionally tied to one of its variants
+CREATE TABLE product_barcodes (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    product_id INTEGER NOT NULL,
+    variant_id INTEGER,
+    code TEXT NOT NULL UNIQUE,
+    label TEXT,
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    FOREIGN KEY (product_id) REFERENCES products(id) ON DELETE CASCADE,
+    FOREIGN KEY (variant_id) REFERENCES product_variants(id) ON DELETE CASCADE
+);
+
+-- Report of duplicate codes found while adding the unique indexes
+CREATE TABLE barcode_conflicts (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    code TEXT NOT NULL,
+    source_table TEXT NOT NULL,
+    row_id INTEGER NOT NULL,
+    kept_row_id INTEGER,
+    resolution TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+-- Keep the oldest row of each duplicated barcode and clear the rest
+INSERT INTO barcode_conflicts (code, source_table, row_id, kept_row_id, resolution)
+SELECT t.barcode, 'products', t.id, k.kept_id, 'cleared'
+FROM products t
+JOIN (SELECT barcode, MIN(id) AS kept_id FROM products
+      WHERE barcode IS NOT NULL AND barcode != '' GROUP BY barcode) k ON k.barcode = t.barcode
+WHERE t.id != k.kept_id;
+
+UPDATE products SET barcode = NULL
+WHERE id IN (SELECT row_id FROM barcode_conflicts WHERE source_table = 'products' AND resolution = 'cleared');
+
+INSERT INTO barcode_conflicts (code, source_table, row_id, kept_row_id, resolution)
+SELECT t.barcode, 'product_variants', t.id, k.kept_id, 'cleared'
+FROM product_variants t
+JOIN (SELECT barcode, MIN(id) AS kept_id FROM product_variants
+      WHERE barcode IS NOT NULL AND barcode != '' GROUP BY barcode) k ON k.barcode = t.barcode
+WHERE t.id != k.kept_id;
+
+UPDATE product_variants SET barcode = NULL
+WHERE id IN (SELECT row_id FROM barcode_conflicts WHERE source_table = 'product_variants' AND resolution = 'cleared');
+
+-- Codes shared between different rows are only reported
+WITH codes AS (
+    SELECT barcode AS code, 'products' AS source_table, id AS row_id FROM products
+    WHERE barcode IS NOT NULL AND barcode != ''
+    UNION ALL SELECT sku, 'products', id FROM products
+    UNION ALL SELECT barcode, 'product_variants', id FROM product_variants
+    WHERE barcode IS NOT NULL AND barcode != ''
+    UNION ALL SELECT sku, 'product_variants', id FROM product_variants
+)
+INSERT INTO barcode_conflicts (code, source_table, row_id, resolution)
+SELECT DISTINCT code, source_table, row_id, 'conflict' FROM codes
+WHERE code IN (
+    SELECT code FROM codes GROUP BY code
+    HAVING COUNT(DISTINCT source_table || ':' || row_id) > 1
+);
+
+CREATE UNIQUE INDEX idx_product_variants_barcode ON product_variants(barcode)
+WHERE barcode IS NOT NULL AND barcode != '';
+
+CREATE UNIQUE INDEX idx_products_barcode ON products(barcode)
+WHERE barcode IS NOT NULL AND barcode != '';
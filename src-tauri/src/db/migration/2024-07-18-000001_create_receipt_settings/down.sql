@@ -0,0 +1,2 @@
+DROP TABLE receipt_prints;
+DROP TABLE receipt_settings;
@@ -0,0 +1,26 @@
+-- Receipt template and printer settings; there is only ever one row
+CREATE TABLE receipt_settings (
+    id INTEGER PRIMARY KEY NOT NULL CHECK (id = 1),
+    store_name TEXT NOT NULL,
+    header TEXT,
+    footer TEXT,
+    paper_width INTEGER NOT NULL DEFAULT 42,
+    printer_type TEXT NOT NULL DEFAULT 'none',
+    printer_target TEXT,
+    pdf_directory TEXT,
+    cut_paper INTEGER NOT NULL DEFAULT 1,
+    open_drawer INTEGER NOT NULL DEFAULT 0,
+    updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+-- Every receipt printed or saved, including reprints
+CREATE TABLE receipt_prints (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    order_id INTEGER NOT NULL,
+    reprint INTEGER NOT NULL DEFAULT 0,
+    format TEXT NOT NULL,
+    destination TEXT NOT NULL,
+    printer_error TEXT,
+    printed_at TEXT NOT NULL DEFAULT (datetime('now')),
+    FOREIGN KEY (order_id) REFERENCES orders(id) ON DELETE CASCADE
+);
@@ -0,0 +1,2 @@
+DROP INDEX idx_order_payments_order;
+DROP TABLE order_payments;
@@ -0,0 +1,18 @@
+-- Payments settling each order, one row per method when a payment is split
+CREATE TABLE order_payments (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    order_id INTEGER NOT NULL,
+    method TEXT NOT NULL,
+    amount REAL NOT NULL,
+    reference TEXT,
+    tendered REAL,
+    change REAL NOT NULL DEFAULT 0,
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    FOREIGN KEY (order_id) REFERENCES orders(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_order_payments_order ON order_payments(order_id);
+
+-- Orders taken before payments were tracked were all paid in cash
+INSERT INTO order_payments (order_id, method, amount, tendered, change, created_at)
+SELECT id, 'cash', total, total, 0, created_at FROM orders;
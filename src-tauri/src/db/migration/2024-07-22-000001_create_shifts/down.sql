@@ -0,0 +1,4 @@
+DROP INDEX idx_orders_shift;
+ALTER TABLE orders DROP COLUMN shift_id;
+DROP TABLE cash_movements;
+DROP TABLE shifts;
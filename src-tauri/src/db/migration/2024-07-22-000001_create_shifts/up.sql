@@ -0,0 +1,30 @@
+-- Cashier shifts; closed shifts keep their Z report as JSON
+CREATE TABLE shifts (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    user_id INTEGER NOT NULL,
+    cashier TEXT NOT NULL,
+    opening_float REAL NOT NULL DEFAULT 0,
+    opened_at TEXT NOT NULL,
+    closed_at TEXT,
+    counted_cash REAL,
+    expected_cash REAL,
+    over_short REAL,
+    notes TEXT,
+    z_report TEXT,
+    FOREIGN KEY (user_id) REFERENCES users(id)
+);
+
+-- Cash put into or taken out of the drawer outside of sales
+CREATE TABLE cash_movements (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    shift_id INTEGER NOT NULL,
+    kind TEXT NOT NULL,
+    amount REAL NOT NULL,
+    reason TEXT,
+    created_at TEXT NOT NULL,
+    FOREIGN KEY (shift_id) REFERENCES shifts(id) ON DELETE CASCADE
+);
+
+ALTER TABLE orders ADD COLUMN shift_id INTEGER REFERENCES shifts(id);
+
+CREATE INDEX idx_orders_shift ON orders(shift_id);
@@ -0,0 +1,5 @@
+ALTER TABLE order_items DROP COLUMN discount;
+ALTER TABLE orders DROP COLUMN discount;
+DROP INDEX idx_order_discounts_order;
+DROP TABLE order_discounts;
+DROP TABLE promotions;
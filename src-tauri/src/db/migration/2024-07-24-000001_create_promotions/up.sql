@@ -0,0 +1,41 @@
+-- Scheduled promotions applied when an order is priced
+CREATE TABLE promotions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    name TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    percent REAL NOT NULL,
+    product_id INTEGER,
+    category_id INTEGER,
+    buy_quantity REAL,
+    get_quantity REAL,
+    start_time TEXT,
+    end_time TEXT,
+    starts_on TEXT,
+    ends_on TEXT,
+    active BOOLEAN NOT NULL DEFAULT 1,
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    FOREIGN KEY (product_id) REFERENCES products(id) ON DELETE CASCADE,
+    FOREIGN KEY (category_id) REFERENCES categories(id) ON DELETE CASCADE
+);
+
+-- Every discount given, against the order line it was given on
+CREATE TABLE order_discounts (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    order_id INTEGER NOT NULL,
+    order_item_id INTEGER,
+    source TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    promotion_id INTEGER,
+    description TEXT NOT NULL,
+    amount REAL NOT NULL,
+    reason TEXT,
+    id_number TEXT,
+    created_at TEXT NOT NULL,
+    FOREIGN KEY (order_id) REFERENCES orders(id) ON DELETE CASCADE,
+    FOREIGN KEY (order_item_id) REFERENCES order_items(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_order_discounts_order ON order_discounts(order_id);
+
+ALTER TABLE orders ADD COLUMN discount REAL NOT NULL DEFAULT 0;
+ALTER TABLE order_items ADD COLUMN discount REAL NOT NULL DEFAULT 0;
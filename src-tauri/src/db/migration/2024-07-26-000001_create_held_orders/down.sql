@@ -0,0 +1,2 @@
+DROP INDEX idx_held_orders_status;
+DROP TABLE held_orders;
@@ -0,0 +1,19 @@
+-- Carts parked at the register; lines are kept as JSON until the cart is finalized
+CREATE TABLE held_orders (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    cashier TEXT NOT NULL,
+    label TEXT,
+    items TEXT NOT NULL,
+    discount TEXT,
+    subtotal REAL NOT NULL,
+    tax REAL NOT NULL,
+    total REAL NOT NULL,
+    status TEXT NOT NULL DEFAULT 'held',
+    order_id INTEGER,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL,
+    expires_at TEXT NOT NULL,
+    FOREIGN KEY (order_id) REFERENCES orders(id)
+);
+
+CREATE INDEX idx_held_orders_status ON held_orders(status, expires_at);
@@ -0,0 +1,2 @@
+DROP INDEX idx_orders_order_id;
+DROP TABLE order_sequences;
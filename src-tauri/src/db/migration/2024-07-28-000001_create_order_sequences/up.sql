@@ -0,0 +1,11 @@
+-- Last order number handed out per terminal and day, e.g. POS-20240728
+CREATE TABLE order_sequences (
+    prefix TEXT PRIMARY KEY NOT NULL,
+    last_number INTEGER NOT NULL
+);
+
+-- Numbers issued twice by the registers get their row id appended
+UPDATE orders SET order_id = order_id || '-' || id
+WHERE id NOT IN (SELECT MIN(id) FROM orders GROUP BY order_id);
+
+CREATE UNIQUE INDEX idx_orders_order_id ON orders(order_id);
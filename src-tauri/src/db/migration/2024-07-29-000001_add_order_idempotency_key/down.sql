@@ -0,0 +1,2 @@
+DROP INDEX idx_orders_idempotency_key;
+ALTER TABLE orders DROP COLUMN idempotency_key;
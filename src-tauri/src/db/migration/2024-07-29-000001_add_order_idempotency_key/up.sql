@@ -0,0 +1,4 @@
+-- Key sent by the register with each sale so a retried submission isn't recorded twice
+ALTER TABLE orders ADD COLUMN idempotency_key TEXT;
+
+CREATE UNIQUE INDEX idx_orders_idempotency_key ON orders(idempotency_key) WHERE idempotency_key IS NOT NULL;
@@ -0,0 +1,5 @@
+ALTER TABLE held_orders DROP COLUMN customer_id;
+ALTER TABLE orders DROP COLUMN customer_id;
+DROP INDEX idx_customer_ledger_customer;
+DROP TABLE customer_ledger;
+DROP TABLE customers;
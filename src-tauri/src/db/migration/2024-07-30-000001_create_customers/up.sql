@@ -0,0 +1,35 @@
+-- Customers who can run a tab; a positive balance is owed, a negative one is store credit
+CREATE TABLE customers (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    name TEXT NOT NULL,
+    id_number TEXT,
+    phone TEXT,
+    email TEXT,
+    credit_limit REAL NOT NULL DEFAULT 0,
+    balance REAL NOT NULL DEFAULT 0,
+    active BOOLEAN NOT NULL DEFAULT 1,
+    notes TEXT,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Charges (positive) and payments (negative) on each account
+CREATE TABLE customer_ledger (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    customer_id INTEGER NOT NULL,
+    kind TEXT NOT NULL,
+    order_id INTEGER,
+    amount REAL NOT NULL,
+    method TEXT,
+    reference TEXT,
+    notes TEXT,
+    balance_after REAL NOT NULL,
+    created_at TEXT NOT NULL,
+    FOREIGN KEY (customer_id) REFERENCES customers(id),
+    FOREIGN KEY (order_id) REFERENCES orders(id)
+);
+
+CREATE INDEX idx_customer_ledger_customer ON customer_ledger(customer_id, created_at);
+
+ALTER TABLE orders ADD COLUMN customer_id INTEGER REFERENCES customers(id);
+ALTER TABLE held_orders ADD COLUMN customer_id INTEGER REFERENCES customers(id);
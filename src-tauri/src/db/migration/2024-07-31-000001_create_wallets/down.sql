@@ -0,0 +1,5 @@
+DROP INDEX idx_wallet_ledger_customer;
+DROP TABLE wallet_ledger;
+DROP INDEX idx_customers_card_code;
+ALTER TABLE customers DROP COLUMN wallet_balance;
+ALTER TABLE customers DROP COLUMN card_code;
@@ -0,0 +1,23 @@
+-- Cards linked to customers' prepaid wallets
+ALTER TABLE customers ADD COLUMN card_code TEXT;
+ALTER TABLE customers ADD COLUMN wallet_balance REAL NOT NULL DEFAULT 0;
+
+CREATE UNIQUE INDEX idx_customers_card_code ON customers(card_code) WHERE card_code IS NOT NULL;
+
+-- Top-ups (positive), purchases and refunds (negative) on each wallet
+CREATE TABLE wallet_ledger (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    customer_id INTEGER NOT NULL,
+    kind TEXT NOT NULL,
+    order_id INTEGER,
+    amount REAL NOT NULL,
+    method TEXT,
+    reference TEXT,
+    notes TEXT,
+    balance_after REAL NOT NULL,
+    created_at TEXT NOT NULL,
+    FOREIGN KEY (customer_id) REFERENCES customers(id),
+    FOREIGN KEY (order_id) REFERENCES orders(id)
+);
+
+CREATE INDEX idx_wallet_ledger_customer ON wallet_ledger(customer_id, created_at);
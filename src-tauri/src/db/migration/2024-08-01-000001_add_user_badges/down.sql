@@ -0,0 +1,4 @@
+DROP TABLE terminal_cashiers;
+DROP INDEX idx_users_badge_hash;
+ALTER TABLE users DROP COLUMN badge_pin_hash;
+ALTER TABLE users DROP COLUMN badge_hash;
@@ -0,0 +1,13 @@
+-- Badges are looked up by hash; the PIN is an optional second factor
+ALTER TABLE users ADD COLUMN badge_hash TEXT;
+ALTER TABLE users ADD COLUMN badge_pin_hash TEXT;
+
+CREATE UNIQUE INDEX idx_users_badge_hash ON users(badge_hash) WHERE badge_hash IS NOT NULL;
+
+-- Cashier last switched in on each terminal; their name goes on the terminal's orders
+CREATE TABLE terminal_cashiers (
+    terminal TEXT PRIMARY KEY NOT NULL,
+    user_id INTEGER NOT NULL,
+    switched_at TEXT NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES users(id)
+);
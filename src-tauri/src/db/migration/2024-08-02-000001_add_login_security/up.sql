@@ -0,0 +1,20 @@
+-- Password policy and lockout rules (a single row)
+CREATE TABLE security_settings (
+    id INTEGER PRIMARY KEY NOT NULL CHECK (id = 1),
+    min_password_length INTEGER NOT NULL DEFAULT 8,
+    require_letter INTEGER NOT NULL DEFAULT 1,
+    require_digit INTEGER NOT NULL DEFAULT 1,
+    require_mixed_case INTEGER NOT NULL DEFAULT 0,
+    require_symbol INTEGER NOT NULL DEFAULT 0,
+    max_failed_logins INTEGER NOT NULL DEFAULT 5,
+    lockout_minutes INTEGER NOT NULL DEFAULT 15,
+    updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+ALTER TABLE users ADD COLUMN failed_login_attempts INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE users ADD COLUMN locked_until TEXT;
+ALTER TABLE users ADD COLUMN must_change_password BOOLEAN NOT NULL DEFAULT 0;
+ALTER TABLE users ADD COLUMN password_changed_at TEXT;
+
+-- The seeded admin account has to pick its own password
+UPDATE users SET must_change_password = 1 WHERE username = 'admin';
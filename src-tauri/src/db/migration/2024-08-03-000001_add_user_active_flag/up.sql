@@ -0,0 +1,3 @@
+-- Deactivated users can't sign in; their orders and shifts still point at them
+ALTER TABLE users ADD COLUMN active BOOLEAN NOT NULL DEFAULT 1;
+ALTER TABLE users ADD COLUMN deactivated_at TEXT;
@@ -0,0 +1,3 @@
+DROP TABLE user_permission_overrides;
+DROP TABLE role_permissions;
+DROP TABLE roles;
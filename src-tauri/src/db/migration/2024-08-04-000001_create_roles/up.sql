@@ -0,0 +1,41 @@
+-- Named permission sets; users.role holds the name
+CREATE TABLE roles (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    name TEXT NOT NULL UNIQUE,
+    description TEXT,
+    built_in BOOLEAN NOT NULL DEFAULT 0,
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE TABLE role_permissions (
+    role_id INTEGER NOT NULL,
+    permission TEXT NOT NULL,
+    PRIMARY KEY (role_id, permission),
+    FOREIGN KEY (role_id) REFERENCES roles(id)
+);
+
+-- Permissions granted to (1) or taken from (0) one user regardless of their role
+CREATE TABLE user_permission_overrides (
+    user_id INTEGER NOT NULL,
+    permission TEXT NOT NULL,
+    granted BOOLEAN NOT NULL,
+    PRIMARY KEY (user_id, permission),
+    FOREIGN KEY (user_id) REFERENCES users(id)
+);
+
+INSERT INTO roles (name, description, built_in) VALUES
+    ('admin', 'Everything, including staff and settings', 1),
+    ('manager', 'Runs the store day to day', 1),
+    ('cashier', 'Rings up sales', 1),
+    ('user', 'Default for new accounts', 1);
+
+INSERT INTO role_permissions (role_id, permission)
+SELECT id, 'view_inventory' FROM roles WHERE name IN ('manager', 'cashier', 'user')
+UNION ALL SELECT id, 'edit_inventory' FROM roles WHERE name = 'manager'
+UNION ALL SELECT id, 'sell' FROM roles WHERE name IN ('manager', 'cashier', 'user')
+UNION ALL SELECT id, 'refund' FROM roles WHERE name = 'manager'
+UNION ALL SELECT id, 'view_reports' FROM roles WHERE name = 'manager';
+
+INSERT OR IGNORE INTO roles (name, description)
+SELECT DISTINCT role, 'Carried over from existing accounts' FROM users;
@@ -0,0 +1,33 @@
+CREATE TABLE price_history (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    product_id INTEGER NOT NULL,
+    old_unit_price REAL NOT NULL,
+    new_unit_price REAL NOT NULL,
+    old_price_bought REAL NOT NULL,
+    new_price_bought REAL NOT NULL,
+    user_id INTEGER,
+    username TEXT,
+    reason TEXT,
+    scheduled_change_id INTEGER,
+    changed_at TEXT NOT NULL,
+    FOREIGN KEY (product_id) REFERENCES products(id)
+);
+
+CREATE INDEX idx_price_history_product ON price_history(product_id, changed_at);
+
+CREATE TABLE scheduled_price_changes (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    product_id INTEGER NOT NULL,
+    unit_price REAL,
+    price_bought REAL,
+    effective_at TEXT NOT NULL,
+    reason TEXT,
+    status TEXT NOT NULL DEFAULT 'pending',
+    created_by_id INTEGER,
+    created_by TEXT,
+    created_at TEXT NOT NULL,
+    applied_at TEXT,
+    FOREIGN KEY (product_id) REFERENCES products(id)
+);
+
+CREATE INDEX idx_scheduled_price_changes_due ON scheduled_price_changes(status, effective_at);
@@ -0,0 +1,2 @@
+ALTER TABLE categories DROP COLUMN archived_at;
+ALTER TABLE products DROP COLUMN archived_at;
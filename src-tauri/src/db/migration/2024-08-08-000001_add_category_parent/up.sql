@@ -0,0 +1,2 @@
+ALTER TABLE categories ADD COLUMN parent_id INTEGER REFERENCES categories(id);
+CREATE INDEX idx_categories_parent ON categories(parent_id);
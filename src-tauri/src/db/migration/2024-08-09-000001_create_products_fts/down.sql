@@ -0,0 +1,10 @@
+DROP TRIGGER product_variants_fts_delete;
+DROP TRIGGER product_variants_fts_update;
+DROP TRIGGER product_variants_fts_insert;
+DROP TRIGGER product_barcodes_fts_delete;
+DROP TRIGGER product_barcodes_fts_update;
+DROP TRIGGER product_barcodes_fts_insert;
+DROP TRIGGER products_fts_delete;
+DROP TRIGGER products_fts_update;
+DROP TRIGGER products_fts_insert;
+DROP TABLE products_fts;
@@ -0,0 +1,120 @@
+CREATE VIRTUAL TABLE products_fts USING fts5(
+    name, description, sku, codes,
+    tokenize = 'unicode61 remove_diacritics 2'
+);
+
+CREATE TRIGGER products_fts_insert AFTER INSERT ON products BEGIN
+    DELETE FROM products_fts WHERE rowid = new.id;
+    INSERT INTO products_fts (rowid, name, description, sku, codes)
+    SELECT p.id, p.name, COALESCE(p.description, ''), p.sku,
+           TRIM(COALESCE(p.barcode, '') || ' ' ||
+                COALESCE((SELECT group_concat(b.code, ' ') FROM product_barcodes b WHERE b.product_id = p.id), '') || ' ' ||
+                COALESCE((SELECT group_concat(v.sku || ' ' || COALESCE(v.barcode, ''), ' ')
+                          FROM product_variants v WHERE v.product_id = p.id), ''))
+    FROM products p WHERE p.id = new.id;
+END;
+
+CREATE TRIGGER products_fts_update AFTER UPDATE OF name, description, sku, barcode ON products BEGIN
+    DELETE FROM products_fts WHERE rowid = new.id;
+    INSERT INTO products_fts (rowid, name, description, sku, codes)
+    SELECT p.id, p.name, COALESCE(p.description, ''), p.sku,
+           TRIM(COALESCE(p.barcode, '') || ' ' ||
+                COALESCE((SELECT group_concat(b.code, ' ') FROM product_barcodes b WHERE b.product_id = p.id), '') || ' ' ||
+                COALESCE((SELECT group_concat(v.sku || ' ' || COALESCE(v.barcode, ''), ' ')
+                          FROM product_variants v WHERE v.product_id = p.id), ''))
+    FROM products p WHERE p.id = new.id;
+END;
+
+CREATE TRIGGER products_fts_delete AFTER DELETE ON products BEGIN
+    DELETE FROM products_fts WHERE rowid = old.id;
+END;
+
+CREATE TRIGGER product_barcodes_fts_insert AFTER INSERT ON product_barcodes BEGIN
+    DELETE FROM products_fts WHERE rowid = new.product_id;
+    INSERT INTO products_fts (rowid, name, description, sku, codes)
+    SELECT p.id, p.name, COALESCE(p.description, ''), p.sku,
+           TRIM(COALESCE(p.barcode, '') || ' ' ||
+                COALESCE((SELECT group_concat(b.code, ' ') FROM product_barcodes b WHERE b.product_id = p.id), '') || ' ' ||
+                COALESCE((SELECT group_concat(v.sku || ' ' || COALESCE(v.barcode, ''), ' ')
+                          FROM product_variants v WHERE v.product_id = p.id), ''))
+    FROM products p WHERE p.id = new.product_id;
+END;
+
+CREATE TRIGGER product_barcodes_fts_update AFTER UPDATE ON product_barcodes BEGIN
+    DELETE FROM products_fts WHERE rowid = old.product_id;
+    INSERT INTO products_fts (rowid, name, description, sku, codes)
+    SELECT p.id, p.name, COALESCE(p.description, ''), p.sku,
+           TRIM(COALESCE(p.barcode, '') || ' ' ||
+                COALESCE((SELECT group_concat(b.code, ' ') FROM product_barcodes b WHERE b.product_id = p.id), '') || ' ' ||
+                COALESCE((SELECT group_concat(v.sku || ' ' || COALESCE(v.barcode, ''), ' ')
+                          FROM product_variants v WHERE v.product_id = p.id), ''))
+    FROM products p WHERE p.id = old.product_id;
+    DELETE FROM products_fts WHERE rowid = new.product_id;
+    INSERT INTO products_fts (rowid, name, description, sku, codes)
+    SELECT p.id, p.name, COALESCE(p.description, ''), p.sku,
+           TRIM(COALESCE(p.barcode, '') || ' ' ||
+                COALESCE((SELECT group_concat(b.code, ' ') FROM product_barcodes b WHERE b.product_id = p.id), '') || ' ' ||
+                COALESCE((SELECT group_concat(v.sku || ' ' || COALESCE(v.barcode, ''), ' ')
+                          FROM product_variants v WHERE v.product_id = p.id), ''))
+    FROM products p WHERE p.id = new.product_id;
+END;
+
+CREATE TRIGGER product_barcodes_fts_delete AFTER DELETE ON product_barcodes BEGIN
+    DELETE FROM products_fts WHERE rowid = old.product_id;
+    INSERT INTO products_fts (rowid, name, description, sku, codes)
+    SELECT p.id, p.name, COALESCE(p.description, ''), p.sku,
+           TRIM(COALESCE(p.barcode, '') || ' ' ||
+                COALESCE((SELECT group_concat(b.code, ' ') FROM product_barcodes b WHERE b.product_id = p.id), '') || ' ' ||
+                COALESCE((SELECT group_concat(v.sku || ' ' || COALESCE(v.barcode, ''), ' ')
+                          FROM product_variants v WHERE v.product_id = p.id), ''))
+    FROM products p WHERE p.id = old.product_id;
+END;
+
+CREATE TRIGGER product_variants_fts_insert AFTER INSERT ON product_variants BEGIN
+    DELETE FROM products_fts WHERE rowid = new.product_id;
+    INSERT INTO products_fts (rowid, name, description, sku, codes)
+    SELECT p.id, p.name, COALESCE(p.description, ''), p.sku,
+           TRIM(COALESCE(p.barcode, '') || ' ' ||
+                COALESCE((SELECT group_concat(b.code, ' ') FROM product_barcodes b WHERE b.product_id = p.id), '') || ' ' ||
+                COALESCE((SELECT group_concat(v.sku || ' ' || COALESCE(v.barcode, ''), ' ')
+                          FROM product_variants v WHERE v.product_id = p.id), ''))
+    FROM products p WHERE p.id = new.product_id;
+END;
+
+CREATE TRIGGER product_variants_fts_update AFTER UPDATE OF product_id, sku, barcode ON product_variants BEGIN
+    DELETE FROM products_fts WHERE rowid = old.product_id;
+    INSERT INTO products_fts (rowid, name, description, sku, codes)
+    SELECT p.id, p.name, COALESCE(p.description, ''), p.sku,
+           TRIM(COALESCE(p.barcode, '') || ' ' ||
+                COALESCE((SELECT group_concat(b.code, ' ') FROM product_barcodes b WHERE b.product_id = p.id), '') || ' ' ||
+                COALESCE((SELECT group_concat(v.sku || ' ' || COALESCE(v.barcode, ''), ' ')
+                          FROM product_variants v WHERE v.product_id = p.id), ''))
+    FROM products p WHERE p.id = old.product_id;
+    DELETE FROM products_fts WHERE rowid = new.product_id;
+    INSERT INTO products_fts (rowid, name, description, sku, codes)
+    SELECT p.id, p.name, COALESCE(p.description, ''), p.sku,
+           TRIM(COALESCE(p.barcode, '') || ' ' ||
+                COALESCE((SELECT group_concat(b.code, ' ') FROM product_barcodes b WHERE b.product_id = p.id), '') || ' ' ||
+                COALESCE((SELECT group_concat(v.sku || ' ' || COALESCE(v.barcode, ''), ' ')
+                          FROM product_variants v WHERE v.product_id = p.id), ''))
+    FROM products p WHERE p.id = new.product_id;
+END;
+
+CREATE TRIGGER product_variants_fts_delete AFTER DELETE ON product_variants BEGIN
+    DELETE FROM products_fts WHERE rowid = old.product_id;
+    INSERT INTO products_fts (rowid, name, description, sku, codes)
+    SELECT p.id, p.name, COALESCE(p.description, ''), p.sku,
+           TRIM(COALESCE(p.barcode, '') || ' ' ||
+                COALESCE((SELECT group_concat(b.code, ' ') FROM product_barcodes b WHERE b.product_id = p.id), '') || ' ' ||
+                COALESCE((SELECT group_concat(v.sku || ' ' || COALESCE(v.barcode, ''), ' ')
+                          FROM product_variants v WHERE v.product_id = p.id), ''))
+    FROM products p WHERE p.id = old.product_id;
+END;
+
+INSERT INTO products_fts (rowid, name, description, sku, codes)
+    SELECT p.id, p.name, COALESCE(p.description, ''), p.sku,
+           TRIM(COALESCE(p.barcode, '') || ' ' ||
+                COALESCE((SELECT group_concat(b.code, ' ') FROM product_barcodes b WHERE b.product_id = p.id), '') || ' ' ||
+                COALESCE((SELECT group_concat(v.sku || ' ' || COALESCE(v.barcode, ''), ' ')
+                          FROM product_variants v WHERE v.product_id = p.id), ''))
+    FROM products p;
@@ -0,0 +1,2 @@
+-- Alerts for a variant's own stock; NULL for product-level alerts
+ALTER TABLE notifications ADD COLUMN variant_id INTEGER REFERENCES product_variants(id) ON DELETE CASCADE;
@@ -0,0 +1,42 @@
+use serde::{Deserialize, Serialize};
+
+// What was done to the entity
+pub const CREATE: &str = "create";
+pub const UPDATE: &str = "update";
+pub const DELETE: &str = "delete";
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub id: i64,
+    // Who did it; empty when the command was called without a session
+    pub user_id: Option<i32>,
+    pub username: Option<String>,
+    pub action: String,
+    pub entity_type: String,
+    pub entity_id: Option<i64>,
+    // Rows as they were before and after, with password and badge hashes left out
+    pub before: Option<serde_json::Value>,
+    pub after: Option<serde_json::Value>,
+    pub created_at: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct AuditLogFilter {
+    pub user_id: Option<i32>,
+    pub action: Option<String>,
+    pub entity_type: Option<String>,
+    pub entity_id: Option<i64>,
+    pub start_date: Option<String>,
+    pub end_date: Option<String>,
+    // 1-based
+    pub page: Option<i64>,
+    pub page_size: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AuditLogPage {
+    pub entries: Vec<AuditEntry>,
+    pub total: i64,
+    pub page: i64,
+    pub page_size: i64,
+}
@@ -0,0 +1,43 @@
+use serde::{Deserialize, Serialize};
+use super::product::ProductWithCategory;
+use super::variant::ProductVariant;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProductBarcode {
+    pub id: i32,
+    pub product_id: i32,
+    pub variant_id: Option<i32>,
+    pub code: String,
+    pub label: Option<String>,
+    pub created_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NewProductBarcode {
+    pub product_id: i32,
+    pub variant_id: Option<i32>,
+    pub code: String,
+    pub label: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CodeMatch {
+    pub code: String,
+    // barcode, alternate, variant_barcode, sku or variant_sku
+    pub matched_on: String,
+    pub product: ProductWithCategory,
+    pub variant: Option<ProductVariant>,
+}
+
+// A duplicate code found while adding the unique barcode indexes
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BarcodeConflict {
+    pub id: i32,
+    pub code: String,
+    pub source_table: String,
+    pub row_id: i32,
+    pub kept_row_id: Option<i32>,
+    // "cleared" when the duplicate was removed, "conflict" when it needs a manual fix
+    pub resolution: String,
+    pub created_at: String,
+}
@@ -1,5 +1,13 @@
 use serde::{Deserialize, Serialize};
 
+// What delete_category does with the products still in the category
+pub const REFUSE: &str = "refuse";
+pub const MOVE: &str = "move";
+pub const ARCHIVE: &str = "archive";
+
+// The category old versions of delete_category filed sold products under
+pub const UNCATEGORIZED: &str = "Uncategorized";
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Category {
     pub id: i32,
@@ -8,6 +16,10 @@ pub struct Category {
     pub created_at: String,
     pub updated_at: String,
     pub icon: Option<String>,
+    // Set while the category is archived, along with the products archived with it
+    pub archived_at: Option<String>,
+    // Empty for top-level categories
+    pub parent_id: Option<i32>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -15,6 +27,8 @@ pub struct NewCategory {
     pub name: String,
     pub description: Option<String>,
     pub icon: Option<String>,
+    #[serde(default)]
+    pub parent_id: Option<i32>,
 } 
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -23,4 +37,49 @@ pub struct UpdateCategory {
     pub name: String,
     pub description: Option<String>,
     pub icon: Option<String>,
-} 
\ No newline at end of file
+}
+
+/// A product a category deletion or repair touches
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AffectedProduct {
+    pub id: i32,
+    pub name: String,
+    pub sku: String,
+    pub archived: bool,
+    // Sold products are kept for reports, so they can be moved or archived but not deleted
+    pub sold: bool,
+}
+
+/// What deleting a category would touch, to show before picking a strategy
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CategoryDeletionPreview {
+    pub category: Category,
+    pub products: Vec<AffectedProduct>,
+    pub sold_products: usize,
+    // Category-wide promotions, deleted with the category
+    pub promotions: i64,
+    // Direct subcategories; they move up to the deleted category's parent
+    pub subcategories: Vec<Category>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CategoryRepair {
+    pub target_category_id: i32,
+    // Products moved out of the Uncategorized category
+    pub moved_products: Vec<AffectedProduct>,
+    // Products whose category no longer exists
+    pub orphaned_products: Vec<AffectedProduct>,
+    pub removed_uncategorized: bool,
+}
+
+/// A category in the category tree, with the categories nested under it
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CategoryNode {
+    #[serde(flatten)]
+    pub category: Category,
+    // Listed products filed directly under this category
+    pub product_count: i64,
+    // Listed products in this category and every category below it
+    pub total_product_count: i64,
+    pub children: Vec<CategoryNode>,
+}
@@ -0,0 +1,88 @@
+use serde::{Deserialize, Serialize};
+
+// Entries on a customer's account
+pub const CHARGE: &str = "charge";
+pub const PAYMENT: &str = "payment";
+
+/// Someone who can run a tab. A positive balance is owed to the store, a negative one is
+/// store credit.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Customer {
+    pub id: i32,
+    pub name: String,
+    // School or employee ID the tab is kept under
+    pub id_number: Option<String>,
+    pub phone: Option<String>,
+    pub email: Option<String>,
+    pub credit_limit: f64,
+    pub balance: f64,
+    // RFID/ID card linked to the customer's prepaid wallet
+    pub card_code: Option<String>,
+    pub wallet_balance: f64,
+    pub active: bool,
+    pub notes: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NewCustomer {
+    pub name: String,
+    pub id_number: Option<String>,
+    pub phone: Option<String>,
+    pub email: Option<String>,
+    #[serde(default)]
+    pub credit_limit: f64,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateCustomer {
+    pub id: i32,
+    pub name: String,
+    pub id_number: Option<String>,
+    pub phone: Option<String>,
+    pub email: Option<String>,
+    pub credit_limit: f64,
+    pub active: bool,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AccountEntry {
+    pub id: i32,
+    pub customer_id: i32,
+    pub kind: String,
+    // Charges come from an order
+    pub order_id: Option<i32>,
+    pub order_number: Option<String>,
+    // Positive for charges, negative for payments
+    pub amount: f64,
+    pub method: Option<String>,
+    pub reference: Option<String>,
+    pub notes: Option<String>,
+    pub balance_after: f64,
+    pub created_at: String,
+}
+
+/// Money received against a customer's tab
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NewAccountPayment {
+    pub customer_id: i32,
+    pub amount: f64,
+    pub method: String,
+    pub reference: Option<String>,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CustomerStatement {
+    pub customer: Customer,
+    pub start_date: String,
+    pub end_date: String,
+    pub opening_balance: f64,
+    pub charges: f64,
+    pub payments: f64,
+    pub closing_balance: f64,
+    pub entries: Vec<AccountEntry>,
+}
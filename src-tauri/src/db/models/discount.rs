@@ -0,0 +1,139 @@
+use serde::{Deserialize, Serialize};
+
+// Discounts keyed in by the cashier; staff and senior-citizen discounts are percentages
+// that also need a reason and the ID they were granted against
+pub const PERCENT: &str = "percent";
+pub const FIXED: &str = "fixed";
+pub const STAFF: &str = "staff";
+pub const SENIOR: &str = "senior";
+
+// Scheduled promotions applied automatically when an order is priced
+pub const BUY_X_GET_Y: &str = "buy_x_get_y";
+pub const HAPPY_HOUR: &str = "happy_hour";
+pub const CATEGORY: &str = "category";
+
+// Where a recorded discount came from
+pub const SOURCE_PROMOTION: &str = "promotion";
+pub const SOURCE_LINE: &str = "line";
+pub const SOURCE_ORDER: &str = "order";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiscountRequest {
+    pub kind: String,
+    // Percentage for percent/staff/senior, amount for fixed
+    pub value: f64,
+    pub reason: Option<String>,
+    pub id_number: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Promotion {
+    pub id: i32,
+    pub name: String,
+    pub kind: String,
+    // Percentage taken off; 100 makes the "get" items of a buy-X-get-Y free
+    pub percent: f64,
+    // Limits the promotion to a product or a category; neither means everything
+    pub product_id: Option<i32>,
+    pub category_id: Option<i32>,
+    pub buy_quantity: Option<f64>,
+    pub get_quantity: Option<f64>,
+    // Daily window as HH:MM, may wrap past midnight
+    pub start_time: Option<String>,
+    pub end_time: Option<String>,
+    // Dates as YYYY-MM-DD, inclusive
+    pub starts_on: Option<String>,
+    pub ends_on: Option<String>,
+    pub active: bool,
+    pub created_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NewPromotion {
+    pub name: String,
+    pub kind: String,
+    pub percent: f64,
+    pub product_id: Option<i32>,
+    pub category_id: Option<i32>,
+    pub buy_quantity: Option<f64>,
+    pub get_quantity: Option<f64>,
+    pub start_time: Option<String>,
+    pub end_time: Option<String>,
+    pub starts_on: Option<String>,
+    pub ends_on: Option<String>,
+    #[serde(default = "default_active")]
+    pub active: bool,
+}
+
+fn default_active() -> bool {
+    true
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdatePromotion {
+    pub id: i32,
+    #[serde(flatten)]
+    pub promotion: NewPromotion,
+}
+
+/// A discount worked out while pricing an order, before it is saved
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppliedDiscount {
+    // Index of the order line; an order discount is split into one entry per line
+    pub line: usize,
+    pub source: String,
+    pub kind: String,
+    pub promotion_id: Option<i32>,
+    pub description: String,
+    pub amount: f64,
+    pub reason: Option<String>,
+    pub id_number: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OrderDiscount {
+    pub id: i32,
+    pub order_id: i32,
+    pub order_item_id: Option<i32>,
+    pub source: String,
+    pub kind: String,
+    pub promotion_id: Option<i32>,
+    pub description: String,
+    pub amount: f64,
+    pub reason: Option<String>,
+    pub id_number: Option<String>,
+    pub created_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PricedOrderLine {
+    pub product_id: i32,
+    pub variant_id: Option<i32>,
+    pub product_name: String,
+    pub variant_name: Option<String>,
+    pub quantity: f64,
+    pub unit_price: f64,
+    pub discount: f64,
+    // quantity * unit_price - discount
+    pub total: f64,
+}
+
+/// What an order comes to once the backend has priced and discounted it
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PricedOrder {
+    pub lines: Vec<PricedOrderLine>,
+    pub discounts: Vec<AppliedDiscount>,
+    pub subtotal: f64,
+    pub discount: f64,
+    pub tax: f64,
+    pub total: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DiscountSales {
+    pub source: String,
+    pub kind: String,
+    pub description: String,
+    pub orders: i64,
+    pub amount: f64,
+}
@@ -0,0 +1,56 @@
+use serde::{Deserialize, Serialize};
+
+use super::discount::{DiscountRequest, PricedOrder};
+use super::order::NewOrderItem;
+
+pub const HELD: &str = "held";
+pub const FINALIZED: &str = "finalized";
+pub const EXPIRED: &str = "expired";
+pub const CANCELLED: &str = "cancelled";
+
+// How long a parked cart is kept when the register doesn't say
+pub const DEFAULT_HOLD_MINUTES: i64 = 240;
+
+/// A cart set aside at the register. Nothing is taken out of stock until it is finalized.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HeldOrder {
+    pub id: i32,
+    pub cashier: String,
+    // Customer name or note to find the cart again
+    pub label: Option<String>,
+    pub customer_id: Option<i32>,
+    pub items: Vec<NewOrderItem>,
+    pub discount: Option<DiscountRequest>,
+    pub subtotal: f64,
+    pub tax: f64,
+    pub total: f64,
+    pub status: String,
+    // Set once the cart has become an order, which is when it gets its order number
+    pub order_id: Option<i32>,
+    pub created_at: String,
+    pub updated_at: String,
+    pub expires_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HoldOrderRequest {
+    pub cashier: String,
+    pub label: Option<String>,
+    #[serde(default)]
+    pub customer_id: Option<i32>,
+    pub items: Vec<NewOrderItem>,
+    #[serde(default)]
+    pub discount: Option<DiscountRequest>,
+    pub subtotal: f64,
+    pub tax: f64,
+    pub total: f64,
+    #[serde(default)]
+    pub expires_in_minutes: Option<i64>,
+}
+
+/// A held cart brought back to the register, priced as it would be sold now
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ResumedOrder {
+    pub held: HeldOrder,
+    pub priced: PricedOrder,
+}
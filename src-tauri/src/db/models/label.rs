@@ -0,0 +1,62 @@
+use serde::{Deserialize, Serialize};
+
+// Grid of labels on a sheet, in millimetres. Defaults to a 3 x 8 sheet of 70 x 37 mm labels on A4.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LabelTemplate {
+    pub page_width_mm: f64,
+    pub page_height_mm: f64,
+    pub columns: u32,
+    pub rows: u32,
+    pub label_width_mm: f64,
+    pub label_height_mm: f64,
+    pub margin_left_mm: f64,
+    pub margin_top_mm: f64,
+    pub gap_x_mm: f64,
+    pub gap_y_mm: f64,
+}
+
+impl Default for LabelTemplate {
+    fn default() -> Self {
+        LabelTemplate {
+            page_width_mm: 210.0,
+            page_height_mm: 297.0,
+            columns: 3,
+            rows: 8,
+            label_width_mm: 70.0,
+            label_height_mm: 37.0,
+            margin_left_mm: 0.0,
+            margin_top_mm: 0.5,
+            gap_x_mm: 0.0,
+            gap_y_mm: 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LabelRequest {
+    pub product_ids: Vec<i32>,
+    // "pdf" or "svg"; SVG writes one file per sheet
+    pub format: String,
+    pub path: String,
+    // "ean13", "code128", or none to use EAN-13 where the barcode allows it
+    pub symbology: Option<String>,
+    pub template: Option<LabelTemplate>,
+    pub copies: Option<u32>,
+    // Give products without a barcode an internal EAN-13 (defaults to true)
+    pub assign_missing: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AssignedBarcode {
+    pub product_id: i32,
+    pub barcode: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LabelSheet {
+    pub files: Vec<String>,
+    pub labels: usize,
+    pub pages: usize,
+    pub assigned: Vec<AssignedBarcode>,
+}
@@ -2,6 +2,23 @@ pub mod category;
 pub mod product;
 pub mod user;
 pub mod order;
+pub mod notification;
+pub mod variant;
+pub mod recipe;
+pub mod unit;
+pub mod barcode;
+pub mod label;
+pub mod receipt;
+pub mod payment;
+pub mod shift;
+pub mod discount;
+pub mod held_order;
+pub mod customer;
+pub mod wallet;
+pub mod security;
+pub mod role;
+pub mod audit;
+pub mod price;
 
 // Re-export the key user types
 pub use user::User; 
@@ -0,0 +1,31 @@
+use serde::{Deserialize, Serialize};
+
+pub const LOW_STOCK: &str = "low_stock";
+pub const OUT_OF_STOCK: &str = "out_of_stock";
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Notification {
+    pub id: i32,
+    pub product_id: i32,
+    // Set when the alert is for one of the product's variants
+    #[serde(default)]
+    pub variant_id: Option<i32>,
+    pub product_name: String,
+    pub kind: String,
+    pub message: String,
+    pub stock_level: f64,
+    pub minimum_stock: f64,
+    pub created_at: String,
+    pub resolved_at: Option<String>,
+    // Per-user state, filled in for the requesting user
+    pub is_read: bool,
+    pub is_dismissed: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NotificationQuery {
+    pub include_dismissed: Option<bool>,
+    pub include_resolved: Option<bool>,
+    pub unread_only: Option<bool>,
+    pub limit: Option<i32>,
+}
@@ -1,5 +1,9 @@
 use serde::{Deserialize, Serialize};
 
+use super::discount::{DiscountRequest, OrderDiscount};
+use super::payment::OrderPayment;
+use super::variant::OrderItemModifier;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Order {
     pub id: i32,
@@ -10,6 +14,11 @@ pub struct Order {
     pub total: f64,
     pub status: String,
     pub created_at: String,
+    // Total of every discount given on the order, already taken off the subtotal
+    #[serde(default)]
+    pub discount: f64,
+    #[serde(default)]
+    pub customer_id: Option<i32>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -17,22 +26,32 @@ pub struct OrderItem {
     pub id: i32,
     pub order_id: i32,
     pub product_id: Option<i32>,
-    pub quantity: i32,
+    pub quantity: f64,
     pub price: f64,
     pub created_at: String,
     // This field can come either from a join or directly from the database
     pub product_name: Option<String>,
+    pub variant_id: Option<i32>,
+    pub variant_name: Option<String>,
+    #[serde(default)]
+    pub modifiers: Vec<OrderItemModifier>,
+    // Amount taken off the line, including its share of any order discount
+    #[serde(default)]
+    pub discount: f64,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct OrderWithItems {
     pub order: Order,
     pub items: Vec<OrderItem>,
+    #[serde(default)]
+    pub payments: Vec<OrderPayment>,
+    #[serde(default)]
+    pub discounts: Vec<OrderDiscount>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct NewOrder {
-    pub order_id: String,
     pub cashier: String,
     pub subtotal: f64,
     pub tax: f64,
@@ -40,11 +59,18 @@ pub struct NewOrder {
     pub status: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NewOrderItem {
     pub product_id: i32,
-    pub quantity: i32,
+    pub quantity: f64,
     pub price: f64,
+    // When a variant or modifiers are given the backend prices the line itself
+    #[serde(default)]
+    pub variant_id: Option<i32>,
+    #[serde(default)]
+    pub modifier_ids: Vec<i32>,
+    #[serde(default)]
+    pub discount: Option<DiscountRequest>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -57,5 +83,5 @@ pub struct DetailedSale {
     pub profit: f64,
     pub margin: String,
     pub revenue: f64,
-    pub quantity: i32,
+    pub quantity: f64,
 } 
\ No newline at end of file
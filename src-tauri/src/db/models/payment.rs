@@ -0,0 +1,41 @@
+use serde::{Deserialize, Serialize};
+
+pub const CASH: &str = "cash";
+pub const GCASH: &str = "gcash";
+pub const CARD: &str = "card";
+// Charged to the tab of the customer on the order
+pub const ACCOUNT: &str = "account";
+// Taken from the prepaid balance of the scanned card; the card code is the reference
+pub const WALLET: &str = "wallet";
+
+// Methods create_order accepts
+pub const PAYMENT_METHODS: [&str; 5] = [CASH, GCASH, CARD, ACCOUNT, WALLET];
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OrderPayment {
+    pub id: i32,
+    pub order_id: i32,
+    pub method: String,
+    // Portion of the order total settled by this payment
+    pub amount: f64,
+    pub reference: Option<String>,
+    // Cash handed over and the change given back; only cash payments have these
+    pub tendered: Option<f64>,
+    pub change: f64,
+    pub created_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NewPayment {
+    pub method: String,
+    pub amount: f64,
+    pub reference: Option<String>,
+    pub tendered: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PaymentMethodSales {
+    pub method: String,
+    pub transactions: i64,
+    pub amount: f64,
+}
@@ -0,0 +1,61 @@
+use serde::{Deserialize, Serialize};
+
+pub const PENDING: &str = "pending";
+pub const APPLIED: &str = "applied";
+pub const CANCELLED: &str = "cancelled";
+
+/// One change to a product's retail price and/or cost
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PriceChange {
+    pub id: i64,
+    pub product_id: i32,
+    pub old_unit_price: f64,
+    pub new_unit_price: f64,
+    pub old_price_bought: f64,
+    pub new_price_bought: f64,
+    // Empty for changes nobody was signed in for, e.g. scheduled ones taking effect
+    pub user_id: Option<i32>,
+    pub username: Option<String>,
+    pub reason: Option<String>,
+    // Set when the change came from a schedule
+    pub scheduled_change_id: Option<i64>,
+    pub changed_at: String,
+}
+
+/// A price change set to take effect later. Either price can be left alone.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ScheduledPriceChange {
+    pub id: i64,
+    pub product_id: i32,
+    pub product_name: String,
+    pub unit_price: Option<f64>,
+    pub price_bought: Option<f64>,
+    pub effective_at: String,
+    pub reason: Option<String>,
+    pub status: String,
+    pub created_by: Option<String>,
+    pub created_at: String,
+    pub applied_at: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NewScheduledPriceChange {
+    pub product_id: i32,
+    pub unit_price: Option<f64>,
+    pub price_bought: Option<f64>,
+    // YYYY-MM-DD HH:MM:SS, or YYYY-MM-DD for the start of that day
+    pub effective_at: String,
+    pub reason: Option<String>,
+}
+
+/// A product's prices now, how they got there and what is coming up
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PriceTimeline {
+    pub product_id: i32,
+    pub unit_price: f64,
+    pub price_bought: f64,
+    // Newest first
+    pub history: Vec<PriceChange>,
+    // Pending changes, soonest first
+    pub scheduled: Vec<ScheduledPriceChange>,
+}
@@ -9,14 +9,16 @@ pub struct Product {
     pub category_id: i32,
     pub unit_price: f64,
     pub price_bought: f64,
-    pub current_stock: i32,
-    pub minimum_stock: i32,
+    pub current_stock: f64,
+    pub minimum_stock: f64,
     pub supplier: Option<String>,
     pub created_at: String,
     pub updated_at: String,
     #[serde(rename = "thumbnailUrl")]
     pub thumbnail_url: Option<String>,
     pub barcode: Option<String>,
+    // Set while the product is archived: hidden from the catalogue and the register
+    pub archived_at: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -29,14 +31,15 @@ pub struct ProductWithCategory {
     pub category_name: String,
     pub unit_price: f64,
     pub price_bought: f64,
-    pub current_stock: i32,
-    pub minimum_stock: i32,
+    pub current_stock: f64,
+    pub minimum_stock: f64,
     pub supplier: Option<String>,
     pub created_at: String,
     pub updated_at: String,
     #[serde(rename = "thumbnailUrl")]
     pub thumbnail_url: Option<String>,
     pub barcode: Option<String>,
+    pub archived_at: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -47,10 +50,53 @@ pub struct NewProduct {
     pub category_id: i32,
     pub unit_price: f64,
     pub price_bought: f64,
-    pub current_stock: i32,
-    pub minimum_stock: i32,
+    pub current_stock: f64,
+    pub minimum_stock: f64,
     pub supplier: Option<String>,
     #[serde(rename = "thumbnailUrl")]
     pub thumbnail_url: Option<String>,
     pub barcode: Option<String>,
 }
+
+// Stock statuses to filter a search by; out and low match when stock alerts are raised
+pub const STOCK_OUT: &str = "out";
+pub const STOCK_LOW: &str = "low";
+pub const STOCK_OK: &str = "ok";
+
+// What a search can be sorted by. Relevance only applies to text searches, and is their default.
+pub const SORT_RELEVANCE: &str = "relevance";
+pub const SORT_NAME: &str = "name";
+pub const SORT_SKU: &str = "sku";
+pub const SORT_PRICE: &str = "price";
+pub const SORT_STOCK: &str = "stock";
+pub const SORT_CREATED: &str = "created_at";
+pub const SORT_UPDATED: &str = "updated_at";
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ProductSearch {
+    // Matched against name, description, SKU and barcodes; each word may be the start of one
+    pub query: Option<String>,
+    pub category_id: Option<i32>,
+    // Whether products in the category's subcategories count; defaults to true
+    pub include_subcategories: Option<bool>,
+    pub supplier: Option<String>,
+    pub stock_status: Option<String>,
+    pub min_price: Option<f64>,
+    pub max_price: Option<f64>,
+    // Archived products only when true, otherwise listed ones only
+    pub archived: Option<bool>,
+    pub sort_by: Option<String>,
+    pub descending: Option<bool>,
+    pub limit: Option<i64>,
+    // next_cursor from the previous page
+    pub cursor: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProductSearchPage {
+    pub products: Vec<ProductWithCategory>,
+    // Products matching the search across all pages
+    pub total: i64,
+    // Empty on the last page
+    pub next_cursor: Option<String>,
+}
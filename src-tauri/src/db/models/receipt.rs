@@ -0,0 +1,48 @@
+use serde::{Deserialize, Serialize};
+
+// Where receipts go: "none" (PDF only), "file" for a device path such as /dev/usb/lp0
+// or a spool file, or "tcp" for a network printer at host[:port]
+pub const PRINTER_NONE: &str = "none";
+pub const PRINTER_FILE: &str = "file";
+pub const PRINTER_TCP: &str = "tcp";
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReceiptSettings {
+    pub store_name: String,
+    pub header: Option<String>,
+    pub footer: Option<String>,
+    // Characters per line: 32 for 58 mm paper, 42 or 48 for 80 mm
+    pub paper_width: i32,
+    pub printer_type: String,
+    pub printer_target: Option<String>,
+    // Receipts are saved here as PDF when there is no printer or it can't be reached
+    pub pdf_directory: Option<String>,
+    pub cut_paper: bool,
+    pub open_drawer: bool,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SaveReceiptSettings {
+    pub store_name: String,
+    pub header: Option<String>,
+    pub footer: Option<String>,
+    pub paper_width: Option<i32>,
+    pub printer_type: String,
+    pub printer_target: Option<String>,
+    pub pdf_directory: Option<String>,
+    pub cut_paper: Option<bool>,
+    pub open_drawer: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReceiptOutput {
+    pub order_id: i32,
+    pub reprint: bool,
+    // "escpos" when sent to the printer, "pdf" when saved as a file
+    pub format: String,
+    pub destination: String,
+    pub bytes: usize,
+    // Set when the printer failed and the receipt was saved as PDF instead
+    pub printer_error: Option<String>,
+}
@@ -0,0 +1,41 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecipeComponent {
+    pub id: i32,
+    pub product_id: i32,
+    pub component_id: i32,
+    pub component_name: String,
+    // Amount of the component consumed per unit sold, in `unit` or else the component's base unit
+    pub quantity: f64,
+    pub unit: Option<String>,
+    // The same amount in the component's base unit, which its stock and cost are kept in
+    pub base_quantity: f64,
+    pub unit_cost: f64,
+    pub line_cost: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Recipe {
+    pub product_id: i32,
+    pub product_name: String,
+    // Whether the finished item keeps its own stock count besides the ingredients
+    pub deduct_finished_item: bool,
+    pub components: Vec<RecipeComponent>,
+    pub total_cost: f64,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NewRecipeComponent {
+    pub component_id: i32,
+    pub quantity: f64,
+    pub unit: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SaveRecipe {
+    pub product_id: i32,
+    pub deduct_finished_item: Option<bool>,
+    pub components: Vec<NewRecipeComponent>,
+}
@@ -0,0 +1,70 @@
+use serde::{Deserialize, Serialize};
+
+// What a role can allow
+pub const VIEW_INVENTORY: &str = "view_inventory";
+pub const EDIT_INVENTORY: &str = "edit_inventory";
+pub const SELL: &str = "sell";
+pub const REFUND: &str = "refund";
+pub const VIEW_REPORTS: &str = "view_reports";
+pub const MANAGE_STAFF: &str = "manage_staff";
+pub const MANAGE_SETTINGS: &str = "manage_settings";
+
+pub const PERMISSIONS: [&str; 7] = [
+    VIEW_INVENTORY,
+    EDIT_INVENTORY,
+    SELL,
+    REFUND,
+    VIEW_REPORTS,
+    MANAGE_STAFF,
+    MANAGE_SETTINGS,
+];
+
+// Always has every permission and can't be edited or deleted
+pub const ADMIN_ROLE: &str = "admin";
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Role {
+    pub id: i32,
+    pub name: String,
+    pub description: Option<String>,
+    pub permissions: Vec<String>,
+    // Seeded roles can be edited but not deleted
+    pub built_in: bool,
+    // Users who have the role
+    pub users: i64,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NewRole {
+    pub name: String,
+    pub description: Option<String>,
+    pub permissions: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateRole {
+    pub id: i32,
+    #[serde(flatten)]
+    pub role: NewRole,
+}
+
+/// A permission granted to or taken from one user on top of their role
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PermissionOverride {
+    pub permission: String,
+    pub granted: bool,
+}
+
+/// A user's role and what they can actually do once their overrides are applied
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UserAccess {
+    pub user_id: i32,
+    pub username: String,
+    pub full_name: String,
+    pub role: String,
+    pub active: bool,
+    pub overrides: Vec<PermissionOverride>,
+    pub permissions: Vec<String>,
+}
@@ -0,0 +1,27 @@
+use serde::{Deserialize, Serialize};
+
+/// Password rules checked when a password is set, and how failed logins lock an account
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SecuritySettings {
+    pub min_password_length: i32,
+    pub require_letter: bool,
+    pub require_digit: bool,
+    // Upper and lower case letters
+    pub require_mixed_case: bool,
+    pub require_symbol: bool,
+    // Wrong passwords in a row before the account is locked
+    pub max_failed_logins: i32,
+    pub lockout_minutes: i32,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SaveSecuritySettings {
+    pub min_password_length: i32,
+    pub require_letter: bool,
+    pub require_digit: bool,
+    pub require_mixed_case: bool,
+    pub require_symbol: bool,
+    pub max_failed_logins: i32,
+    pub lockout_minutes: i32,
+}
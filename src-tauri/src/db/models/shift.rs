@@ -0,0 +1,68 @@
+use serde::{Deserialize, Serialize};
+use super::payment::PaymentMethodSales;
+
+pub const CASH_IN: &str = "cash_in";
+pub const CASH_OUT: &str = "cash_out";
+
+// X reports are taken mid-shift, the Z report is produced when the shift closes
+pub const X_REPORT: &str = "X";
+pub const Z_REPORT: &str = "Z";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Shift {
+    pub id: i32,
+    pub user_id: i32,
+    // Name orders are rung up under, matching orders.cashier
+    pub cashier: String,
+    pub opening_float: f64,
+    pub opened_at: String,
+    pub closed_at: Option<String>,
+    pub counted_cash: Option<f64>,
+    pub expected_cash: Option<f64>,
+    pub over_short: Option<f64>,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CashMovement {
+    pub id: i32,
+    pub shift_id: i32,
+    pub kind: String,
+    pub amount: f64,
+    pub reason: Option<String>,
+    pub created_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NewCashMovement {
+    pub kind: String,
+    pub amount: f64,
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OrderTotals {
+    pub count: i64,
+    pub amount: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ShiftReport {
+    pub kind: String,
+    pub generated_at: String,
+    pub shift: Shift,
+    pub orders: i64,
+    pub gross_sales: f64,
+    pub tax: f64,
+    pub net_sales: f64,
+    pub payments: Vec<PaymentMethodSales>,
+    pub refunds: OrderTotals,
+    pub voids: OrderTotals,
+    pub cash_sales: f64,
+    pub cash_in: f64,
+    pub cash_out: f64,
+    // Opening float + cash sales + cash in - cash out
+    pub expected_cash: f64,
+    pub counted_cash: Option<f64>,
+    pub over_short: Option<f64>,
+}
@@ -0,0 +1,60 @@
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_BASE_UNIT: &str = "pc";
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ProductUnits {
+    pub product_id: i32,
+    // Unit that current_stock, sale quantities and recipes are counted in
+    pub base_unit: String,
+    // Unit the product is bought in (e.g. a case), worth purchase_factor base units
+    pub purchase_unit: Option<String>,
+    pub purchase_factor: f64,
+    pub allow_fractional: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SetProductUnits {
+    pub product_id: i32,
+    pub base_unit: String,
+    pub purchase_unit: Option<String>,
+    pub purchase_factor: Option<f64>,
+    pub allow_fractional: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReceiveStock {
+    pub product_id: i32,
+    pub quantity: f64,
+    // True when quantity is given in the purchase unit, false for base units
+    pub in_purchase_unit: Option<bool>,
+    // Cost per unit the quantity is given in; updates the product's cost when set
+    pub unit_cost: Option<f64>,
+    pub reference: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StocktakeCount {
+    pub product_id: i32,
+    // Full purchase units counted (e.g. unopened cases) plus loose base units
+    pub purchase_quantity: Option<f64>,
+    pub base_quantity: Option<f64>,
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StockMovement {
+    pub id: i32,
+    pub product_id: i32,
+    pub product_name: String,
+    pub kind: String,
+    // Change in stock, in base units
+    pub quantity: f64,
+    // The quantity and unit as entered by the user
+    pub entered_quantity: f64,
+    pub entered_unit: String,
+    pub unit_cost: Option<f64>,
+    pub stock_after: f64,
+    pub reference: Option<String>,
+    pub created_at: String,
+}
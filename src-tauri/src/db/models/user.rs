@@ -36,4 +36,23 @@ pub struct NewUser {
 pub struct LoginCredentials {
     pub username: String,
     pub password: String,
-} 
\ No newline at end of file
+    // Register being signed in on; a cashier switched in there by badge is switched out
+    #[serde(default)]
+    pub terminal: Option<String>,
+} 
+#[derive(Debug, Deserialize)]
+pub struct BadgeCredentials {
+    pub badge_code: String,
+    // Only needed when a PIN was set along with the badge
+    pub pin: Option<String>,
+    #[serde(default)]
+    pub terminal: Option<String>,
+}
+
+/// A temporary password handed to the user after an admin reset
+#[derive(Debug, Serialize)]
+pub struct PasswordReset {
+    pub user_id: i32,
+    pub username: String,
+    pub temporary_password: String,
+}
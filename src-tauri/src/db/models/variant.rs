@@ -0,0 +1,85 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProductVariant {
+    pub id: i32,
+    pub product_id: i32,
+    pub name: String,
+    pub sku: String,
+    pub barcode: Option<String>,
+    pub unit_price: f64,
+    pub price_bought: f64,
+    pub current_stock: f64,
+    pub minimum_stock: f64,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NewProductVariant {
+    pub product_id: i32,
+    pub name: String,
+    pub sku: String,
+    pub barcode: Option<String>,
+    pub unit_price: f64,
+    pub price_bought: f64,
+    pub current_stock: f64,
+    pub minimum_stock: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateProductVariant {
+    pub id: i32,
+    pub name: String,
+    pub sku: String,
+    pub barcode: Option<String>,
+    pub unit_price: f64,
+    pub price_bought: f64,
+    pub current_stock: f64,
+    pub minimum_stock: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Modifier {
+    pub id: i32,
+    pub group_id: i32,
+    pub name: String,
+    pub price: f64,
+    pub cost: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ModifierGroup {
+    pub id: i32,
+    pub product_id: i32,
+    pub name: String,
+    // How many modifiers of this group a single line must / may carry
+    pub min_select: i32,
+    pub max_select: i32,
+    pub modifiers: Vec<Modifier>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NewModifierGroup {
+    pub product_id: i32,
+    pub name: String,
+    pub min_select: Option<i32>,
+    pub max_select: Option<i32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NewModifier {
+    pub group_id: i32,
+    pub name: String,
+    pub price: f64,
+    pub cost: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OrderItemModifier {
+    pub id: i32,
+    pub order_item_id: i32,
+    pub modifier_id: Option<i32>,
+    pub modifier_name: String,
+    pub price: f64,
+}
@@ -0,0 +1,34 @@
+use serde::{Deserialize, Serialize};
+
+// Entries on a customer's prepaid wallet
+pub const TOP_UP: &str = "top_up";
+pub const PURCHASE: &str = "purchase";
+pub const REFUND: &str = "refund";
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WalletEntry {
+    pub id: i32,
+    pub customer_id: i32,
+    pub kind: String,
+    // Purchases come from an order
+    pub order_id: Option<i32>,
+    pub order_number: Option<String>,
+    // Positive for top-ups, negative for purchases and refunds
+    pub amount: f64,
+    // How the money came in or went back out; purchases have none
+    pub method: Option<String>,
+    pub reference: Option<String>,
+    pub notes: Option<String>,
+    pub balance_after: f64,
+    pub created_at: String,
+}
+
+/// Money loaded onto a card, or paid back out of it, at the counter
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WalletFunds {
+    pub card_code: String,
+    pub amount: f64,
+    pub method: String,
+    pub reference: Option<String>,
+    pub notes: Option<String>,
+}
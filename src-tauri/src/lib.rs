@@ -9,6 +9,7 @@
 pub mod commands;
 pub mod db;
 pub mod auth;
+pub mod printing;
 
 #[tauri::command]
 fn greet(name: &str) -> String {
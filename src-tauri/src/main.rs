@@ -3,15 +3,52 @@
 mod auth;
 mod commands;
 mod db;
+mod printing;
 
-use rusqlite::Connection;
-use commands::auth::{login, register};
-use commands::category::{get_all_categories, add_category, delete_category, update_category};
-use commands::product::{get_all_products, get_products_by_category, add_product, update_product, delete_product, update_product_stock};
-use commands::transaction::{create_order, get_order_by_id, get_order_items, get_order_with_items, get_recent_orders, get_order_history, get_order_statistics, get_sales_report_data};
+use rusqlite::{Connection, OptionalExtension};
+use commands::auth::{
+    change_password, deactivate_user, login, login_with_badge, reactivate_user, register, reset_password, set_user_badge,
+    switch_cashier, logout, clear_terminal_cashier,
+};
+use commands::security::{get_security_settings, save_security_settings};
+use commands::audit::{export_audit_log, get_audit_log};
+use commands::role::{
+    add_role, delete_role, get_permissions, get_roles, get_user_access, get_users, set_permission_override,
+    set_user_role, update_role,
+};
+use commands::category::{
+    get_all_categories, add_category, delete_category, update_category, archive_category, restore_category,
+    get_archived_categories, preview_delete_category, repair_uncategorized, get_category_tree, move_category,
+};
+use commands::product::{
+    get_all_products, get_products_by_category, add_product, update_product, delete_product, update_product_stock,
+    archive_product, restore_product, get_archived_products, search_products,
+};
+use commands::price::{cancel_scheduled_price_change, get_price_history, schedule_price_change};
+use commands::transaction::{create_order, get_order_by_id, get_order_items, get_order_with_items, get_recent_orders, get_order_history, get_order_statistics, get_sales_report_data, preview_order, find_order_by_number};
 use commands::file::read_image_to_base64;
+use commands::variant::{get_product_variants, add_product_variant, update_product_variant, delete_product_variant, get_modifier_groups, add_modifier_group, delete_modifier_group, add_modifier, delete_modifier};
+use commands::recipe::{get_recipe, save_recipe, delete_recipe};
+use commands::unit::{get_product_units, set_product_units, receive_stock, record_stocktake, get_stock_movements};
+use commands::barcode::{find_product_by_code, get_product_barcodes, add_product_barcode, delete_product_barcode, get_barcode_conflicts};
+use commands::label::generate_labels;
+use commands::receipt::{get_receipt_settings, save_receipt_settings, print_receipt, reprint_receipt, save_receipt_pdf};
+use commands::payment::get_order_payments;
+use commands::discount::{add_promotion, delete_promotion, get_promotions, update_promotion};
+use commands::held_order::{
+    cancel_held_order, finalize_held_order, get_held_orders, hold_order, resume_held_order, update_held_order,
+};
+use commands::customer::{
+    add_customer, get_customer, get_customer_statement, get_customers, record_account_payment, update_customer,
+};
+use commands::wallet::{assign_wallet_card, get_wallet_by_card, get_wallet_history, refund_wallet, top_up_wallet};
+use commands::shift::{
+    add_cash_movement, close_shift, export_shift_report, get_cash_movements, get_current_shift, get_shift_report,
+    get_shifts, open_shift,
+};
+use commands::notification::{get_notifications, mark_notification_read, mark_all_notifications_read, dismiss_notification, refresh_stock_alerts};
 use crate::db::DbState;
-use bcrypt::{hash, DEFAULT_COST};
+use bcrypt::{hash, verify, DEFAULT_COST};
 use r2d2_sqlite::SqliteConnectionManager;
 
 // Function to initialize database with default admin user
@@ -101,6 +138,76 @@ fn initialize_database(conn: &Connection) -> Result<(), String> {
     
     // Add icon column to categories if needed
     add_icon_to_categories(conn)?;
+    
+    // Create the stock notification tables if needed
+    create_notification_tables(conn)?;
+    
+    // Create the variant and modifier tables if needed
+    create_variant_tables(conn)?;
+    
+    // Create the recipe tables if needed
+    create_recipe_tables(conn)?;
+    
+    // Create the unit of measure and stock movement tables if needed
+    create_unit_tables(conn)?;
+    
+    // Add the barcode column, alternate barcodes and the unique code indexes if needed
+    add_barcode_to_products(conn)?;
+    create_barcode_tables(conn)?;
+    
+    // Create the receipt settings and print log tables if needed
+    create_receipt_tables(conn)?;
+    
+    // Create the order payments table if needed
+    create_payment_tables(conn)?;
+    
+    // Create the cashier shift tables and link orders to shifts if needed
+    create_shift_tables(conn)?;
+    
+    // Create the promotion and order discount tables if needed
+    create_discount_tables(conn)?;
+    
+    // Create the held (parked) orders table if needed
+    create_held_order_tables(conn)?;
+    
+    // Create the order number sequences and make order numbers unique if needed
+    create_order_number_tables(conn)?;
+    
+    // Add the idempotency key column to orders if needed
+    add_idempotency_key_to_orders(conn)?;
+    
+    // Create the customer account tables and link orders to customers if needed
+    create_customer_tables(conn)?;
+    
+    // Create the prepaid wallet ledger and link customers to cards if needed
+    create_wallet_tables(conn)?;
+    
+    // Add badge login to users and track who is on each terminal if needed
+    create_badge_tables(conn)?;
+    
+    // Create the password policy settings and add login lockout columns to users if needed
+    create_security_tables(conn)?;
+    
+    // Add the active flag to users if needed
+    add_active_flag_to_users(conn)?;
+    
+    // Create the roles and permission tables and seed the built-in roles if needed
+    create_role_tables(conn)?;
+    
+    // Create the append-only audit log if needed
+    create_audit_tables(conn)?;
+    
+    // Create the price history and scheduled price change tables if needed
+    create_price_tables(conn)?;
+    
+    // Add the archived state to products and categories if needed
+    add_archived_at_to_catalogue(conn)?;
+    
+    // Let categories nest under a parent category if needed
+    add_parent_id_to_categories(conn)?;
+    
+    // Create the full-text product search index if needed
+    create_product_search_index(conn)?;
 
     // Check if admin user exists
     let mut stmt = conn.prepare("SELECT COUNT(*) FROM users WHERE username = 'admin'")
@@ -118,8 +225,8 @@ fn initialize_database(conn: &Connection) -> Result<(), String> {
             .map_err(|e| format!("Failed to hash password: {}", e))?;
         
         conn.execute(
-            "INSERT INTO users (username, email, password_hash, full_name, role, created_at, updated_at) 
-             VALUES (?, ?, ?, ?, ?, datetime('now'), datetime('now'))",
+            "INSERT INTO users (username, email, password_hash, full_name, role, must_change_password, created_at, updated_at) 
+             VALUES (?, ?, ?, ?, ?, 1, datetime('now'), datetime('now'))",
             rusqlite::params![
                 "admin",
                 "admin@example.com",
@@ -426,26 +533,1260 @@ fn add_icon_to_categories(conn: &Connection) -> Result<(), String> {
     Ok(())
 }
 
-// Function to clear all sample data for production builds
-fn clean_database_for_production(conn: &mut Connection) -> Result<(), String> {
-    println!("Cleaning database for production build...");
-    
-    // Start a transaction to ensure all operations complete or none do
-    let tx = conn.transaction()
-        .map_err(|e| format!("Failed to start transaction: {}", e))?;
-    
-    // Delete all sample data
-    tx.execute("DELETE FROM order_items", [])
-        .map_err(|e| format!("Failed to delete order items: {}", e))?;
-    
-    tx.execute("DELETE FROM orders", [])
-        .map_err(|e| format!("Failed to delete orders: {}", e))?;
-    
-    tx.execute("DELETE FROM products", [])
-        .map_err(|e| format!("Failed to delete products: {}", e))?;
+// Stock alerts are shared by all users, read/dismissed state is kept per user
+fn create_notification_tables(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS notifications (
+            id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+            product_id INTEGER NOT NULL,
+            product_name TEXT NOT NULL,
+            kind TEXT NOT NULL,
+            message TEXT NOT NULL,
+            stock_level INTEGER NOT NULL,
+            minimum_stock INTEGER NOT NULL,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            resolved_at TEXT,
+            FOREIGN KEY (product_id) REFERENCES products(id) ON DELETE CASCADE
+        )",
+        [],
+    ).map_err(|e| format!("Failed to create notifications table: {}", e))?;
+
+    // Variants keep their own stock, so their alerts name the variant too
+    let has_variant_id = conn.query_row(
+        "SELECT COUNT(*) FROM pragma_table_info('notifications') WHERE name = 'variant_id'",
+        [],
+        |row| row.get::<_, i64>(0)
+    ).map_err(|e| format!("Failed to check for notifications.variant_id column: {}", e))? > 0;
+
+    if !has_variant_id {
+        println!("Adding variant_id column to notifications table...");
+        conn.execute(
+            "ALTER TABLE notifications ADD COLUMN variant_id INTEGER REFERENCES product_variants(id) ON DELETE CASCADE",
+            [],
+        ).map_err(|e| format!("Failed to add variant_id column to notifications: {}", e))?;
+    }
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_notifications_open ON notifications (product_id, kind, resolved_at)",
+        [],
+    ).map_err(|e| format!("Failed to create notifications index: {}", e))?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS notification_states (
+            notification_id INTEGER NOT NULL,
+            user_id INTEGER NOT NULL,
+            read_at TEXT,
+            dismissed_at TEXT,
+            PRIMARY KEY (notification_id, user_id),
+            FOREIGN KEY (notification_id) REFERENCES notifications(id) ON DELETE CASCADE,
+            FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+        )",
+        [],
+    ).map_err(|e| format!("Failed to create notification_states table: {}", e))?;
+
+    Ok(())
+}
+
+// Variants carry their own price/cost/stock, modifiers are priced add-ons chosen per sale line
+fn create_variant_tables(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS product_variants (
+            id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+            product_id INTEGER NOT NULL,
+            name TEXT NOT NULL,
+            sku TEXT NOT NULL UNIQUE,
+            barcode TEXT,
+            unit_price REAL NOT NULL,
+            price_bought REAL NOT NULL DEFAULT 0,
+            current_stock INTEGER NOT NULL DEFAULT 0,
+            minimum_stock INTEGER NOT NULL DEFAULT 0,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            updated_at TEXT NOT NULL DEFAULT (datetime('now')),
+            FOREIGN KEY (product_id) REFERENCES products(id) ON DELETE CASCADE
+        )",
+        [],
+    ).map_err(|e| format!("Failed to create product_variants table: {}", e))?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS modifier_groups (
+            id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+            product_id INTEGER NOT NULL,
+            name TEXT NOT NULL,
+            min_select INTEGER NOT NULL DEFAULT 0,
+            max_select INTEGER NOT NULL DEFAULT 1,
+            FOREIGN KEY (product_id) REFERENCES products(id) ON DELETE CASCADE
+        )",
+        [],
+    ).map_err(|e| format!("Failed to create modifier_groups table: {}", e))?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS modifiers (
+            id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+            group_id INTEGER NOT NULL,
+            name TEXT NOT NULL,
+            price REAL NOT NULL DEFAULT 0,
+            cost REAL NOT NULL DEFAULT 0,
+            FOREIGN KEY (group_id) REFERENCES modifier_groups(id) ON DELETE CASCADE
+        )",
+        [],
+    ).map_err(|e| format!("Failed to create modifiers table: {}", e))?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS order_item_modifiers (
+            id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+            order_item_id INTEGER NOT NULL,
+            modifier_id INTEGER,
+            modifier_name TEXT NOT NULL,
+            price REAL NOT NULL,
+            FOREIGN KEY (order_item_id) REFERENCES order_items(id) ON DELETE CASCADE,
+            FOREIGN KEY (modifier_id) REFERENCES modifiers(id) ON DELETE SET NULL
+        )",
+        [],
+    ).map_err(|e| format!("Failed to create order_item_modifiers table: {}", e))?;
+
+    // Order lines remember the variant sold and the unit cost at the time of sale
+    let order_item_columns = [
+        ("variant_id", "ALTER TABLE order_items ADD COLUMN variant_id INTEGER REFERENCES product_variants(id) ON DELETE SET NULL"),
+        ("variant_name", "ALTER TABLE order_items ADD COLUMN variant_name TEXT"),
+        ("cost", "ALTER TABLE order_items ADD COLUMN cost REAL"),
+    ];
+
+    for (column, statement) in order_item_columns.iter() {
+        let exists = conn.query_row(
+            "SELECT COUNT(*) FROM pragma_table_info('order_items') WHERE name = ?1",
+            [column],
+            |row| row.get::<_, i64>(0)
+        ).map_err(|e| format!("Failed to check for {} column: {}", column, e))? > 0;
+
+        if !exists {
+            println!("Adding {} column to order_items table...", column);
+            conn.execute(statement, [])
+                .map_err(|e| format!("Failed to add {} column: {}", column, e))?;
+        }
+    }
+
+    Ok(())
+}
+
+// Recipes link a prepared item to the ingredient products it consumes when sold
+fn create_recipe_tables(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS recipes (
+            product_id INTEGER PRIMARY KEY NOT NULL,
+            deduct_finished_item INTEGER NOT NULL DEFAULT 0,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            updated_at TEXT NOT NULL DEFAULT (datetime('now')),
+            FOREIGN KEY (product_id) REFERENCES products(id) ON DELETE CASCADE
+        )",
+        [],
+    ).map_err(|e| format!("Failed to create recipes table: {}", e))?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS recipe_components (
+            id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+            product_id INTEGER NOT NULL,
+            component_id INTEGER NOT NULL,
+            quantity INTEGER NOT NULL,
+            unit TEXT,
+            UNIQUE (product_id, component_id),
+            FOREIGN KEY (product_id) REFERENCES recipes(product_id) ON DELETE CASCADE,
+            FOREIGN KEY (component_id) REFERENCES products(id) ON DELETE RESTRICT
+        )",
+        [],
+    ).map_err(|e| format!("Failed to create recipe_components table: {}", e))?;
+
+    Ok(())
+}
+
+// Units of measure per product, and a log of stock received or counted.
+// Stock and quantity columns keep their INTEGER affinity; SQLite stores fractional
+// values (e.g. 1.5 kg) as REAL in them, so no table rebuild is needed.
+fn create_unit_tables(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS product_units (
+            product_id INTEGER PRIMARY KEY NOT NULL,
+            base_unit TEXT NOT NULL DEFAULT 'pc',
+            purchase_unit TEXT,
+            purchase_factor REAL NOT NULL DEFAULT 1,
+            allow_fractional INTEGER NOT NULL DEFAULT 0,
+            updated_at TEXT NOT NULL DEFAULT (datetime('now')),
+            FOREIGN KEY (product_id) REFERENCES products(id) ON DELETE CASCADE
+        )",
+        [],
+    ).map_err(|e| format!("Failed to create product_units table: {}", e))?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS stock_movements (
+            id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+            product_id INTEGER NOT NULL,
+            kind TEXT NOT NULL,
+            quantity REAL NOT NULL,
+            entered_quantity REAL NOT NULL,
+            entered_unit TEXT NOT NULL,
+            unit_cost REAL,
+            stock_after REAL NOT NULL,
+            reference TEXT,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            FOREIGN KEY (product_id) REFERENCES products(id) ON DELETE CASCADE
+        )",
+        [],
+    ).map_err(|e| format!("Failed to create stock_movements table: {}", e))?;
+
+    Ok(())
+}
+
+// Add the barcode column to the products table
+fn add_barcode_to_products(conn: &Connection) -> Result<(), String> {
+    let result = conn.query_row(
+        "SELECT COUNT(*) FROM pragma_table_info('products') WHERE name='barcode'",
+        [],
+        |row| row.get::<_, i64>(0)
+    );
+
+    if let Ok(0) = result {
+        println!("Adding barcode column to products table...");
+        
+        conn.execute(
+            "ALTER TABLE products ADD COLUMN barcode TEXT",
+            [],
+        ).map_err(|e| format!("Failed to add barcode column: {}", e))?;
+        
+        println!("Successfully added barcode column to products table!");
+    } else if let Err(e) = result {
+        return Err(format!("Failed to check for barcode column: {}", e));
+    }
     
-    tx.execute("DELETE FROM categories", [])
-        .map_err(|e| format!("Failed to delete categories: {}", e))?;
+    Ok(())
+}
+
+// Alternate barcodes per product or variant, and unique indexes on every barcode column.
+// Duplicates in existing data are cleared (keeping the oldest row) and written to
+// barcode_conflicts before the indexes are created, so they can be reviewed later.
+fn create_barcode_tables(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS product_barcodes (
+            id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+            product_id INTEGER NOT NULL,
+            variant_id INTEGER,
+            code TEXT NOT NULL UNIQUE,
+            label TEXT,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            FOREIGN KEY (product_id) REFERENCES products(id) ON DELETE CASCADE,
+            FOREIGN KEY (variant_id) REFERENCES product_variants(id) ON DELETE CASCADE
+        )",
+        [],
+    ).map_err(|e| format!("Failed to create product_barcodes table: {}", e))?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS barcode_conflicts (
+            id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+            code TEXT NOT NULL,
+            source_table TEXT NOT NULL,
+            row_id INTEGER NOT NULL,
+            kept_row_id INTEGER,
+            resolution TEXT NOT NULL,
+            created_at TEXT NOT NULL DEFAULT (datetime('now'))
+        )",
+        [],
+    ).map_err(|e| format!("Failed to create barcode_conflicts table: {}", e))?;
+
+    let indexed: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'index' AND name = 'idx_products_barcode'",
+        [],
+        |row| row.get(0),
+    ).map_err(|e| format!("Failed to check for barcode index: {}", e))?;
+
+    if indexed > 0 {
+        return Ok(());
+    }
+
+    println!("Adding unique barcode indexes...");
+
+    for table in ["products", "product_variants"] {
+        conn.execute(
+            &format!(
+                "INSERT INTO barcode_conflicts (code, source_table, row_id, kept_row_id, resolution)
+                 SELECT t.barcode, '{table}', t.id, k.kept_id, 'cleared'
+                 FROM {table} t
+                 JOIN (SELECT barcode, MIN(id) AS kept_id FROM {table}
+                       WHERE barcode IS NOT NULL AND barcode != '' GROUP BY barcode) k ON k.barcode = t.barcode
+                 WHERE t.id != k.kept_id",
+                table = table
+            ),
+            [],
+        ).map_err(|e| format!("Failed to record duplicate barcodes in {}: {}", table, e))?;
+
+        conn.execute(
+            &format!(
+                "UPDATE {table} SET barcode = NULL
+                 WHERE id IN (SELECT row_id FROM barcode_conflicts WHERE source_table = '{table}' AND resolution = 'cleared')",
+                table = table
+            ),
+            [],
+        ).map_err(|e| format!("Failed to clear duplicate barcodes in {}: {}", table, e))?;
+    }
+
+    // Codes shared between different rows (e.g. one product's barcode is another's SKU)
+    // can't be fixed automatically; they are only reported
+    conn.execute(
+        "WITH codes AS (
+            SELECT barcode AS code, 'products' AS source_table, id AS row_id FROM products
+            WHERE barcode IS NOT NULL AND barcode != ''
+            UNION ALL SELECT sku, 'products', id FROM products
+            UNION ALL SELECT barcode, 'product_variants', id FROM product_variants
+            WHERE barcode IS NOT NULL AND barcode != ''
+            UNION ALL SELECT sku, 'product_variants', id FROM product_variants
+            UNION ALL SELECT code, 'product_barcodes', id FROM product_barcodes
+         )
+         INSERT INTO barcode_conflicts (code, source_table, row_id, resolution)
+         SELECT DISTINCT code, source_table, row_id, 'conflict' FROM codes
+         WHERE code IN (
+            SELECT code FROM codes GROUP BY code
+            HAVING COUNT(DISTINCT source_table || ':' || row_id) > 1
+         )",
+        [],
+    ).map_err(|e| format!("Failed to record conflicting codes: {}", e))?;
+
+    conn.execute(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_product_variants_barcode ON product_variants(barcode)
+         WHERE barcode IS NOT NULL AND barcode != ''",
+        [],
+    ).map_err(|e| format!("Failed to create variant barcode index: {}", e))?;
+
+    conn.execute(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_products_barcode ON products(barcode)
+         WHERE barcode IS NOT NULL AND barcode != ''",
+        [],
+    ).map_err(|e| format!("Failed to create product barcode index: {}", e))?;
+
+    let conflicts: i64 = conn.query_row("SELECT COUNT(*) FROM barcode_conflicts", [], |row| row.get(0))
+        .map_err(|e| format!("Failed to count barcode conflicts: {}", e))?;
+    println!("Successfully added barcode indexes, {} duplicate codes recorded", conflicts);
+
+    Ok(())
+}
+
+// Receipt template and printer settings (a single row), and a log of printed receipts
+fn create_receipt_tables(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS receipt_settings (
+            id INTEGER PRIMARY KEY NOT NULL CHECK (id = 1),
+            store_name TEXT NOT NULL,
+            header TEXT,
+            footer TEXT,
+            paper_width INTEGER NOT NULL DEFAULT 42,
+            printer_type TEXT NOT NULL DEFAULT 'none',
+            printer_target TEXT,
+            pdf_directory TEXT,
+            cut_paper INTEGER NOT NULL DEFAULT 1,
+            open_drawer INTEGER NOT NULL DEFAULT 0,
+            updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+        )",
+        [],
+    ).map_err(|e| format!("Failed to create receipt_settings table: {}", e))?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS receipt_prints (
+            id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+            order_id INTEGER NOT NULL,
+            reprint INTEGER NOT NULL DEFAULT 0,
+            format TEXT NOT NULL,
+            destination TEXT NOT NULL,
+            printer_error TEXT,
+            printed_at TEXT NOT NULL DEFAULT (datetime('now')),
+            FOREIGN KEY (order_id) REFERENCES orders(id) ON DELETE CASCADE
+        )",
+        [],
+    ).map_err(|e| format!("Failed to create receipt_prints table: {}", e))?;
+
+    Ok(())
+}
+
+// Payments settling each order, one row per method when a payment is split
+fn create_payment_tables(conn: &Connection) -> Result<(), String> {
+    let exists: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = 'order_payments'",
+        [],
+        |row| row.get(0),
+    ).map_err(|e| format!("Failed to check for order_payments table: {}", e))?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS order_payments (
+            id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+            order_id INTEGER NOT NULL,
+            method TEXT NOT NULL,
+            amount REAL NOT NULL,
+            reference TEXT,
+            tendered REAL,
+            change REAL NOT NULL DEFAULT 0,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            FOREIGN KEY (order_id) REFERENCES orders(id) ON DELETE CASCADE
+        )",
+        [],
+    ).map_err(|e| format!("Failed to create order_payments table: {}", e))?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_order_payments_order ON order_payments(order_id)",
+        [],
+    ).map_err(|e| format!("Failed to create order_payments index: {}", e))?;
+
+    // Orders taken before payments were tracked were all paid in cash
+    if exists == 0 {
+        conn.execute(
+            "INSERT INTO order_payments (order_id, method, amount, tendered, change, created_at)
+             SELECT id, 'cash', total, total, 0, created_at FROM orders",
+            [],
+        ).map_err(|e| format!("Failed to backfill order payments: {}", e))?;
+    }
+
+    Ok(())
+}
+
+// Cashier shifts with their drawer movements; closed shifts keep their Z report as JSON
+fn create_shift_tables(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS shifts (
+            id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+            user_id INTEGER NOT NULL,
+            cashier TEXT NOT NULL,
+            opening_float REAL NOT NULL DEFAULT 0,
+            opened_at TEXT NOT NULL,
+            closed_at TEXT,
+            counted_cash REAL,
+            expected_cash REAL,
+            over_short REAL,
+            notes TEXT,
+            z_report TEXT,
+            FOREIGN KEY (user_id) REFERENCES users(id)
+        )",
+        [],
+    ).map_err(|e| format!("Failed to create shifts table: {}", e))?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS cash_movements (
+            id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+            shift_id INTEGER NOT NULL,
+            kind TEXT NOT NULL,
+            amount REAL NOT NULL,
+            reason TEXT,
+            created_at TEXT NOT NULL,
+            FOREIGN KEY (shift_id) REFERENCES shifts(id) ON DELETE CASCADE
+        )",
+        [],
+    ).map_err(|e| format!("Failed to create cash_movements table: {}", e))?;
+
+    let result = conn.query_row(
+        "SELECT COUNT(*) FROM pragma_table_info('orders') WHERE name='shift_id'",
+        [],
+        |row| row.get::<_, i64>(0)
+    );
+
+    if let Ok(0) = result {
+        println!("Adding shift_id column to orders table...");
+        
+        conn.execute(
+            "ALTER TABLE orders ADD COLUMN shift_id INTEGER REFERENCES shifts(id)",
+            [],
+        ).map_err(|e| format!("Failed to add shift_id column: {}", e))?;
+    } else if let Err(e) = result {
+        return Err(format!("Failed to check for shift_id column: {}", e));
+    }
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_orders_shift ON orders(shift_id)",
+        [],
+    ).map_err(|e| format!("Failed to create orders shift index: {}", e))?;
+
+    Ok(())
+}
+
+// Scheduled promotions and the discounts given on each order line
+fn create_discount_tables(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS promotions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+            name TEXT NOT NULL,
+            kind TEXT NOT NULL,
+            percent REAL NOT NULL,
+            product_id INTEGER,
+            category_id INTEGER,
+            buy_quantity REAL,
+            get_quantity REAL,
+            start_time TEXT,
+            end_time TEXT,
+            starts_on TEXT,
+            ends_on TEXT,
+            active BOOLEAN NOT NULL DEFAULT 1,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            FOREIGN KEY (product_id) REFERENCES products(id) ON DELETE CASCADE,
+            FOREIGN KEY (category_id) REFERENCES categories(id) ON DELETE CASCADE
+        )",
+        [],
+    ).map_err(|e| format!("Failed to create promotions table: {}", e))?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS order_discounts (
+            id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+            order_id INTEGER NOT NULL,
+            order_item_id INTEGER,
+            source TEXT NOT NULL,
+            kind TEXT NOT NULL,
+            promotion_id INTEGER,
+            description TEXT NOT NULL,
+            amount REAL NOT NULL,
+            reason TEXT,
+            id_number TEXT,
+            created_at TEXT NOT NULL,
+            FOREIGN KEY (order_id) REFERENCES orders(id) ON DELETE CASCADE,
+            FOREIGN KEY (order_item_id) REFERENCES order_items(id) ON DELETE CASCADE
+        )",
+        [],
+    ).map_err(|e| format!("Failed to create order_discounts table: {}", e))?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_order_discounts_order ON order_discounts(order_id)",
+        [],
+    ).map_err(|e| format!("Failed to create order_discounts index: {}", e))?;
+
+    for table in ["orders", "order_items"] {
+        let result = conn.query_row(
+            &format!("SELECT COUNT(*) FROM pragma_table_info('{}') WHERE name='discount'", table),
+            [],
+            |row| row.get::<_, i64>(0)
+        );
+
+        if let Ok(0) = result {
+            println!("Adding discount column to {} table...", table);
+            
+            conn.execute(
+                &format!("ALTER TABLE {} ADD COLUMN discount REAL NOT NULL DEFAULT 0", table),
+                [],
+            ).map_err(|e| format!("Failed to add discount column to {}: {}", table, e))?;
+        } else if let Err(e) = result {
+            return Err(format!("Failed to check for discount column on {}: {}", table, e));
+        }
+    }
+
+    Ok(())
+}
+
+// Carts parked at the register; lines are kept as JSON until the cart is finalized
+fn create_held_order_tables(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS held_orders (
+            id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+            cashier TEXT NOT NULL,
+            label TEXT,
+            items TEXT NOT NULL,
+            discount TEXT,
+            subtotal REAL NOT NULL,
+            tax REAL NOT NULL,
+            total REAL NOT NULL,
+            status TEXT NOT NULL DEFAULT 'held',
+            order_id INTEGER,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            expires_at TEXT NOT NULL,
+            FOREIGN KEY (order_id) REFERENCES orders(id)
+        )",
+        [],
+    ).map_err(|e| format!("Failed to create held_orders table: {}", e))?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_held_orders_status ON held_orders(status, expires_at)",
+        [],
+    ).map_err(|e| format!("Failed to create held_orders index: {}", e))?;
+
+    Ok(())
+}
+
+// Order numbers are allocated per terminal and day from order_sequences. Numbers handed out
+// twice before that are renamed with their row id so the unique index can be created.
+fn create_order_number_tables(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS order_sequences (
+            prefix TEXT PRIMARY KEY NOT NULL,
+            last_number INTEGER NOT NULL
+        )",
+        [],
+    ).map_err(|e| format!("Failed to create order_sequences table: {}", e))?;
+
+    let indexed: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'index' AND name = 'idx_orders_order_id'",
+        [],
+        |row| row.get(0),
+    ).map_err(|e| format!("Failed to check for order number index: {}", e))?;
+
+    if indexed == 0 {
+        let renamed = conn.execute(
+            "UPDATE orders SET order_id = order_id || '-' || id
+             WHERE id NOT IN (SELECT MIN(id) FROM orders GROUP BY order_id)",
+            [],
+        ).map_err(|e| format!("Failed to rename duplicate order numbers: {}", e))?;
+
+        if renamed > 0 {
+            println!("Renamed {} duplicate order numbers", renamed);
+        }
+
+        conn.execute(
+            "CREATE UNIQUE INDEX idx_orders_order_id ON orders(order_id)",
+            [],
+        ).map_err(|e| format!("Failed to create order number index: {}", e))?;
+    }
+
+    Ok(())
+}
+
+// Key sent by the register with each sale so a retried submission isn't recorded twice
+fn add_idempotency_key_to_orders(conn: &Connection) -> Result<(), String> {
+    let result = conn.query_row(
+        "SELECT COUNT(*) FROM pragma_table_info('orders') WHERE name='idempotency_key'",
+        [],
+        |row| row.get::<_, i64>(0)
+    );
+
+    if let Ok(0) = result {
+        println!("Adding idempotency_key column to orders table...");
+        
+        conn.execute(
+            "ALTER TABLE orders ADD COLUMN idempotency_key TEXT",
+            [],
+        ).map_err(|e| format!("Failed to add idempotency_key column: {}", e))?;
+    } else if let Err(e) = result {
+        return Err(format!("Failed to check for idempotency_key column: {}", e));
+    }
+
+    conn.execute(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_orders_idempotency_key ON orders(idempotency_key)
+         WHERE idempotency_key IS NOT NULL",
+        [],
+    ).map_err(|e| format!("Failed to create idempotency key index: {}", e))?;
+
+    Ok(())
+}
+
+// Customers who can run a tab, and the ledger of charges and payments on their accounts
+fn create_customer_tables(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS customers (
+            id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+            name TEXT NOT NULL,
+            id_number TEXT,
+            phone TEXT,
+            email TEXT,
+            credit_limit REAL NOT NULL DEFAULT 0,
+            balance REAL NOT NULL DEFAULT 0,
+            active BOOLEAN NOT NULL DEFAULT 1,
+            notes TEXT,
+            created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+        )",
+        [],
+    ).map_err(|e| format!("Failed to create customers table: {}", e))?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS customer_ledger (
+            id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+            customer_id INTEGER NOT NULL,
+            kind TEXT NOT NULL,
+            order_id INTEGER,
+            amount REAL NOT NULL,
+            method TEXT,
+            reference TEXT,
+            notes TEXT,
+            balance_after REAL NOT NULL,
+            created_at TEXT NOT NULL,
+            FOREIGN KEY (customer_id) REFERENCES customers(id),
+            FOREIGN KEY (order_id) REFERENCES orders(id)
+        )",
+        [],
+    ).map_err(|e| format!("Failed to create customer_ledger table: {}", e))?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_customer_ledger_customer ON customer_ledger(customer_id, created_at)",
+        [],
+    ).map_err(|e| format!("Failed to create customer_ledger index: {}", e))?;
+
+    for table in ["orders", "held_orders"] {
+        let result = conn.query_row(
+            &format!("SELECT COUNT(*) FROM pragma_table_info('{}') WHERE name='customer_id'", table),
+            [],
+            |row| row.get::<_, i64>(0)
+        );
+
+        if let Ok(0) = result {
+            println!("Adding customer_id column to {} table...", table);
+            
+            conn.execute(
+                &format!("ALTER TABLE {} ADD COLUMN customer_id INTEGER REFERENCES customers(id)", table),
+                [],
+            ).map_err(|e| format!("Failed to add customer_id column to {}: {}", table, e))?;
+        } else if let Err(e) = result {
+            return Err(format!("Failed to check for customer_id column on {}: {}", table, e));
+        }
+    }
+
+    Ok(())
+}
+
+fn create_wallet_tables(conn: &Connection) -> Result<(), String> {
+    for (column, definition) in [("card_code", "TEXT"), ("wallet_balance", "REAL NOT NULL DEFAULT 0")] {
+        let result = conn.query_row(
+            &format!("SELECT COUNT(*) FROM pragma_table_info('customers') WHERE name='{}'", column),
+            [],
+            |row| row.get::<_, i64>(0)
+        );
+
+        if let Ok(0) = result {
+            println!("Adding {} column to customers table...", column);
+            
+            conn.execute(
+                &format!("ALTER TABLE customers ADD COLUMN {} {}", column, definition),
+                [],
+            ).map_err(|e| format!("Failed to add {} column to customers: {}", column, e))?;
+        } else if let Err(e) = result {
+            return Err(format!("Failed to check for {} column on customers: {}", column, e));
+        }
+    }
+
+    // A card opens one wallet
+    conn.execute(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_customers_card_code ON customers(card_code) WHERE card_code IS NOT NULL",
+        [],
+    ).map_err(|e| format!("Failed to create customers card index: {}", e))?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS wallet_ledger (
+            id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+            customer_id INTEGER NOT NULL,
+            kind TEXT NOT NULL,
+            order_id INTEGER,
+            amount REAL NOT NULL,
+            method TEXT,
+            reference TEXT,
+            notes TEXT,
+            balance_after REAL NOT NULL,
+            created_at TEXT NOT NULL,
+            FOREIGN KEY (customer_id) REFERENCES customers(id),
+            FOREIGN KEY (order_id) REFERENCES orders(id)
+        )",
+        [],
+    ).map_err(|e| format!("Failed to create wallet_ledger table: {}", e))?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_wallet_ledger_customer ON wallet_ledger(customer_id, created_at)",
+        [],
+    ).map_err(|e| format!("Failed to create wallet_ledger index: {}", e))?;
+
+    Ok(())
+}
+
+fn create_badge_tables(conn: &Connection) -> Result<(), String> {
+    for column in ["badge_hash", "badge_pin_hash"] {
+        let result = conn.query_row(
+            &format!("SELECT COUNT(*) FROM pragma_table_info('users') WHERE name='{}'", column),
+            [],
+            |row| row.get::<_, i64>(0)
+        );
+
+        if let Ok(0) = result {
+            println!("Adding {} column to users table...", column);
+            
+            conn.execute(
+                &format!("ALTER TABLE users ADD COLUMN {} TEXT", column),
+                [],
+            ).map_err(|e| format!("Failed to add {} column to users: {}", column, e))?;
+        } else if let Err(e) = result {
+            return Err(format!("Failed to check for {} column on users: {}", column, e));
+        }
+    }
+
+    conn.execute(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_users_badge_hash ON users(badge_hash) WHERE badge_hash IS NOT NULL",
+        [],
+    ).map_err(|e| format!("Failed to create users badge index: {}", e))?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS terminal_cashiers (
+            terminal TEXT PRIMARY KEY NOT NULL,
+            user_id INTEGER NOT NULL,
+            switched_at TEXT NOT NULL,
+            FOREIGN KEY (user_id) REFERENCES users(id)
+        )",
+        [],
+    ).map_err(|e| format!("Failed to create terminal_cashiers table: {}", e))?;
+
+    Ok(())
+}
+
+// Password policy settings (a single row) and the login lockout state of each user
+fn create_security_tables(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS security_settings (
+            id INTEGER PRIMARY KEY NOT NULL CHECK (id = 1),
+            min_password_length INTEGER NOT NULL DEFAULT 8,
+            require_letter INTEGER NOT NULL DEFAULT 1,
+            require_digit INTEGER NOT NULL DEFAULT 1,
+            require_mixed_case INTEGER NOT NULL DEFAULT 0,
+            require_symbol INTEGER NOT NULL DEFAULT 0,
+            max_failed_logins INTEGER NOT NULL DEFAULT 5,
+            lockout_minutes INTEGER NOT NULL DEFAULT 15,
+            updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+        )",
+        [],
+    ).map_err(|e| format!("Failed to create security_settings table: {}", e))?;
+
+    let columns = [
+        ("failed_login_attempts", "INTEGER NOT NULL DEFAULT 0"),
+        ("locked_until", "TEXT"),
+        ("must_change_password", "BOOLEAN NOT NULL DEFAULT 0"),
+        ("password_changed_at", "TEXT"),
+    ];
+    for (column, definition) in columns {
+        let result = conn.query_row(
+            &format!("SELECT COUNT(*) FROM pragma_table_info('users') WHERE name='{}'", column),
+            [],
+            |row| row.get::<_, i64>(0)
+        );
+
+        if let Ok(0) = result {
+            println!("Adding {} column to users table...", column);
+            
+            conn.execute(
+                &format!("ALTER TABLE users ADD COLUMN {} {}", column, definition),
+                [],
+            ).map_err(|e| format!("Failed to add {} column to users: {}", column, e))?;
+
+            // An admin still on the seeded password has to pick a new one
+            if column == "must_change_password" {
+                let admin_hash: Option<String> = conn.query_row(
+                    "SELECT password_hash FROM users WHERE username = 'admin'",
+                    [],
+                    |row| row.get(0),
+                ).optional().map_err(|e| format!("Failed to check admin password: {}", e))?;
+
+                if admin_hash.is_some_and(|h| verify("admin", &h).unwrap_or(false)) {
+                    conn.execute("UPDATE users SET must_change_password = 1 WHERE username = 'admin'", [])
+                        .map_err(|e| format!("Failed to flag admin password: {}", e))?;
+                }
+            }
+        } else if let Err(e) = result {
+            return Err(format!("Failed to check for {} column on users: {}", column, e));
+        }
+    }
+
+    Ok(())
+}
+
+// Deactivated users can't sign in; their orders and shifts still point at them
+fn add_active_flag_to_users(conn: &Connection) -> Result<(), String> {
+    for (column, definition) in [("active", "BOOLEAN NOT NULL DEFAULT 1"), ("deactivated_at", "TEXT")] {
+        let result = conn.query_row(
+            &format!("SELECT COUNT(*) FROM pragma_table_info('users') WHERE name='{}'", column),
+            [],
+            |row| row.get::<_, i64>(0)
+        );
+
+        if let Ok(0) = result {
+            println!("Adding {} column to users table...", column);
+            
+            conn.execute(
+                &format!("ALTER TABLE users ADD COLUMN {} {}", column, definition),
+                [],
+            ).map_err(|e| format!("Failed to add {} column to users: {}", column, e))?;
+        } else if let Err(e) = result {
+            return Err(format!("Failed to check for {} column on users: {}", column, e));
+        }
+    }
+
+    Ok(())
+}
+
+// Named permission sets users are given by role, and per-user exceptions to them
+fn create_role_tables(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS roles (
+            id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+            name TEXT NOT NULL UNIQUE,
+            description TEXT,
+            built_in BOOLEAN NOT NULL DEFAULT 0,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+        )",
+        [],
+    ).map_err(|e| format!("Failed to create roles table: {}", e))?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS role_permissions (
+            role_id INTEGER NOT NULL,
+            permission TEXT NOT NULL,
+            PRIMARY KEY (role_id, permission),
+            FOREIGN KEY (role_id) REFERENCES roles(id)
+        )",
+        [],
+    ).map_err(|e| format!("Failed to create role_permissions table: {}", e))?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS user_permission_overrides (
+            user_id INTEGER NOT NULL,
+            permission TEXT NOT NULL,
+            granted BOOLEAN NOT NULL,
+            PRIMARY KEY (user_id, permission),
+            FOREIGN KEY (user_id) REFERENCES users(id)
+        )",
+        [],
+    ).map_err(|e| format!("Failed to create user_permission_overrides table: {}", e))?;
+
+    // Built-in roles get their default permissions the first time they are created
+    let built_in: [(&str, &str, &[&str]); 4] = [
+        ("admin", "Everything, including staff and settings", &[]),
+        ("manager", "Runs the store day to day", &["view_inventory", "edit_inventory", "sell", "refund", "view_reports"]),
+        ("cashier", "Rings up sales", &["view_inventory", "sell"]),
+        ("user", "Default for new accounts", &["view_inventory", "sell"]),
+    ];
+    for (name, description, permissions) in built_in {
+        let created = conn.execute(
+            "INSERT OR IGNORE INTO roles (name, description, built_in) VALUES (?1, ?2, 1)",
+            rusqlite::params![name, description],
+        ).map_err(|e| format!("Failed to seed role {}: {}", name, e))?;
+
+        if created > 0 {
+            let role_id = conn.last_insert_rowid();
+            for permission in permissions {
+                conn.execute(
+                    "INSERT INTO role_permissions (role_id, permission) VALUES (?1, ?2)",
+                    rusqlite::params![role_id, permission],
+                ).map_err(|e| format!("Failed to seed permissions of role {}: {}", name, e))?;
+            }
+        }
+    }
+
+    // Roles typed into users.role before there was a roles table become roles without permissions
+    conn.execute(
+        "INSERT OR IGNORE INTO roles (name, description)
+         SELECT DISTINCT role, 'Carried over from existing accounts' FROM users",
+        [],
+    ).map_err(|e| format!("Failed to carry over existing roles: {}", e))?;
+
+    Ok(())
+}
+
+// Who changed what and when; triggers refuse edits and deletes so entries can't be rewritten
+fn create_audit_tables(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS audit_log (
+            id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+            user_id INTEGER,
+            username TEXT,
+            action TEXT NOT NULL,
+            entity_type TEXT NOT NULL,
+            entity_id INTEGER,
+            before TEXT,
+            after TEXT,
+            created_at TEXT NOT NULL DEFAULT (datetime('now', 'localtime'))
+        )",
+        [],
+    ).map_err(|e| format!("Failed to create audit_log table: {}", e))?;
+
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_audit_log_created_at ON audit_log(created_at)", [])
+        .map_err(|e| format!("Failed to create audit log index: {}", e))?;
+
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_audit_log_entity ON audit_log(entity_type, entity_id)", [])
+        .map_err(|e| format!("Failed to create audit log index: {}", e))?;
+
+    for (trigger, event) in [("audit_log_no_update", "UPDATE"), ("audit_log_no_delete", "DELETE")] {
+        conn.execute(
+            &format!(
+                "CREATE TRIGGER IF NOT EXISTS {} BEFORE {} ON audit_log
+                 BEGIN
+                    SELECT RAISE(ABORT, 'The audit log is append-only');
+                 END",
+                trigger, event
+            ),
+            [],
+        ).map_err(|e| format!("Failed to create {} trigger: {}", trigger, e))?;
+    }
+
+    Ok(())
+}
+
+// Archived products and categories are hidden from listings and the register but kept for
+// reports and can be restored
+fn add_archived_at_to_catalogue(conn: &Connection) -> Result<(), String> {
+    for table in ["products", "categories"] {
+        let result = conn.query_row(
+            &format!("SELECT COUNT(*) FROM pragma_table_info('{}') WHERE name='archived_at'", table),
+            [],
+            |row| row.get::<_, i64>(0)
+        );
+
+        if let Ok(0) = result {
+            println!("Adding archived_at column to {} table...", table);
+            
+            conn.execute(
+                &format!("ALTER TABLE {} ADD COLUMN archived_at TEXT", table),
+                [],
+            ).map_err(|e| format!("Failed to add archived_at column to {}: {}", table, e))?;
+        } else if let Err(e) = result {
+            return Err(format!("Failed to check for archived_at column on {}: {}", table, e));
+        }
+    }
+
+    Ok(())
+}
+
+// Subcategories point at their parent; top-level categories have none
+fn add_parent_id_to_categories(conn: &Connection) -> Result<(), String> {
+    let result = conn.query_row(
+        "SELECT COUNT(*) FROM pragma_table_info('categories') WHERE name='parent_id'",
+        [],
+        |row| row.get::<_, i64>(0)
+    );
+
+    if let Ok(0) = result {
+        println!("Adding parent_id column to categories table...");
+        
+        conn.execute(
+            "ALTER TABLE categories ADD COLUMN parent_id INTEGER REFERENCES categories(id)",
+            [],
+        ).map_err(|e| format!("Failed to add parent_id column to categories: {}", e))?;
+    } else if let Err(e) = result {
+        return Err(format!("Failed to check for parent_id column: {}", e));
+    }
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_categories_parent ON categories(parent_id)",
+        [],
+    ).map_err(|e| format!("Failed to create category parent index: {}", e))?;
+
+    Ok(())
+}
+
+// What products_fts holds for each product. Codes covers the product's own barcode, the
+// extra barcodes of it and its variants, and the variants' own SKUs and barcodes.
+const PRODUCT_SEARCH_ROWS: &str =
+    "INSERT INTO products_fts (rowid, name, description, sku, codes)
+     SELECT p.id, p.name, COALESCE(p.description, ''), p.sku,
+            TRIM(COALESCE(p.barcode, '') || ' ' ||
+                 COALESCE((SELECT group_concat(b.code, ' ') FROM product_barcodes b WHERE b.product_id = p.id), '') || ' ' ||
+                 COALESCE((SELECT group_concat(v.sku || ' ' || COALESCE(v.barcode, ''), ' ')
+                           FROM product_variants v WHERE v.product_id = p.id), ''))
+     FROM products p";
+
+// Re-index one product; the id is an SQL expression such as new.id
+fn product_search_refresh(id: &str) -> String {
+    format!(
+        "DELETE FROM products_fts WHERE rowid = {id}; {rows} WHERE p.id = {id};",
+        id = id,
+        rows = PRODUCT_SEARCH_ROWS
+    )
+}
+
+// Full-text index over product names, descriptions, SKUs and barcodes for search_products,
+// kept up to date by triggers
+fn create_product_search_index(conn: &Connection) -> Result<(), String> {
+    let exists: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = 'products_fts'",
+        [],
+        |row| row.get(0)
+    ).map_err(|e| format!("Failed to check for products_fts table: {}", e))?;
+
+    // Indexes built before variant codes were searchable are rebuilt with new triggers
+    let indexes_variants: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'trigger' AND name = 'product_variants_fts_insert'",
+        [],
+        |row| row.get(0)
+    ).map_err(|e| format!("Failed to check for product_variants_fts_insert trigger: {}", e))?;
+
+    conn.execute(
+        "CREATE VIRTUAL TABLE IF NOT EXISTS products_fts USING fts5(
+            name, description, sku, codes,
+            tokenize = 'unicode61 remove_diacritics 2'
+        )",
+        [],
+    ).map_err(|e| format!("Failed to create products_fts table: {}", e))?;
+
+    let triggers = [
+        ("products_fts_insert", "AFTER INSERT ON products", product_search_refresh("new.id")),
+        ("products_fts_update", "AFTER UPDATE OF name, description, sku, barcode ON products", product_search_refresh("new.id")),
+        ("products_fts_delete", "AFTER DELETE ON products", "DELETE FROM products_fts WHERE rowid = old.id;".to_string()),
+        ("product_barcodes_fts_insert", "AFTER INSERT ON product_barcodes", product_search_refresh("new.product_id")),
+        ("product_barcodes_fts_update", "AFTER UPDATE ON product_barcodes", product_search_refresh("old.product_id") + &product_search_refresh("new.product_id")),
+        ("product_barcodes_fts_delete", "AFTER DELETE ON product_barcodes", product_search_refresh("old.product_id")),
+        ("product_variants_fts_insert", "AFTER INSERT ON product_variants", product_search_refresh("new.product_id")),
+        ("product_variants_fts_update", "AFTER UPDATE OF product_id, sku, barcode ON product_variants", product_search_refresh("old.product_id") + &product_search_refresh("new.product_id")),
+        ("product_variants_fts_delete", "AFTER DELETE ON product_variants", product_search_refresh("old.product_id")),
+    ];
+    for (name, event, body) in triggers {
+        if indexes_variants == 0 {
+            conn.execute(&format!("DROP TRIGGER IF EXISTS {}", name), [])
+                .map_err(|e| format!("Failed to drop {} trigger: {}", name, e))?;
+        }
+        conn.execute(
+            &format!("CREATE TRIGGER IF NOT EXISTS {} {} BEGIN {} END", name, event, body),
+            [],
+        ).map_err(|e| format!("Failed to create {} trigger: {}", name, e))?;
+    }
+
+    // Index the products that were there before the index was
+    if exists == 0 || indexes_variants == 0 {
+        println!("Indexing products for search...");
+        conn.execute("DELETE FROM products_fts", [])
+            .map_err(|e| format!("Failed to clear products_fts: {}", e))?;
+        conn.execute(PRODUCT_SEARCH_ROWS, [])
+            .map_err(|e| format!("Failed to index products for search: {}", e))?;
+    }
+
+    Ok(())
+}
+
+// Every change to a product's retail price or cost, and changes set to happen later
+fn create_price_tables(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS price_history (
+            id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+            product_id INTEGER NOT NULL,
+            old_unit_price REAL NOT NULL,
+            new_unit_price REAL NOT NULL,
+            old_price_bought REAL NOT NULL,
+            new_price_bought REAL NOT NULL,
+            user_id INTEGER,
+            username TEXT,
+            reason TEXT,
+            scheduled_change_id INTEGER,
+            changed_at TEXT NOT NULL,
+            FOREIGN KEY (product_id) REFERENCES products(id)
+        )",
+        [],
+    ).map_err(|e| format!("Failed to create price_history table: {}", e))?;
+
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_price_history_product ON price_history(product_id, changed_at)", [])
+        .map_err(|e| format!("Failed to create price history index: {}", e))?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS scheduled_price_changes (
+            id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+            product_id INTEGER NOT NULL,
+            unit_price REAL,
+            price_bought REAL,
+            effective_at TEXT NOT NULL,
+            reason TEXT,
+            status TEXT NOT NULL DEFAULT 'pending',
+            created_by_id INTEGER,
+            created_by TEXT,
+            created_at TEXT NOT NULL,
+            applied_at TEXT,
+            FOREIGN KEY (product_id) REFERENCES products(id)
+        )",
+        [],
+    ).map_err(|e| format!("Failed to create scheduled_price_changes table: {}", e))?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_scheduled_price_changes_due ON scheduled_price_changes(status, effective_at)",
+        [],
+    ).map_err(|e| format!("Failed to create scheduled price change index: {}", e))?;
+
+    Ok(())
+}
+
+// Function to clear all sample data for production builds
+fn clean_database_for_production(conn: &mut Connection) -> Result<(), String> {
+    println!("Cleaning database for production build...");
+    
+    // Start a transaction to ensure all operations complete or none do
+    let tx = conn.transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    
+    // Delete all sample data
+    tx.execute("DELETE FROM notifications", [])
+        .map_err(|e| format!("Failed to delete notifications: {}", e))?;
+    
+    tx.execute("DELETE FROM order_item_modifiers", [])
+        .map_err(|e| format!("Failed to delete order item modifiers: {}", e))?;
+    
+    tx.execute("DELETE FROM terminal_cashiers", [])
+        .map_err(|e| format!("Failed to delete terminal cashiers: {}", e))?;
+    
+    tx.execute("DELETE FROM wallet_ledger", [])
+        .map_err(|e| format!("Failed to delete wallet ledger: {}", e))?;
+    
+    tx.execute("DELETE FROM customer_ledger", [])
+        .map_err(|e| format!("Failed to delete customer ledger: {}", e))?;
+    
+    tx.execute("DELETE FROM customers", [])
+        .map_err(|e| format!("Failed to delete customers: {}", e))?;
+    
+    tx.execute("DELETE FROM order_sequences", [])
+        .map_err(|e| format!("Failed to delete order sequences: {}", e))?;
+    
+    tx.execute("DELETE FROM held_orders", [])
+        .map_err(|e| format!("Failed to delete held orders: {}", e))?;
+    
+    tx.execute("DELETE FROM order_discounts", [])
+        .map_err(|e| format!("Failed to delete order discounts: {}", e))?;
+    
+    tx.execute("DELETE FROM order_payments", [])
+        .map_err(|e| format!("Failed to delete order payments: {}", e))?;
+    
+    tx.execute("DELETE FROM receipt_prints", [])
+        .map_err(|e| format!("Failed to delete receipt prints: {}", e))?;
+    
+    tx.execute("DELETE FROM order_items", [])
+        .map_err(|e| format!("Failed to delete order items: {}", e))?;
+    
+    tx.execute("DELETE FROM orders", [])
+        .map_err(|e| format!("Failed to delete orders: {}", e))?;
+    
+    tx.execute("DELETE FROM cash_movements", [])
+        .map_err(|e| format!("Failed to delete cash movements: {}", e))?;
+    
+    tx.execute("DELETE FROM shifts", [])
+        .map_err(|e| format!("Failed to delete shifts: {}", e))?;
+    
+    tx.execute("DELETE FROM recipe_components", [])
+        .map_err(|e| format!("Failed to delete recipe components: {}", e))?;
+    
+    tx.execute("DELETE FROM recipes", [])
+        .map_err(|e| format!("Failed to delete recipes: {}", e))?;
+    
+    tx.execute("DELETE FROM modifiers", [])
+        .map_err(|e| format!("Failed to delete modifiers: {}", e))?;
+    
+    tx.execute("DELETE FROM modifier_groups", [])
+        .map_err(|e| format!("Failed to delete modifier groups: {}", e))?;
+    
+    tx.execute("DELETE FROM product_variants", [])
+        .map_err(|e| format!("Failed to delete product variants: {}", e))?;
+    
+    tx.execute("DELETE FROM product_barcodes", [])
+        .map_err(|e| format!("Failed to delete product barcodes: {}", e))?;
+    
+    tx.execute("DELETE FROM barcode_conflicts", [])
+        .map_err(|e| format!("Failed to delete barcode conflicts: {}", e))?;
+    
+    tx.execute("DELETE FROM stock_movements", [])
+        .map_err(|e| format!("Failed to delete stock movements: {}", e))?;
+    
+    tx.execute("DELETE FROM product_units", [])
+        .map_err(|e| format!("Failed to delete product units: {}", e))?;
+    
+    tx.execute("DELETE FROM scheduled_price_changes", [])
+        .map_err(|e| format!("Failed to delete scheduled price changes: {}", e))?;
+    
+    tx.execute("DELETE FROM price_history", [])
+        .map_err(|e| format!("Failed to delete price history: {}", e))?;
+    
+    tx.execute("DELETE FROM promotions", [])
+        .map_err(|e| format!("Failed to delete promotions: {}", e))?;
+    
+    tx.execute("DELETE FROM products", [])
+        .map_err(|e| format!("Failed to delete products: {}", e))?;
+    
+    tx.execute("DELETE FROM categories", [])
+        .map_err(|e| format!("Failed to delete categories: {}", e))?;
+    
+    tx.execute("DELETE FROM user_permission_overrides", [])
+        .map_err(|e| format!("Failed to delete permission overrides: {}", e))?;
     
     // Keep the admin user but delete any other users
     tx.execute(
@@ -542,7 +1883,108 @@ fn main() {
             get_order_history,
             get_order_statistics,
             get_sales_report_data,
-            read_image_to_base64
+            read_image_to_base64,
+            get_notifications,
+            mark_notification_read,
+            mark_all_notifications_read,
+            dismiss_notification,
+            refresh_stock_alerts,
+            get_product_variants,
+            add_product_variant,
+            update_product_variant,
+            delete_product_variant,
+            get_modifier_groups,
+            add_modifier_group,
+            delete_modifier_group,
+            add_modifier,
+            delete_modifier,
+            get_recipe,
+            save_recipe,
+            delete_recipe,
+            get_product_units,
+            set_product_units,
+            receive_stock,
+            record_stocktake,
+            get_stock_movements,
+            find_product_by_code,
+            get_product_barcodes,
+            add_product_barcode,
+            delete_product_barcode,
+            get_barcode_conflicts,
+            generate_labels,
+            get_receipt_settings,
+            save_receipt_settings,
+            print_receipt,
+            reprint_receipt,
+            save_receipt_pdf,
+            get_order_payments,
+            open_shift,
+            get_current_shift,
+            get_shifts,
+            add_cash_movement,
+            get_cash_movements,
+            get_shift_report,
+            close_shift,
+            export_shift_report,
+            preview_order,
+            get_promotions,
+            add_promotion,
+            update_promotion,
+            delete_promotion,
+            hold_order,
+            get_held_orders,
+            resume_held_order,
+            update_held_order,
+            finalize_held_order,
+            cancel_held_order,
+            find_order_by_number,
+            get_customers,
+            get_customer,
+            add_customer,
+            update_customer,
+            record_account_payment,
+            get_customer_statement,
+            assign_wallet_card,
+            get_wallet_by_card,
+            top_up_wallet,
+            refund_wallet,
+            get_wallet_history,
+            set_user_badge,
+            login_with_badge,
+            switch_cashier,
+            logout,
+            clear_terminal_cashier,
+            change_password,
+            get_security_settings,
+            save_security_settings,
+            reset_password,
+            deactivate_user,
+            reactivate_user,
+            get_permissions,
+            get_roles,
+            add_role,
+            update_role,
+            delete_role,
+            get_users,
+            get_user_access,
+            set_user_role,
+            set_permission_override,
+            get_audit_log,
+            export_audit_log,
+            get_price_history,
+            schedule_price_change,
+            cancel_scheduled_price_change,
+            archive_product,
+            restore_product,
+            get_archived_products,
+            archive_category,
+            restore_category,
+            get_archived_categories,
+            preview_delete_category,
+            repair_uncategorized,
+            get_category_tree,
+            move_category,
+            search_products
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,251 @@
+// Barcode symbologies, encoded as a row of modules (true = bar, false = space)
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Symbology {
+    Ean13,
+    Code128,
+}
+
+impl Symbology {
+    pub fn parse(name: &str) -> Result<Symbology, String> {
+        match name.to_lowercase().as_str() {
+            "ean13" | "ean-13" => Ok(Symbology::Ean13),
+            "code128" | "code-128" => Ok(Symbology::Code128),
+            _ => Err(format!("Unsupported barcode type: {}", name)),
+        }
+    }
+
+    pub fn encode(self, data: &str) -> Result<Vec<bool>, String> {
+        match self {
+            Symbology::Ean13 => encode_ean13(data),
+            Symbology::Code128 => encode_code128(data),
+        }
+    }
+}
+
+// Left-hand odd parity (L) patterns; R is the complement of L and G is R reversed
+const EAN_L: [&str; 10] = [
+    "0001101", "0011001", "0010011", "0111101", "0100011",
+    "0110001", "0101111", "0111011", "0110111", "0001011",
+];
+
+// Which of the six left-hand digits use the G set, chosen by the first digit
+const EAN_PARITY: [&str; 10] = [
+    "LLLLLL", "LLGLGG", "LLGGLG", "LLGGGL", "LGLLGG",
+    "LGGLLG", "LGGGLL", "LGLGLL", "LGLGGL", "LGGLGL",
+];
+
+// The value of each digit in a code, or an error naming the first character that isn't one
+fn digit_values(code: &str) -> Result<Vec<u32>, String> {
+    code.chars()
+        .map(|c| c.to_digit(10).ok_or_else(|| format!("{:?} is not a digit in {:?}", c, code)))
+        .collect()
+}
+
+/// Check digit for the first 12 digits of an EAN-13 code
+pub fn ean13_check_digit(digits: &str) -> Result<u32, String> {
+    if digits.len() != 12 || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(format!("EAN-13 needs 12 digits before the check digit, got {:?}", digits));
+    }
+
+    let sum: u32 = digit_values(digits)?
+        .into_iter()
+        .enumerate()
+        .map(|(i, digit)| digit * if i % 2 == 0 { 1 } else { 3 })
+        .sum();
+
+    Ok((10 - sum % 10) % 10)
+}
+
+pub fn is_valid_ean13(code: &str) -> bool {
+    code.len() == 13
+        && code.bytes().all(|b| b.is_ascii_digit())
+        && ean13_check_digit(&code[..12]).ok() == code[12..].parse::<u32>().ok()
+}
+
+fn push_pattern(modules: &mut Vec<bool>, pattern: &str) {
+    modules.extend(pattern.chars().map(|c| c == '1'));
+}
+
+fn encode_ean13(code: &str) -> Result<Vec<bool>, String> {
+    if !is_valid_ean13(code) {
+        return Err(format!("{} is not a valid EAN-13 code", code));
+    }
+
+    let digits: Vec<usize> = digit_values(code)?.into_iter().map(|digit| digit as usize).collect();
+    let parity = EAN_PARITY[digits[0]];
+    let mut modules = Vec::with_capacity(95);
+
+    push_pattern(&mut modules, "101");
+    for (i, set) in parity.chars().enumerate() {
+        let l = EAN_L[digits[i + 1]];
+        if set == 'L' {
+            push_pattern(&mut modules, l);
+        } else {
+            // G: complement of L, reversed
+            modules.extend(l.chars().rev().map(|c| c == '0'));
+        }
+    }
+    push_pattern(&mut modules, "01010");
+    for &digit in &digits[7..] {
+        modules.extend(EAN_L[digit].chars().map(|c| c == '0'));
+    }
+    push_pattern(&mut modules, "101");
+
+    Ok(modules)
+}
+
+// Bar/space widths of the Code 128 symbols 0-105; the stop symbol is separate
+const CODE128_WIDTHS: [&str; 106] = [
+    "212222", "222122", "222221", "121223", "121322", "131222", "122213", "122312", "132212", "221213",
+    "221312", "231212", "112232", "122132", "122231", "113222", "123122", "123221", "223211", "221132",
+    "221231", "213212", "223112", "312131", "311222", "321122", "321221", "312212", "322112", "322211",
+    "212123", "212321", "232121", "111323", "131123", "131321", "112313", "132113", "132311", "211313",
+    "231113", "231311", "112133", "112331", "132131", "113123", "113321", "133121", "313121", "211331",
+    "231131", "213113", "213311", "213131", "311123", "311321", "331121", "312113", "312311", "332111",
+    "314111", "221411", "431111", "111224", "111422", "121124", "121421", "141122", "141221", "112214",
+    "112412", "122114", "122411", "142112", "142211", "241211", "221114", "413111", "241112", "134111",
+    "111242", "121142", "121241", "114212", "124112", "124211", "411212", "421112", "421211", "212141",
+    "214121", "412121", "111143", "111341", "131141", "114113", "114311", "411113", "411311", "113141",
+    "114131", "311141", "411131", "211412", "211214", "211232",
+];
+const CODE128_STOP: &str = "2331112";
+const CODE128_START_B: usize = 104;
+const CODE128_START_C: usize = 105;
+
+fn push_widths(modules: &mut Vec<bool>, widths: &str) -> Result<(), String> {
+    for (i, width) in digit_values(widths)?.into_iter().enumerate() {
+        let bar = i % 2 == 0;
+        modules.extend(std::iter::repeat_n(bar, width as usize));
+    }
+    Ok(())
+}
+
+// Code set C packs digit pairs and is used for all-numeric data of even length;
+// everything else is encoded in code set B (printable ASCII)
+fn encode_code128(data: &str) -> Result<Vec<bool>, String> {
+    if data.is_empty() {
+        return Err("Cannot encode an empty barcode".to_string());
+    }
+
+    let mut symbols = Vec::new();
+    if data.len().is_multiple_of(2) && data.chars().all(|c| c.is_ascii_digit()) {
+        symbols.push(CODE128_START_C);
+        for pair in data.as_bytes().chunks(2) {
+            symbols.push(((pair[0] - b'0') * 10 + (pair[1] - b'0')) as usize);
+        }
+    } else {
+        symbols.push(CODE128_START_B);
+        for c in data.chars() {
+            if !(' '..='~').contains(&c) {
+                return Err(format!("Code 128 cannot encode the character {:?}", c));
+            }
+            symbols.push(c as usize - 32);
+        }
+    }
+
+    let checksum = symbols
+        .iter()
+        .enumerate()
+        .map(|(i, &symbol)| symbol * i.max(1))
+        .sum::<usize>()
+        % 103;
+    symbols.push(checksum);
+
+    let mut modules = Vec::new();
+    for symbol in symbols {
+        push_widths(&mut modules, CODE128_WIDTHS[symbol])?;
+    }
+    push_widths(&mut modules, CODE128_STOP)?;
+
+    Ok(modules)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bits(modules: &[bool]) -> String {
+        modules.iter().map(|&bar| if bar { '1' } else { '0' }).collect()
+    }
+
+    #[test]
+    fn ean13_check_digits() {
+        assert_eq!(ean13_check_digit("400638133393"), Ok(1));
+        assert_eq!(ean13_check_digit("590123412345"), Ok(7));
+        assert_eq!(ean13_check_digit("978030640615"), Ok(7));
+        assert!(ean13_check_digit("40063813339").is_err());
+        assert!(ean13_check_digit("40063813339X").is_err());
+    }
+
+    #[test]
+    fn ean13_validation() {
+        assert!(is_valid_ean13("4006381333931"));
+        assert!(is_valid_ean13("5901234123457"));
+        assert!(!is_valid_ean13("5901234123458"));
+        assert!(!is_valid_ean13("590123412345"));
+        assert!(!is_valid_ean13("ABCDEFGHIJKLM"));
+        assert!(!is_valid_ean13("590123412345X"));
+        assert!(!is_valid_ean13("X901234123457"));
+    }
+
+    #[test]
+    fn ean13_encoding() {
+        let modules = bits(&Symbology::Ean13.encode("5901234123457").unwrap());
+        assert_eq!(modules.len(), 95);
+        assert_eq!(&modules[..3], "101");
+        assert_eq!(&modules[45..50], "01010");
+        assert_eq!(&modules[92..], "101");
+        // First digit 5 sets the parity LGGLLG: 9 in L, 0 in G, 1 in G
+        assert_eq!(&modules[3..10], "0001011");
+        assert_eq!(&modules[10..17], "0100111");
+        assert_eq!(&modules[17..24], "0110011");
+        // Right-hand digits use R: the last is the check digit 7
+        assert_eq!(&modules[85..92], "1000100");
+    }
+
+    #[test]
+    fn ean13_rejects_non_digits() {
+        assert!(Symbology::Ean13.encode("ABCDEFGHIJKLM").is_err());
+        assert!(Symbology::Ean13.encode("5901234123458").is_err());
+    }
+
+    #[test]
+    fn code128_set_b() {
+        let modules = bits(&Symbology::Code128.encode("PJJ123C").unwrap());
+        // Start, 7 data symbols and the checksum at 11 modules each, then the 13-module stop
+        assert_eq!(modules.len(), 11 * 9 + 13);
+        assert_eq!(&modules[..11], "11010010000");
+        // (104 + 48 + 42*2 + 42*3 + 17*4 + 18*5 + 19*6 + 35*7) % 103 = 55
+        assert_eq!(&modules[88..99], bits(&widths("311321")));
+        assert_eq!(&modules[99..], "1100011101011");
+    }
+
+    #[test]
+    fn code128_set_c() {
+        let modules = bits(&Symbology::Code128.encode("123456").unwrap());
+        assert_eq!(modules.len(), 11 * 5 + 13);
+        assert_eq!(&modules[..11], "11010011100");
+        // (105 + 12 + 34*2 + 56*3) % 103 = 44
+        assert_eq!(&modules[44..55], bits(&widths("132131")));
+    }
+
+    #[test]
+    fn code128_odd_length_digits_use_set_b() {
+        let modules = bits(&Symbology::Code128.encode("12345").unwrap());
+        assert_eq!(&modules[..11], "11010010000");
+    }
+
+    #[test]
+    fn code128_rejects_unprintable() {
+        assert!(Symbology::Code128.encode("").is_err());
+        assert!(Symbology::Code128.encode("caf\u{e9}").is_err());
+        assert!(Symbology::Code128.encode("tab\there").is_err());
+    }
+
+    fn widths(pattern: &str) -> Vec<bool> {
+        let mut modules = Vec::new();
+        push_widths(&mut modules, pattern).unwrap();
+        modules
+    }
+}
@@ -0,0 +1,174 @@
+use crate::db::models::label::LabelTemplate;
+use crate::printing::barcode::Symbology;
+use crate::printing::pdf::{self, Font, PdfPage};
+
+const PADDING_MM: f64 = 2.0;
+const NAME_SIZE_PT: f64 = 8.0;
+const PRICE_SIZE_PT: f64 = 8.0;
+const CODE_SIZE_PT: f64 = 7.0;
+// Blank modules required on each side of the bars
+const QUIET_ZONE_MODULES: f64 = 10.0;
+const MAX_MODULE_MM: f64 = 0.33;
+
+pub struct Label {
+    pub name: String,
+    pub price: String,
+    pub code: String,
+    pub symbology: Symbology,
+}
+
+// Something drawn on a sheet, positioned in millimetres from the top-left corner
+enum Mark {
+    Bar { x: f64, y: f64, width: f64, height: f64 },
+    Text { x: f64, y: f64, size_pt: f64, bold: bool, text: String },
+}
+
+fn pt_to_mm(size_pt: f64) -> f64 {
+    size_pt * 25.4 / 72.0
+}
+
+fn fit_text(text: &str, size_pt: f64, width_mm: f64) -> String {
+    if pdf::text_width_mm(text, size_pt) <= width_mm {
+        return text.to_string();
+    }
+
+    let mut fitted: String = text.to_string();
+    while !fitted.is_empty() && pdf::text_width_mm(&format!("{}...", fitted), size_pt) > width_mm {
+        fitted.pop();
+    }
+    format!("{}...", fitted.trim_end())
+}
+
+fn centred_text(marks: &mut Vec<Mark>, left: f64, width: f64, y: f64, size_pt: f64, bold: bool, text: String) {
+    let x = left + (width - pdf::text_width_mm(&text, size_pt)).max(0.0) / 2.0;
+    marks.push(Mark::Text { x, y, size_pt, bold, text });
+}
+
+fn layout_label(marks: &mut Vec<Mark>, template: &LabelTemplate, x: f64, y: f64, label: &Label) -> Result<(), String> {
+    let modules = label.symbology.encode(&label.code)?;
+
+    let inner_width = template.label_width_mm - PADDING_MM * 2.0;
+    let left = x + PADDING_MM;
+
+    let name_baseline = y + PADDING_MM + pt_to_mm(NAME_SIZE_PT);
+    let price_baseline = name_baseline + pt_to_mm(PRICE_SIZE_PT) + 0.8;
+    let code_baseline = y + template.label_height_mm - PADDING_MM;
+    let bars_top = price_baseline + 1.5;
+    let bars_height = code_baseline - pt_to_mm(CODE_SIZE_PT) - 0.5 - bars_top;
+
+    if bars_height <= 0.0 || inner_width <= 0.0 {
+        return Err("Labels are too small to fit a barcode".to_string());
+    }
+
+    centred_text(marks, left, inner_width, name_baseline, NAME_SIZE_PT, true, fit_text(&label.name, NAME_SIZE_PT, inner_width));
+    centred_text(marks, left, inner_width, price_baseline, PRICE_SIZE_PT, false, label.price.clone());
+
+    let module = (inner_width / (modules.len() as f64 + QUIET_ZONE_MODULES * 2.0)).min(MAX_MODULE_MM);
+    let bars_left = left + (inner_width - module * modules.len() as f64) / 2.0;
+
+    // Merge runs of adjacent bar modules into single rectangles
+    let mut i = 0;
+    while i < modules.len() {
+        if !modules[i] {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < modules.len() && modules[i] {
+            i += 1;
+        }
+        marks.push(Mark::Bar {
+            x: bars_left + start as f64 * module,
+            y: bars_top,
+            width: (i - start) as f64 * module,
+            height: bars_height,
+        });
+    }
+
+    centred_text(marks, left, inner_width, code_baseline, CODE_SIZE_PT, false, label.code.clone());
+    Ok(())
+}
+
+// Place the labels on as many sheets as needed, filling each row left to right
+fn layout(template: &LabelTemplate, labels: &[Label]) -> Result<Vec<Vec<Mark>>, String> {
+    let per_page = (template.columns * template.rows) as usize;
+    if per_page == 0 {
+        return Err("Label template needs at least one row and column".to_string());
+    }
+
+    let mut pages = Vec::new();
+    for chunk in labels.chunks(per_page) {
+        let mut marks = Vec::new();
+        for (i, label) in chunk.iter().enumerate() {
+            let column = (i % template.columns as usize) as f64;
+            let row = (i / template.columns as usize) as f64;
+            let x = template.margin_left_mm + column * (template.label_width_mm + template.gap_x_mm);
+            let y = template.margin_top_mm + row * (template.label_height_mm + template.gap_y_mm);
+            layout_label(&mut marks, template, x, y, label)?;
+        }
+        pages.push(marks);
+    }
+
+    Ok(pages)
+}
+
+pub fn render_pdf(template: &LabelTemplate, labels: &[Label]) -> Result<(Vec<u8>, usize), String> {
+    let pages: Vec<PdfPage> = layout(template, labels)?
+        .into_iter()
+        .map(|marks| {
+            let mut page = PdfPage::new(template.page_width_mm, template.page_height_mm);
+            for mark in marks {
+                match mark {
+                    Mark::Bar { x, y, width, height } => page.rect(x, y, width, height),
+                    Mark::Text { x, y, size_pt, bold, text } => {
+                        page.text(x, y, size_pt, if bold { Font::HelveticaBold } else { Font::Helvetica }, &text)
+                    }
+                }
+            }
+            page
+        })
+        .collect();
+
+    let count = pages.len();
+    Ok((pdf::render(&pages), count))
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// One SVG document per sheet
+pub fn render_svg(template: &LabelTemplate, labels: &[Label]) -> Result<Vec<String>, String> {
+    Ok(layout(template, labels)?
+        .into_iter()
+        .map(|marks| {
+            let mut svg = format!(
+                "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}mm\" height=\"{h}mm\" viewBox=\"0 0 {w} {h}\">\n\
+                 <rect width=\"100%\" height=\"100%\" fill=\"white\"/>\n",
+                w = template.page_width_mm,
+                h = template.page_height_mm
+            );
+            for mark in marks {
+                match mark {
+                    Mark::Bar { x, y, width, height } => svg.push_str(&format!(
+                        "<rect x=\"{:.3}\" y=\"{:.3}\" width=\"{:.3}\" height=\"{:.3}\" fill=\"black\"/>\n",
+                        x, y, width, height
+                    )),
+                    Mark::Text { x, y, size_pt, bold, text } => svg.push_str(&format!(
+                        "<text x=\"{:.3}\" y=\"{:.3}\" font-family=\"Helvetica, Arial, sans-serif\" font-size=\"{:.3}\"{}>{}</text>\n",
+                        x,
+                        y,
+                        pt_to_mm(size_pt),
+                        if bold { " font-weight=\"bold\"" } else { "" },
+                        escape_xml(&text)
+                    )),
+                }
+            }
+            svg.push_str("</svg>\n");
+            svg
+        })
+        .collect())
+}
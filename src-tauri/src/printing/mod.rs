@@ -0,0 +1,5 @@
+// Output formats for things the shop prints: barcodes, label sheets, receipts and PDF files
+pub mod barcode;
+pub mod labels;
+pub mod pdf;
+pub mod receipt;
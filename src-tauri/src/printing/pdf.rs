@@ -0,0 +1,145 @@
+// A minimal PDF writer: filled rectangles and standard-font text, enough for labels and receipts.
+// Coordinates are in millimetres from the top-left corner of the page.
+
+const POINTS_PER_MM: f64 = 72.0 / 25.4;
+
+#[derive(Debug, Clone, Copy)]
+pub enum Font {
+    Helvetica,
+    HelveticaBold,
+    Courier,
+    CourierBold,
+}
+
+impl Font {
+    fn resource(self) -> &'static str {
+        match self {
+            Font::Helvetica => "F1",
+            Font::HelveticaBold => "F2",
+            Font::Courier => "F3",
+            Font::CourierBold => "F4",
+        }
+    }
+}
+
+// Resource name and base font of every font a page can use
+const FONTS: [(&str, &str); 4] = [
+    ("F1", "Helvetica"),
+    ("F2", "Helvetica-Bold"),
+    ("F3", "Courier"),
+    ("F4", "Courier-Bold"),
+];
+
+pub struct PdfPage {
+    width_mm: f64,
+    height_mm: f64,
+    content: String,
+}
+
+impl PdfPage {
+    pub fn new(width_mm: f64, height_mm: f64) -> PdfPage {
+        PdfPage {
+            width_mm,
+            height_mm,
+            content: String::new(),
+        }
+    }
+
+    pub fn rect(&mut self, x: f64, y: f64, width: f64, height: f64) {
+        self.content.push_str(&format!(
+            "{:.3} {:.3} {:.3} {:.3} re f\n",
+            x * POINTS_PER_MM,
+            (self.height_mm - y - height) * POINTS_PER_MM,
+            width * POINTS_PER_MM,
+            height * POINTS_PER_MM
+        ));
+    }
+
+    /// Draw a line of text whose baseline is at `y`
+    pub fn text(&mut self, x: f64, y: f64, size_pt: f64, font: Font, text: &str) {
+        self.content.push_str(&format!(
+            "BT /{} {:.2} Tf {:.3} {:.3} Td ({}) Tj ET\n",
+            font.resource(),
+            size_pt,
+            x * POINTS_PER_MM,
+            (self.height_mm - y) * POINTS_PER_MM,
+            escape(text)
+        ));
+    }
+}
+
+// The standard fonts only cover Latin-1, anything else is replaced
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | '(' | ')' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            ' '..='~' => escaped.push(c),
+            _ if (c as u32) < 256 => escaped.push_str(&format!("\\{:03o}", c as u32)),
+            _ => escaped.push('?'),
+        }
+    }
+    escaped
+}
+
+/// Approximate width in millimetres of Helvetica text, used to fit and centre lines
+pub fn text_width_mm(text: &str, size_pt: f64) -> f64 {
+    text.chars().count() as f64 * size_pt * 0.55 / POINTS_PER_MM
+}
+
+/// Serialise the pages into a complete PDF file
+pub fn render(pages: &[PdfPage]) -> Vec<u8> {
+    // Objects 1 and 2 are the catalog and page tree, followed by the fonts; each page
+    // then adds a page object and its content stream
+    let mut objects: Vec<String> = Vec::new();
+    let first_page = 3 + FONTS.len();
+
+    let kids: Vec<String> = (0..pages.len()).map(|i| format!("{} 0 R", first_page + i * 2)).collect();
+    objects.push("<< /Type /Catalog /Pages 2 0 R >>".to_string());
+    objects.push(format!("<< /Type /Pages /Kids [{}] /Count {} >>", kids.join(" "), pages.len()));
+
+    let mut font_resources = Vec::new();
+    for (i, (name, base_font)) in FONTS.iter().enumerate() {
+        objects.push(format!(
+            "<< /Type /Font /Subtype /Type1 /BaseFont /{} /Encoding /WinAnsiEncoding >>",
+            base_font
+        ));
+        font_resources.push(format!("/{} {} 0 R", name, 3 + i));
+    }
+
+    for (i, page) in pages.iter().enumerate() {
+        objects.push(format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {:.3} {:.3}] /Resources << /Font << {} >> >> /Contents {} 0 R >>",
+            page.width_mm * POINTS_PER_MM,
+            page.height_mm * POINTS_PER_MM,
+            font_resources.join(" "),
+            first_page + i * 2 + 1
+        ));
+        objects.push(format!(
+            "<< /Length {} >>\nstream\n{}endstream",
+            page.content.len(),
+            page.content
+        ));
+    }
+
+    let mut out = b"%PDF-1.4\n".to_vec();
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(out.len());
+        out.extend_from_slice(format!("{} 0 obj\n{}\nendobj\n", i + 1, object).as_bytes());
+    }
+
+    let xref = out.len();
+    out.extend_from_slice(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).as_bytes());
+    for offset in offsets {
+        out.extend_from_slice(format!("{:010} 00000 n \n", offset).as_bytes());
+    }
+    out.extend_from_slice(
+        format!("trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n", objects.len() + 1, xref).as_bytes(),
+    );
+
+    out
+}
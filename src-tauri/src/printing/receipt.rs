@@ -0,0 +1,218 @@
+use crate::db::models::order::OrderWithItems;
+use crate::db::models::payment::{ACCOUNT, CARD, CASH, GCASH, WALLET};
+use crate::db::models::receipt::ReceiptSettings;
+use crate::printing::pdf::{self, Font, PdfPage};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Align {
+    Left,
+    Center,
+}
+
+// One printed line of a receipt, independent of the output format
+pub enum ReceiptLine {
+    Text { text: String, align: Align, bold: bool, large: bool },
+    // Left text with an amount flush right
+    Pair { left: String, right: String, bold: bool },
+    Rule,
+    Blank,
+}
+
+fn text(text: impl Into<String>, align: Align) -> ReceiptLine {
+    ReceiptLine::Text { text: text.into(), align, bold: false, large: false }
+}
+
+fn pair(left: impl Into<String>, right: impl Into<String>) -> ReceiptLine {
+    ReceiptLine::Pair { left: left.into(), right: right.into(), bold: false }
+}
+
+fn quantity(value: f64) -> String {
+    if value.fract() == 0.0 {
+        format!("{}", value as i64)
+    } else {
+        format!("{}", value)
+    }
+}
+
+fn payment_method_label(method: &str) -> String {
+    match method {
+        CASH => "Cash".to_string(),
+        GCASH => "GCash".to_string(),
+        CARD => "Card".to_string(),
+        ACCOUNT => "Charge to account".to_string(),
+        WALLET => "Prepaid card".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// Lay out a receipt for an order from the store's template settings
+pub fn build(settings: &ReceiptSettings, order: &OrderWithItems, reprint: bool) -> Vec<ReceiptLine> {
+    let mut lines = vec![ReceiptLine::Text {
+        text: settings.store_name.clone(),
+        align: Align::Center,
+        bold: true,
+        large: true,
+    }];
+
+    for line in settings.header.as_deref().unwrap_or("").lines() {
+        lines.push(text(line, Align::Center));
+    }
+
+    lines.push(ReceiptLine::Rule);
+    if reprint {
+        lines.push(ReceiptLine::Text { text: "*** REPRINT ***".to_string(), align: Align::Center, bold: true, large: false });
+    }
+    lines.push(pair("Order", order.order.order_id.clone()));
+    lines.push(pair("Date", order.order.created_at.clone()));
+    lines.push(pair("Cashier", order.order.cashier.clone()));
+    lines.push(ReceiptLine::Rule);
+
+    for item in &order.items {
+        let mut name = item.product_name.clone().unwrap_or_else(|| "Item".to_string());
+        if let Some(variant) = &item.variant_name {
+            name = format!("{} ({})", name, variant);
+        }
+        lines.push(text(name, Align::Left));
+        for modifier in &item.modifiers {
+            lines.push(text(format!("  + {}", modifier.modifier_name), Align::Left));
+        }
+        lines.push(pair(
+            format!("  {} x {:.2}", quantity(item.quantity), item.price),
+            format!("{:.2}", item.price * item.quantity),
+        ));
+        for discount in order.discounts.iter().filter(|d| d.order_item_id == Some(item.id)) {
+            lines.push(pair(format!("  {}", discount.description), format!("-{:.2}", discount.amount)));
+        }
+    }
+
+    lines.push(ReceiptLine::Rule);
+    if order.order.discount > 0.0 {
+        lines.push(pair("Discounts", format!("-{:.2}", order.order.discount)));
+    }
+    lines.push(pair("Subtotal", format!("{:.2}", order.order.subtotal)));
+    lines.push(pair("Tax", format!("{:.2}", order.order.tax)));
+    lines.push(ReceiptLine::Pair {
+        left: "TOTAL".to_string(),
+        right: format!("PHP {:.2}", order.order.total),
+        bold: true,
+    });
+
+    if !order.payments.is_empty() {
+        lines.push(ReceiptLine::Blank);
+        for payment in &order.payments {
+            lines.push(pair(payment_method_label(&payment.method), format!("{:.2}", payment.amount)));
+            if let Some(reference) = &payment.reference {
+                if payment.method == WALLET {
+                    // Only the end of a card's code goes on the receipt
+                    let tail: String = reference.chars().skip(reference.chars().count().saturating_sub(4)).collect();
+                    lines.push(pair("  Card", format!("****{}", tail)));
+                } else {
+                    lines.push(pair("  Ref", reference.clone()));
+                }
+            }
+            if let Some(tendered) = payment.tendered {
+                lines.push(pair("  Tendered", format!("{:.2}", tendered)));
+                lines.push(pair("  Change", format!("{:.2}", payment.change)));
+            }
+        }
+    }
+
+    lines.push(ReceiptLine::Rule);
+    for line in settings.footer.as_deref().unwrap_or("Thank you!").lines() {
+        lines.push(text(line, Align::Center));
+    }
+    lines.push(ReceiptLine::Blank);
+
+    lines
+}
+
+// Receipt printers take single-byte text; anything outside ASCII is replaced
+fn printable(text: &str) -> String {
+    text.chars().map(|c| if (' '..='~').contains(&c) { c } else { '?' }).collect()
+}
+
+fn truncate(text: &str, width: usize) -> String {
+    text.chars().take(width).collect()
+}
+
+// The line as it appears on a fixed-width printer, without alignment for centred text
+fn format_line(line: &ReceiptLine, width: usize) -> String {
+    match line {
+        ReceiptLine::Text { text, large, .. } => truncate(text, if *large { width / 2 } else { width }),
+        ReceiptLine::Pair { left, right, .. } => {
+            let right = truncate(right, width);
+            let left = truncate(left, width.saturating_sub(right.chars().count() + 1));
+            let padding = width - left.chars().count() - right.chars().count();
+            format!("{}{}{}", left, " ".repeat(padding), right)
+        }
+        ReceiptLine::Rule => "-".repeat(width),
+        ReceiptLine::Blank => String::new(),
+    }
+}
+
+const ESC: u8 = 0x1b;
+const GS: u8 = 0x1d;
+
+/// Encode the receipt as ESC/POS commands for a thermal printer
+pub fn to_escpos(lines: &[ReceiptLine], width: usize, cut_paper: bool, open_drawer: bool) -> Vec<u8> {
+    let mut out = vec![ESC, b'@'];
+
+    for line in lines {
+        let (align, bold, large) = match line {
+            ReceiptLine::Text { align, bold, large, .. } => (*align, *bold, *large),
+            ReceiptLine::Pair { bold, .. } => (Align::Left, *bold, false),
+            _ => (Align::Left, false, false),
+        };
+
+        out.extend_from_slice(&[ESC, b'a', if align == Align::Center { 1 } else { 0 }]);
+        out.extend_from_slice(&[ESC, b'E', bold as u8]);
+        out.extend_from_slice(&[GS, b'!', if large { 0x11 } else { 0 }]);
+        out.extend_from_slice(printable(&format_line(line, width)).as_bytes());
+        out.push(b'\n');
+    }
+
+    out.extend_from_slice(&[ESC, b'E', 0, GS, b'!', 0, ESC, b'a', 0]);
+    if cut_paper {
+        // Feed past the cutter, then partial cut
+        out.extend_from_slice(&[GS, b'V', 66, 3]);
+    }
+    if open_drawer {
+        out.extend_from_slice(&[ESC, b'p', 0, 25, 250]);
+    }
+
+    out
+}
+
+/// Render the receipt as a single-page PDF sized like the paper roll
+pub fn to_pdf(lines: &[ReceiptLine], width: usize) -> Vec<u8> {
+    let page_width_mm = if width <= 32 { 58.0 } else { 80.0 };
+    let margin_mm = 4.0;
+
+    // Courier glyphs are 0.6 em wide; pick the size that fits `width` characters
+    let size_pt = ((page_width_mm - margin_mm * 2.0) / (width as f64 * 0.6 * 25.4 / 72.0)).min(10.0);
+    let line_height_mm = size_pt * 1.3 * 25.4 / 72.0;
+    let page_height_mm = margin_mm * 2.0 + line_height_mm * (lines.len() as f64 + 1.0);
+
+    let mut page = PdfPage::new(page_width_mm, page_height_mm);
+    for (i, line) in lines.iter().enumerate() {
+        let baseline = margin_mm + line_height_mm * (i as f64 + 1.0);
+        let (align, bold, large) = match line {
+            ReceiptLine::Text { align, bold, large, .. } => (*align, *bold, *large),
+            ReceiptLine::Pair { bold, .. } => (Align::Left, *bold, false),
+            _ => (Align::Left, false, false),
+        };
+
+        let formatted = format_line(line, width);
+        let line_size = if large { size_pt * 1.4 } else { size_pt };
+        let line_width = formatted.chars().count() as f64 * line_size * 0.6 * 25.4 / 72.0;
+        let x = if align == Align::Center {
+            (page_width_mm - line_width) / 2.0
+        } else {
+            margin_mm
+        };
+
+        page.text(x, baseline, line_size, if bold { Font::CourierBold } else { Font::Courier }, &formatted);
+    }
+
+    pdf::render(&[page])
+}
@@ -11,6 +11,7 @@ import { Button } from "./ui/button";
 import { Input } from "./ui/input";
 import { Textarea } from "./ui/textarea";
 import { useCategories, NewCategory } from "../hooks/useCategories";
+import { useAuth } from "../context/AuthContext";
 import { invoke } from "@tauri-apps/api/core";
 import { open as openDialog } from "@tauri-apps/plugin-dialog";
 import { formatFilePath } from "../utils/fileUtils";
@@ -31,6 +32,7 @@ export default function AddCategoryDialog({
   const [imageUrl, setImageUrl] = useState<string | null>(null);
   const [description, setDescription] = useState("");
   const { addCategory } = useCategories();
+  const { token } = useAuth();
 
   const handleSubmit = async (e: React.FormEvent) => {
     e.preventDefault();
@@ -45,9 +47,12 @@ export default function AddCategoryDialog({
     try {
       // Invoke the backend command to add a new category
       await invoke("add_category", {
-        name: categoryName,
-        description: description || null,
-        icon: imageUrl || null,
+        category: {
+          name: categoryName,
+          description: description || null,
+          icon: imageUrl || null,
+        },
+        token,
       });
 
       // Clear inputs and close dialog
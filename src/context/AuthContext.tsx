@@ -38,6 +38,7 @@ interface RegisterData {
   email: string;
   password: string;
   full_name: string;
+  role: string;
 }
 
 interface AuthProviderProps {
@@ -385,16 +386,11 @@ const AuthProvider = ({ children }: AuthProviderProps) => {
 
   const register = async (userData: RegisterData) => {
     try {
-      const response = await invoke<AuthResponse>("register", {
-        request: userData,
+      // Accounts are created by a signed-in staff manager, whose session stays active
+      await invoke<User>("register", {
+        token,
+        newUser: userData,
       });
-
-      setUser(response.user);
-      setToken(response.token);
-
-      // Store auth data
-      localStorage.setItem("auth_token", response.token);
-      localStorage.setItem("auth_user", JSON.stringify(response.user));
     } catch (error) {
       throw new Error(error as string);
     }
@@ -1,6 +1,7 @@
 import { invoke } from "@tauri-apps/api/core";
 import { useQuery, useMutation, useQueryClient } from "@tanstack/react-query";
 import { toast } from "sonner";
+import { useAuth } from "../context/AuthContext";
 
 export interface Category {
   id: number;
@@ -98,6 +99,7 @@ function debounce<T extends (...args: any[]) => any>(
 export function useCategories() {
   console.log("useCategories hook called");
   const queryClient = useQueryClient();
+  const { token } = useAuth();
 
   // Use real database data with better error handling
   const {
@@ -136,6 +138,7 @@ export function useCategories() {
       await new Promise((resolve) => setTimeout(resolve, 50));
       return safeTauriInvoke<Category>("add_category", {
         category: newCategory,
+        token,
       });
     },
     onMutate: async (newCategory) => {
@@ -205,6 +208,7 @@ export function useCategories() {
       await new Promise((resolve) => setTimeout(resolve, 50));
       return safeTauriInvoke<Category>("update_category", {
         category: updatedCategory,
+        token,
       });
     },
     onMutate: async (updatedCategory) => {
@@ -274,7 +278,7 @@ export function useCategories() {
     mutationFn: async (id: number) => {
       // Delay execution slightly to allow UI to settle
       await new Promise((resolve) => setTimeout(resolve, 50));
-      return safeTauriInvoke<void>("delete_category", { id });
+      return safeTauriInvoke<void>("delete_category", { id, token });
     },
     onMutate: async (id) => {
       // Cancel any outgoing refetches
@@ -2,6 +2,7 @@ import { invoke } from "@tauri-apps/api/core";
 import { useQuery, useMutation } from "@tanstack/react-query";
 // Temporarily disable toast notifications
 import { toast } from "sonner";
+import { useAuth } from "../context/AuthContext";
 
 export interface OrderItem {
   id: number;
@@ -127,6 +128,8 @@ async function safeTauriInvoke<T>(
 }
 
 export function useOrders() {
+  const { token } = useAuth();
+
   // Remove the unused queryClient variable
   // const queryClient = useQueryClient();
 
@@ -158,6 +161,7 @@ export function useOrders() {
         try {
           return await safeTauriInvoke<Order[]>("get_order_history", {
             request,
+            token,
           });
         } catch (error) {
           console.error("Failed to fetch order history:", error);
@@ -179,6 +183,7 @@ export function useOrders() {
             {
               start_date: startDate,
               end_date: endDate,
+              token,
             }
           );
         } catch (error) {
@@ -212,7 +217,7 @@ export function useOrders() {
   // Create new order
   const createOrder = useMutation({
     mutationFn: async (request: CreateOrderRequest) => {
-      return safeTauriInvoke<Order>("create_order", { request });
+      return safeTauriInvoke<Order>("create_order", { request, token });
     },
     onSuccess: () => {
       // Refetch orders after successful creation
@@ -238,7 +243,7 @@ export function useOrders() {
           });
 
           // Build args object, only include dates if defined
-          const args: Record<string, unknown> = { period };
+          const args: Record<string, unknown> = { period, token };
           if (startDate !== undefined) args.startDate = startDate;
           if (endDate !== undefined) args.endDate = endDate;
 
@@ -1,6 +1,7 @@
 import { invoke } from "@tauri-apps/api/core";
 import { useQuery, useMutation, useQueryClient } from "@tanstack/react-query";
 import { toast } from "sonner";
+import { useAuth } from "../context/AuthContext";
 
 // Add debug logs
 console.log("Loading useProducts module");
@@ -156,6 +157,7 @@ function debounce<T extends (...args: any[]) => any>(
 export function useProducts(categoryId?: number) {
   console.log("useProducts hook called with categoryId:", categoryId);
   const queryClient = useQueryClient();
+  const { token } = useAuth();
 
   // Use real database data with better error handling
   const {
@@ -216,7 +218,10 @@ export function useProducts(categoryId?: number) {
     mutationFn: async (newProduct: NewProduct) => {
       // Delay execution slightly to allow UI to settle
       await new Promise((resolve) => setTimeout(resolve, 50));
-      return safeTauriInvoke<Product>("add_product", { product: newProduct });
+      return safeTauriInvoke<Product>("add_product", {
+        product: newProduct,
+        token,
+      });
     },
     onMutate: async (newProduct) => {
       // Cancel any outgoing refetches
@@ -288,7 +293,7 @@ export function useProducts(categoryId?: number) {
     mutationFn: async (id: number) => {
       // Delay execution slightly to allow UI to settle
       await new Promise((resolve) => setTimeout(resolve, 50));
-      return safeTauriInvoke<void>("delete_product", { id });
+      return safeTauriInvoke<void>("delete_product", { id, token });
     },
     onMutate: async (id) => {
       // Cancel any outgoing refetches
@@ -355,6 +360,7 @@ export function useProducts(categoryId?: number) {
       return safeTauriInvoke<void>("update_product_stock", {
         id,
         new_stock: newStock,
+        token,
       });
     },
     onMutate: async ({ id, newStock }) => {
@@ -416,6 +422,7 @@ export function useProducts(categoryId?: number) {
       await new Promise((resolve) => setTimeout(resolve, 50));
       return safeTauriInvoke<Product>("update_product", {
         product: updatedProduct,
+        token,
       });
     },
     onMutate: async (updatedProduct) => {